@@ -1,16 +1,15 @@
 use junocam::print;
 mod subs;
+use anyhow::Result;
 use subs::runnable::RunnableSubcommand;
 use subs::*;
-use anyhow::Result;
 extern crate wild;
-use colored::Colorize;
 use clap::{Parser, Subcommand};
+use colored::Colorize;
 
 #[macro_use]
 extern crate stump;
 
-
 #[derive(Parser)]
 #[clap(name = "juno")]
 #[clap(about = "JunoCam raw processing", long_about = None)]
@@ -23,6 +22,7 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Juno {
     TripletCount(tripcount::TripletCount),
     Infill(infill::Infill),
@@ -31,11 +31,12 @@ enum Juno {
     Hpc(hpc::Hpc),
     Weights(weights::Weights),
     Process(process::Process),
+    Mosaic(mosaic::Mosaic),
     CenterOfMass(centerofmass::CenterOfMass),
 }
 
 #[tokio::main]
-async fn main() -> Result<(), anyhow::Error>  {
+async fn main() -> Result<(), anyhow::Error> {
     let t1 = std::time::Instant::now();
 
     let args = Cli::parse_from(wild::args());
@@ -50,33 +51,20 @@ async fn main() -> Result<(), anyhow::Error>  {
     }
 
     if let Err(why) = match args.command {
-        Juno::TripletCount(args) => {
-            args.run().await
-        }
-        Juno::Infill(args) => {
-            args.run().await
-        }
-        Juno::Decompand(args) => {
-            args.run().await
-        }
-        Juno::Calibrate(args) => {
-            args.run().await
-        }
-        Juno::Hpc(args) => {
-            args.run().await
-        }
-        Juno::Weights(args) => {
-            args.run().await
-        }
-        Juno::Process(args) => {
-            args.run().await
-        }
-        Juno::CenterOfMass(args) => {
-            args.run().await
-        }
+        Juno::TripletCount(args) => args.run().await,
+        Juno::Infill(args) => args.run().await,
+        Juno::Decompand(args) => args.run().await,
+        Juno::Calibrate(args) => args.run().await,
+        Juno::Hpc(args) => args.run().await,
+        Juno::Weights(args) => args.run().await,
+        Juno::Process(args) => args.run().await,
+        Juno::Mosaic(args) => args.run().await,
+        Juno::CenterOfMass(args) => args.run().await,
     } {
         error!("{}", "Unhandled program error:".red());
         error!("{}", why);
+        info!("Runtime: {}s", t1.elapsed().as_secs_f64());
+        std::process::exit(1);
     };
 
     info!("Runtime: {}s", t1.elapsed().as_secs_f64());
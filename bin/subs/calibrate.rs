@@ -1,9 +1,9 @@
 use crate::subs::runnable::RunnableSubcommand;
+use anyhow::{anyhow, Result};
+use junocam::output::{self, OutputFormat};
 use junocam::rawimage;
-use anyhow::Result;
 use junocam::vprintln;
 use sciimg::path;
-use std::process;
 
 #[derive(clap::Args)]
 #[clap(author, version, about = "Calibration (dark, flat)", long_about = None)]
@@ -13,28 +13,32 @@ pub struct Calibrate {
 
     #[clap(long, short, help = "Output image")]
     output: String,
+
+    #[clap(
+        long,
+        help = "Output format (png, tiff, fits). Float formats are unstretched"
+    )]
+    format: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl RunnableSubcommand for Calibrate {
     async fn run(&self) -> Result<()> {
         if !path::file_exists(&self.input) {
-            eprintln!("ERROR: Input file not found!");
-            process::exit(1);
+            return Err(anyhow!("Input file not found: {}", self.input));
         }
 
+        let output_format =
+            OutputFormat::parse_arg(self.format.as_ref())?.unwrap_or(OutputFormat::Stretched);
+
         vprintln!("Loading image file from {}", self.input);
-        let mut raw_image = rawimage::RawImage::new_from_image(&self.input).unwrap();
+        let mut raw_image = rawimage::RawImage::new_from_image(&self.input)?;
 
         vprintln!("Running calibration process...");
-        raw_image
-            .apply_darknoise()
-            .expect("Error with dark/flat field correction");
+        raw_image.apply_darknoise()?;
 
         vprintln!("Saving image to {}", self.output);
-        let mut assembled_final = raw_image.assemble();
-        assembled_final.normalize_mut(0.0, 65535.0);
-        assembled_final.save(&self.output)?;
+        output::save_buffer(&raw_image.assemble()?, &self.output, output_format)?;
         Ok(())
     }
 }
@@ -1,12 +1,10 @@
 use crate::subs::runnable::RunnableSubcommand;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use junocam::vprintln;
 use rayon::prelude::*;
 use sciimg::prelude::*;
 use sciimg::util;
 
-use std::process;
-
 #[derive(clap::Args)]
 #[clap(author, version, about = "Center of mass centering", long_about = None)]
 pub struct CenterOfMass {
@@ -28,22 +26,42 @@ impl RunnableSubcommand for CenterOfMass {
     async fn run(&self) -> Result<()> {
         let threshold = self.threshold.unwrap_or(100.0);
 
-        self.inputs.par_iter().for_each(|file_path| {
-            if !path::file_exists(file_path) {
-                eprintln!("ERROR: Input file not found: {}", file_path);
-                process::exit(1);
-            }
-            vprintln!("Loading image file from {}", file_path);
-
-            let mut img = Image::open(file_path).unwrap();
-
-            let offset = img.calc_center_of_mass_offset(threshold, 0);
-            img.shift(offset.h, offset.v);
+        let failures: Vec<&String> = self
+            .inputs
+            .par_iter()
+            .filter(|file_path| match center_image(file_path, threshold) {
+                Ok(_) => false,
+                Err(why) => {
+                    eprintln!("Error: {}: {}", file_path, why);
+                    true
+                }
+            })
+            .collect();
 
-            let output_filename = util::replace_image_extension(file_path, "-com.png");
-            img.save(&output_filename).expect("Failed to save image");
-        });
+        if !failures.is_empty() {
+            return Err(anyhow!(
+                "{} of {} images failed",
+                failures.len(),
+                self.inputs.len()
+            ));
+        }
 
         Ok(())
     }
 }
+
+fn center_image(file_path: &str, threshold: f32) -> Result<()> {
+    if !path::file_exists(file_path) {
+        return Err(anyhow!("Input file not found"));
+    }
+    vprintln!("Loading image file from {}", file_path);
+
+    let mut img = Image::open(file_path)?;
+
+    let offset = img.calc_center_of_mass_offset(threshold, 0);
+    img.shift(offset.h, offset.v);
+
+    let output_filename = util::replace_image_extension(file_path, "-com.png");
+    img.save(&output_filename)?;
+    Ok(())
+}
@@ -144,12 +144,12 @@ macro_rules! pb_done_with_error {
     };
 }
 
-
 pub mod calibrate;
 pub mod centerofmass;
 pub mod decompand;
 pub mod hpc;
 pub mod infill;
+pub mod mosaic;
 pub mod process;
 pub mod tripcount;
 pub mod weights;
@@ -0,0 +1,333 @@
+use crate::subs::runnable::RunnableSubcommand;
+use anyhow::{anyhow, Result};
+use junocam::{
+    config,
+    interpolate::Interpolation,
+    mosaic::mosaic_images,
+    output::{self, OutputFormat, Stretch},
+    pairing,
+    photometry::PhotometricModel,
+    process::{normalize_output, save_georeference, ProcessOptions, SupportedLens},
+    projection::{polar::Pole, projection::LatLonBounds},
+    render::Blending,
+    vprintln,
+};
+use sciimg::path;
+use sciimg::prelude::*;
+
+#[derive(clap::Args)]
+#[clap(author, version, about = "Mosaic JunoCam images onto a shared map", long_about = None)]
+pub struct Mosaic {
+    #[clap(
+        long,
+        short,
+        help = "Input images, directories or glob patterns",
+        multiple_values = true
+    )]
+    inputs: Vec<String>,
+
+    #[clap(
+        long,
+        short,
+        help = "Input metadata. Paired with the inputs by ID if left off",
+        multiple_values = true
+    )]
+    metadata: Vec<String>,
+
+    #[clap(long, short, help = "Output image")]
+    output: String,
+
+    #[clap(long, short = 'R', help = "Red weight")]
+    red_weight: Option<f32>,
+
+    #[clap(long, short = 'G', help = "Green weight")]
+    green_weight: Option<f32>,
+
+    #[clap(long, short = 'B', help = "Blue weight")]
+    blue_weight: Option<f32>,
+
+    #[clap(long, short, help = "Use predicted kernels")]
+    predicted: bool,
+
+    #[clap(long, short, help = "Output width")]
+    width: Option<usize>,
+
+    #[clap(long, short = 'H', help = "Output height")]
+    height: Option<usize>,
+
+    #[clap(
+        long,
+        short,
+        help = "Map projection (equirectangular, orthographic, stereographic, lambert)"
+    )]
+    lens: Option<String>,
+
+    #[clap(long, short = 'd', help = "Perform decorrelated color stretch")]
+    decorrelated_color_stretch: bool,
+
+    #[clap(long, help = "Interpolation method (nearest, bilinear, bicubic)")]
+    interpolation: Option<String>,
+
+    #[clap(long, short = 'b', help = "Blending method (emission, edge, none)")]
+    blending: Option<String>,
+
+    #[clap(
+        long,
+        help = "Use planetographic latitude and west longitude for surface projections"
+    )]
+    planetographic: bool,
+
+    #[clap(
+        long,
+        help = "Orthographic sub-observer latitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    sub_lat: Option<f64>,
+
+    #[clap(
+        long,
+        help = "Orthographic sub-observer longitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    sub_lon: Option<f64>,
+
+    #[clap(long, help = "Map scale, in kilometers per pixel")]
+    scale: Option<f64>,
+
+    #[clap(long, help = "Polar projection pole (north, south)")]
+    pole: Option<String>,
+
+    #[clap(
+        long,
+        help = "Polar projection bounding latitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    bounding_lat: Option<f64>,
+
+    #[clap(
+        long,
+        help = "Cylindrical map bounds, in degrees (top lat, bottom lat, left lon, right lon)",
+        number_of_values = 4,
+        allow_hyphen_values(true)
+    )]
+    bounds: Option<Vec<f64>>,
+
+    #[clap(long, help = "Cylindrical map resolution, in degrees per pixel")]
+    degrees_per_pixel: Option<f64>,
+
+    #[clap(
+        long,
+        help = "Photometric correction (none, minnaert, lommel-seeliger)"
+    )]
+    photometry: Option<String>,
+
+    #[clap(
+        long,
+        help = "Minnaert limb darkening exponents (red, green, blue)",
+        number_of_values = 3
+    )]
+    minnaert_k: Option<Vec<f64>>,
+
+    #[clap(
+        long,
+        help = "Output format (png, tiff, fits). Float formats are unstretched"
+    )]
+    format: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl RunnableSubcommand for Mosaic {
+    async fn run(&self) -> Result<()> {
+        let juno_config = config::load_configuration()?;
+
+        let red_weight = self.red_weight.unwrap_or(juno_config.defaults.red_weight);
+        let green_weight = self
+            .green_weight
+            .unwrap_or(juno_config.defaults.green_weight);
+        let blue_weight = self.blue_weight.unwrap_or(juno_config.defaults.blue_weight);
+
+        let output_width = self.width.unwrap_or(2048);
+        let output_height = self.height.unwrap_or(1024);
+
+        let map_projection = match &self.lens {
+            Some(l) => match SupportedLens::from(l.as_str()) {
+                Some(lens) if lens.is_surface_projection() => lens,
+                _ => {
+                    return Err(anyhow!(
+                        "Invalid map projection requested: {}. Use either 'equirectangular', 'orthographic', 'stereographic', or 'lambert'",
+                        l
+                    ));
+                }
+            },
+            None => SupportedLens::Equirectangular,
+        };
+        vprintln!("Map projection: {:?}", map_projection);
+
+        let interpolation = match &self.interpolation {
+            Some(i) => {
+                if let Some(interp) = Interpolation::from(i.as_str()) {
+                    interp
+                } else {
+                    return Err(anyhow!(
+                        "Invalid interpolation method requested: {}. Use either 'nearest', 'bilinear', or 'bicubic'",
+                        i
+                    ));
+                }
+            }
+            None => Interpolation::Bilinear,
+        };
+        vprintln!("Interpolation method: {:?}", interpolation);
+
+        let minnaert_k = match &self.minnaert_k {
+            Some(k) => [k[0], k[1], k[2]],
+            None => juno_config.defaults.minnaert_k(),
+        };
+
+        let photometric_model = match self
+            .photometry
+            .as_ref()
+            .or(juno_config.defaults.photometric_model.as_ref())
+        {
+            Some(p) => {
+                if let Some(model) = PhotometricModel::from(p.as_str(), minnaert_k) {
+                    model
+                } else {
+                    return Err(anyhow!(
+                        "Invalid photometric model requested: {}. Use either 'none', 'minnaert', or 'lommel-seeliger'",
+                        p
+                    ));
+                }
+            }
+            None => PhotometricModel::None,
+        };
+        vprintln!("Photometric model: {:?}", photometric_model);
+
+        let blending = match &self.blending {
+            Some(b) => {
+                if let Some(blending) = Blending::from(b.as_str()) {
+                    blending
+                } else {
+                    return Err(anyhow!(
+                        "Invalid blending method requested: {}. Use either 'emission', 'edge', or 'none'",
+                        b
+                    ));
+                }
+            }
+            None => Blending::Emission,
+        };
+        vprintln!("Blending method: {:?}", blending);
+
+        let pole = match &self.pole {
+            Some(p) => {
+                if let Some(pole) = Pole::from(p.as_str()) {
+                    Some(pole)
+                } else {
+                    return Err(anyhow!(
+                        "Invalid pole requested: {}. Use either 'north' or 'south'",
+                        p
+                    ));
+                }
+            }
+            None => None,
+        };
+
+        let bounding_lat = self.bounding_lat.unwrap_or(60.0);
+        if bounding_lat.abs() >= 90.0 {
+            return Err(anyhow!("Invalid bounding latitude: {}", bounding_lat));
+        }
+
+        let map_bounds = match &self.bounds {
+            Some(b) => Some(LatLonBounds::new(b[0], b[1], b[2], b[3])),
+            None => juno_config.defaults.map_bounds,
+        };
+
+        let degrees_per_pixel = match self.degrees_per_pixel {
+            Some(d) => Some(d),
+            None => juno_config.defaults.map_degrees_per_pixel,
+        };
+
+        let output_format =
+            OutputFormat::parse_arg(self.format.as_ref())?.unwrap_or(OutputFormat::Stretched);
+
+        let pairing = pairing::pair_inputs(&self.inputs, &self.metadata)?;
+        pairing.orphan_images.iter().for_each(|i| {
+            eprintln!("Warning: No metadata found for {}", i);
+        });
+        pairing.orphan_metadata.iter().for_each(|m| {
+            eprintln!("Warning: No image found for {}", m);
+        });
+        if pairing.pairs.is_empty() {
+            return Err(anyhow!("No input images found"));
+        }
+
+        for pair in pairing.pairs.iter() {
+            if !path::file_exists(&pair.image) {
+                return Err(anyhow!("Input file not found: {}", pair.image));
+            }
+        }
+
+        let images: Vec<ProcessOptions> = pairing
+            .pairs
+            .iter()
+            .map(|p| (&p.image, &p.metadata))
+            .map(|(file_path, metadata)| ProcessOptions {
+                input: file_path.to_string(),
+                metadata: metadata.to_string(),
+                output: None,
+                red_weight,
+                green_weight,
+                blue_weight,
+                predicted: self.predicted,
+                width: output_width,
+                height: output_height,
+                fov: juno_config.defaults.fisheye_field_of_view,
+                aspect_ratio: 1.0,
+                pitch: 0.0,
+                yaw: 0.0,
+                roll: 0.0,
+                lens: map_projection,
+                fast: false,
+                decorrelated_color_stretch: self.decorrelated_color_stretch,
+                interpolation,
+                legacy_renderer: false,
+                planetographic: self.planetographic,
+                sub_observer_lat: self.sub_lat,
+                sub_observer_lon: self.sub_lon,
+                map_scale: self.scale,
+                pole,
+                bounding_lat,
+                map_bounds,
+                degrees_per_pixel,
+                backplanes: false,
+                photometric_model,
+                radiometric: false,
+                output_format: OutputFormat::Stretched,
+                calibrated_fits: None,
+                coverage_mask: false,
+            })
+            .collect();
+
+        let mosaic = mosaic_images(&images, blending)?;
+
+        let mut map = mosaic.to_image()?;
+
+        vprintln!("Writing mosaic to {}", self.output);
+        if output_format.is_float() {
+            output::save_float_image(&map, &self.output, output_format, Stretch::None, None)?;
+        } else {
+            let stretches = normalize_output(
+                &mut map,
+                self.decorrelated_color_stretch && !juno_config.defaults.correlated_color_balancing,
+            );
+            map.save(&self.output)?;
+            output::save_stretch_sidecar(&self.output, &stretches)?;
+        }
+        save_georeference(mosaic.georeference, &self.output)?;
+
+        let coverage_filename = output::replace_extension(&self.output, "-coverage.png");
+        vprintln!("Writing coverage counts to {}", coverage_filename);
+        Image::new_from_buffer_mono(&mosaic.coverage()?)?.save(&coverage_filename)?;
+
+        Ok(())
+    }
+}
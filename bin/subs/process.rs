@@ -1,21 +1,35 @@
 use crate::subs::runnable::RunnableSubcommand;
+use anyhow::{anyhow, Result};
 use junocam::{
     config,
+    error::JunocamError,
+    interpolate::Interpolation,
+    output::OutputFormat,
+    pairing,
+    photometry::PhotometricModel,
     process::{process_image, ProcessOptions, SupportedLens},
+    projection::{polar::Pole, projection::LatLonBounds},
     vprintln,
 };
-use anyhow::Result;
 use sciimg::path;
-use sciimg::util;
-use std::process;
 
 #[derive(clap::Args)]
 #[clap(author, version, about = "Process RGB JunoCam image", long_about = None)]
 pub struct Process {
-    #[clap(long, short, help = "Input images", multiple_values = true)]
+    #[clap(
+        long,
+        short,
+        help = "Input images (png, -ImageSet.zip, PDS .IMG, or calibrated FITS), directories or glob patterns",
+        multiple_values = true
+    )]
     inputs: Vec<String>,
 
-    #[clap(long, short, help = "Input metadata json", multiple_values = true)]
+    #[clap(
+        long,
+        short,
+        help = "Input metadata (json, -Data.zip, or PDS label). Paired with the inputs by ID if left off",
+        multiple_values = true
+    )]
     metadata: Vec<String>,
 
     // #[clap(long, short, help = "Output images", multiple_values = true)]
@@ -38,9 +52,20 @@ pub struct Process {
     #[clap(long, short = 'H', help = "Output height")]
     height: Option<usize>,
 
-    #[clap(long, short, help = "Fisheye camera field of view, in degrees")]
+    #[clap(
+        long,
+        short,
+        help = "Fisheye or perspective camera field of view, in degrees"
+    )]
     fov: Option<f64>,
 
+    #[clap(
+        long,
+        short = 'a',
+        help = "Perspective camera aspect ratio (width / height)"
+    )]
+    aspect: Option<f64>,
+
     #[clap(
         long,
         short = 'P',
@@ -65,7 +90,11 @@ pub struct Process {
     )]
     roll: Option<f64>,
 
-    #[clap(long, short, help = "Camera lens (cylindrical, fisheye)")]
+    #[clap(
+        long,
+        short,
+        help = "Camera lens (cylindrical, fisheye, perspective, equirectangular, orthographic, stereographic, lambert)"
+    )]
     lens: Option<String>,
 
     #[clap(long, short = 'F', help = "Fast, skip every other line/sample")]
@@ -73,12 +102,102 @@ pub struct Process {
 
     #[clap(long, short = 'd', help = "Perform decorrelated color stretch")]
     decorrelated_color_stretch: bool,
+
+    #[clap(long, help = "Interpolation method (nearest, bilinear, bicubic)")]
+    interpolation: Option<String>,
+
+    #[clap(long, help = "Use the legacy forward-projecting renderer")]
+    legacy: bool,
+
+    #[clap(
+        long,
+        help = "Use planetographic latitude and west longitude for surface projections"
+    )]
+    planetographic: bool,
+
+    #[clap(
+        long,
+        help = "Orthographic sub-observer latitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    sub_lat: Option<f64>,
+
+    #[clap(
+        long,
+        help = "Orthographic sub-observer longitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    sub_lon: Option<f64>,
+
+    #[clap(long, help = "Map scale, in kilometers per pixel")]
+    scale: Option<f64>,
+
+    #[clap(long, help = "Polar projection pole (north, south)")]
+    pole: Option<String>,
+
+    #[clap(
+        long,
+        help = "Polar projection bounding latitude, in degrees",
+        allow_hyphen_values(true)
+    )]
+    bounding_lat: Option<f64>,
+
+    #[clap(
+        long,
+        help = "Cylindrical map bounds, in degrees (top lat, bottom lat, left lon, right lon)",
+        number_of_values = 4,
+        allow_hyphen_values(true)
+    )]
+    bounds: Option<Vec<f64>>,
+
+    #[clap(long, help = "Cylindrical map resolution, in degrees per pixel")]
+    degrees_per_pixel: Option<f64>,
+
+    #[clap(long, help = "Write geometry backplanes as float TIFFs, or FITS with --format fits")]
+    backplanes: bool,
+
+    #[clap(
+        long,
+        help = "Photometric correction (none, minnaert, lommel-seeliger)"
+    )]
+    photometry: Option<String>,
+
+    #[clap(
+        long,
+        help = "Minnaert limb darkening exponents (red, green, blue)",
+        number_of_values = 3
+    )]
+    minnaert_k: Option<Vec<f64>>,
+
+    #[clap(
+        long,
+        help = "Calibrate to I/F. Written as float TIFF unless another format is given"
+    )]
+    radiometric: bool,
+
+    #[clap(
+        long,
+        help = "Output format (png, tiff, fits). Float formats are unstretched"
+    )]
+    format: Option<String>,
+
+    #[clap(
+        long,
+        help = "Also write the calibrated framelets as FITS, which can be used as input later"
+    )]
+    calibrated_fits: bool,
+
+    #[clap(
+        long,
+        help = "Also write a mask of the pixels that received data, one band per output band"
+    )]
+    coverage_mask: bool,
 }
 
 #[async_trait::async_trait]
 impl RunnableSubcommand for Process {
     async fn run(&self) -> Result<()> {
-        let juno_config = config::load_configuration().expect("Failed to load config file");
+        let juno_config = config::load_configuration()?;
 
         let red_weight = self.red_weight.unwrap_or(juno_config.defaults.red_weight);
         let green_weight = self
@@ -97,20 +216,124 @@ impl RunnableSubcommand for Process {
                 if let Some(lens) = SupportedLens::from(l.as_str()) {
                     lens
                 } else {
-                    eprintln!("Error: Invalid camera lens requested: {}", l);
-                    eprintln!("Use either 'cylidrical' or 'fisheye'");
-                    process::exit(1);
+                    return Err(anyhow!(
+                        "Invalid camera lens requested: {}. Use either 'cylidrical', 'fisheye', 'perspective', 'equirectangular', 'orthographic', 'stereographic', or 'lambert'",
+                        l
+                    ));
+                }
+            }
+            None => SupportedLens::from(&juno_config.defaults.camera_lens_projection).ok_or_else(
+                || {
+                    JunocamError::Config(format!(
+                        "Invalid default camera lens projection: {}",
+                        juno_config.defaults.camera_lens_projection
+                    ))
+                },
+            )?,
+        };
+
+        let interpolation = match &self.interpolation {
+            Some(i) => {
+                if let Some(interp) = Interpolation::from(i.as_str()) {
+                    interp
+                } else {
+                    return Err(anyhow!(
+                        "Invalid interpolation method requested: {}. Use either 'nearest', 'bilinear', or 'bicubic'",
+                        i
+                    ));
                 }
             }
-            None => SupportedLens::from(&juno_config.defaults.camera_lens_projection)
-                .expect("Invalid default camera lens projection"),
+            None => Interpolation::Bilinear,
         };
+        vprintln!("Interpolation method: {:?}", interpolation);
+
+        let minnaert_k = match &self.minnaert_k {
+            Some(k) => [k[0], k[1], k[2]],
+            None => juno_config.defaults.minnaert_k(),
+        };
+
+        let photometric_model = match self
+            .photometry
+            .as_ref()
+            .or(juno_config.defaults.photometric_model.as_ref())
+        {
+            Some(p) => {
+                if let Some(model) = PhotometricModel::from(p.as_str(), minnaert_k) {
+                    model
+                } else {
+                    return Err(anyhow!(
+                        "Invalid photometric model requested: {}. Use either 'none', 'minnaert', or 'lommel-seeliger'",
+                        p
+                    ));
+                }
+            }
+            None => PhotometricModel::None,
+        };
+        vprintln!("Photometric model: {:?}", photometric_model);
 
         let fov = match self.fov {
             Some(f) => f,
-            None => juno_config.defaults.fisheye_field_of_view,
+            None => camera_lens.default_field_of_view(&juno_config.defaults),
+        };
+        vprintln!("Camera field of view: {}", fov);
+
+        if camera_lens == SupportedLens::Perspective && (fov <= 0.0 || fov >= 180.0) {
+            return Err(anyhow!(
+                "Perspective field of view must be between 0 and 180 degrees"
+            ));
+        }
+
+        let aspect_ratio = match self.aspect {
+            Some(a) => a,
+            None => output_width as f64 / output_height as f64,
         };
-        vprintln!("Fisheye field of view: {}", fov);
+        if aspect_ratio <= 0.0 {
+            return Err(anyhow!("Invalid aspect ratio: {}", aspect_ratio));
+        }
+        vprintln!("Camera aspect ratio: {}", aspect_ratio);
+
+        let pole = match &self.pole {
+            Some(p) => {
+                if let Some(pole) = Pole::from(p.as_str()) {
+                    Some(pole)
+                } else {
+                    return Err(anyhow!(
+                        "Invalid pole requested: {}. Use either 'north' or 'south'",
+                        p
+                    ));
+                }
+            }
+            None => None,
+        };
+
+        let bounding_lat = self.bounding_lat.unwrap_or(60.0);
+        if bounding_lat.abs() >= 90.0 {
+            return Err(anyhow!("Invalid bounding latitude: {}", bounding_lat));
+        }
+
+        let map_bounds = match &self.bounds {
+            Some(b) => Some(LatLonBounds::new(b[0], b[1], b[2], b[3])),
+            None => juno_config.defaults.map_bounds,
+        };
+        if let Some(bounds) = map_bounds {
+            vprintln!("Map bounds: {:?}", bounds);
+        }
+
+        let degrees_per_pixel = match self.degrees_per_pixel {
+            Some(d) => Some(d),
+            None => juno_config.defaults.map_degrees_per_pixel,
+        };
+        if let Some(dpp) = degrees_per_pixel {
+            if dpp <= 0.0 {
+                return Err(anyhow!("Invalid map resolution: {}", dpp));
+            }
+        }
+
+        if let Some(scale) = self.scale {
+            if scale <= 0.0 {
+                return Err(anyhow!("Invalid map scale: {}", scale));
+            }
+        }
 
         let pitch = match self.pitch {
             Some(p) => p.to_radians() * -1.0, // Make it positive up
@@ -130,50 +353,107 @@ impl RunnableSubcommand for Process {
         };
         vprintln!("Fisheye camera roll: {}", roll.to_degrees());
 
-        if self.inputs.len() != self.metadata.len() {
-            eprintln!("Error: Inputs do not match outputs.");
-            process::exit(1);
+        let radiometric = self.radiometric || juno_config.defaults.apply_radiometric_calibration;
+        if radiometric && juno_config.radiometry.is_none() {
+            return Err(anyhow!(
+                "Radiometric calibration requested but no [radiometry] constants are configured"
+            ));
+        }
+
+        // Calibrated values are kept as they are unless asked otherwise
+        let output_format =
+            OutputFormat::parse_arg(self.format.as_ref())?.unwrap_or(if radiometric {
+                OutputFormat::FloatTiff
+            } else {
+                OutputFormat::Stretched
+            });
+        vprintln!("Output format: {:?}", output_format);
+
+        let pairing = pairing::pair_inputs(&self.inputs, &self.metadata)?;
+        pairing.orphan_images.iter().for_each(|i| {
+            eprintln!("Warning: No metadata found for {}", i);
+        });
+        pairing.orphan_metadata.iter().for_each(|m| {
+            eprintln!("Warning: No image found for {}", m);
+        });
+        if pairing.pairs.is_empty() {
+            return Err(anyhow!("No input images found"));
         }
 
-        self.inputs
-            .iter()
-            .zip(self.metadata.iter())
-            .for_each(|(file_path, metadata)| {
-                vprintln!("Image: {} -- Metadata: {}", file_path, metadata);
-                if !path::file_exists(file_path) {
-                    eprintln!("ERROR: Input file not found: {}", file_path);
-                    process::exit(1);
+        // A bad image is reported and skipped so the rest of the batch still gets processed
+        let mut failures: Vec<&str> = Vec::new();
+        pairing.pairs.iter().for_each(|pair| {
+            let (file_path, metadata) = (&pair.image, &pair.metadata);
+            vprintln!("Image: {} -- Metadata: {}", file_path, metadata);
+            if !path::file_exists(file_path) {
+                eprintln!("Error: Input file not found: {}", file_path);
+                failures.push(file_path);
+                return;
+            }
+            vprintln!("Loading image file from {}", file_path);
+
+            let output_filename = pair.output_file(&format!(
+                "-processed{}",
+                output_format.extension().unwrap_or(".png")
+            ));
+
+            match process_image(&ProcessOptions {
+                input: file_path.to_string(),
+                metadata: metadata.to_string(),
+                output: Some(output_filename),
+                red_weight,
+                green_weight,
+                blue_weight,
+                predicted: self.predicted,
+                width: output_width,
+                height: output_height,
+                fov,
+                aspect_ratio,
+                pitch,
+                yaw,
+                roll,
+                lens: camera_lens,
+                fast: self.fast,
+                decorrelated_color_stretch: self.decorrelated_color_stretch,
+                interpolation,
+                legacy_renderer: self.legacy,
+                planetographic: self.planetographic,
+                sub_observer_lat: self.sub_lat,
+                sub_observer_lon: self.sub_lon,
+                map_scale: self.scale,
+                pole,
+                bounding_lat,
+                map_bounds,
+                degrees_per_pixel,
+                backplanes: self.backplanes,
+                photometric_model,
+                radiometric,
+                output_format,
+                calibrated_fits: if self.calibrated_fits {
+                    Some(pair.output_file("-calibrated.fits"))
+                } else {
+                    None
+                },
+                coverage_mask: self.coverage_mask,
+            }) {
+                Ok(_) => {
+                    vprintln!("Done")
                 }
-                vprintln!("Loading image file from {}", file_path);
-
-                let output_filename = util::replace_image_extension(file_path, "-processed.png");
-
-                match process_image(&ProcessOptions {
-                    input: file_path.to_string(),
-                    metadata: metadata.to_string(),
-                    output: Some(output_filename),
-                    red_weight,
-                    green_weight,
-                    blue_weight,
-                    predicted: self.predicted,
-                    width: output_width,
-                    height: output_height,
-                    fov,
-                    pitch,
-                    yaw,
-                    roll,
-                    lens: camera_lens,
-                    fast: self.fast,
-                    decorrelated_color_stretch: self.decorrelated_color_stretch,
-                }) {
-                    Ok(_) => {
-                        vprintln!("Done")
-                    }
-                    Err(why) => {
-                        eprintln!("Error processing image: {}", why)
-                    }
+                Err(why) => {
+                    eprintln!("Error processing image {}: {}", file_path, why);
+                    failures.push(file_path);
                 }
-            });
+            }
+        });
+
+        if !failures.is_empty() {
+            return Err(anyhow!(
+                "{} of {} images failed: {}",
+                failures.len(),
+                pairing.pairs.len(),
+                failures.join(", ")
+            ));
+        }
 
         Ok(())
     }
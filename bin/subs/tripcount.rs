@@ -1,8 +1,7 @@
 use crate::subs::runnable::RunnableSubcommand;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use junocam::rawimage;
 use sciimg::path;
-use std::process;
 
 #[derive(clap::Args)]
 #[clap(author, version, about = "Triplet Count", long_about = None)]
@@ -13,15 +12,21 @@ pub struct TripletCount {
 
 #[async_trait::async_trait]
 impl RunnableSubcommand for TripletCount {
-    async fn run(&self) -> Result<()>{
+    async fn run(&self) -> Result<()> {
         if !path::file_exists(&self.input) {
-            eprintln!("ERROR: Input file not found!");
-            process::exit(1);
+            return Err(anyhow!("Input file not found: {}", self.input));
         }
 
-        let raw_image = rawimage::RawImage::new_from_image(&self.input).unwrap();
+        let raw_image = rawimage::RawImage::new_from_image(&self.input)?;
         println!("Image File: {}", self.input);
-        println!("Triplet Count: {}", raw_image.get_triplet_count());
+        let partial = raw_image.get_partial_framelet_set_count();
+        println!(
+            "Triplet Count: {}",
+            raw_image.get_framelet_set_count() - partial
+        );
+        if partial > 0 {
+            println!("Partial Triplet Count: {}", partial);
+        }
 
         Ok(())
     }
@@ -1,9 +1,9 @@
 use crate::subs::runnable::RunnableSubcommand;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use junocam::output::{self, OutputFormat};
 use junocam::rawimage;
 use junocam::vprintln;
 use sciimg::path;
-use std::process;
 
 #[derive(clap::Args)]
 #[clap(author, version, about = "Infill Correction", long_about = None)]
@@ -22,32 +22,36 @@ pub struct Weights {
 
     #[clap(long, short, help = "Blue weight")]
     blue: Option<f32>,
+
+    #[clap(
+        long,
+        help = "Output format (png, tiff, fits). Float formats are unstretched"
+    )]
+    format: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl RunnableSubcommand for Weights {
-    async fn run(&self) -> Result<()>{
+    async fn run(&self) -> Result<()> {
         if !path::file_exists(&self.input) {
-            eprintln!("ERROR: Input file not found!");
-            process::exit(1);
+            return Err(anyhow!("Input file not found: {}", self.input));
         }
 
+        let output_format =
+            OutputFormat::parse_arg(self.format.as_ref())?.unwrap_or(OutputFormat::Stretched);
+
         vprintln!("Loading image file from {}", self.input);
-        let mut raw_image = rawimage::RawImage::new_from_image(&self.input).unwrap();
+        let mut raw_image = rawimage::RawImage::new_from_image(&self.input)?;
 
         let red_weight = self.red.unwrap_or(1.0);
         let green_weight = self.green.unwrap_or(1.0);
         let blue_weight = self.blue.unwrap_or(1.0);
 
         vprintln!("Applying weights...");
-        raw_image
-            .apply_weights(red_weight, green_weight, blue_weight)
-            .expect("Error applying channel weight values");
+        raw_image.apply_weights(red_weight, green_weight, blue_weight)?;
 
         vprintln!("Saving image to {}", self.output);
-        let mut assembled_final = raw_image.assemble();
-        assembled_final.normalize_mut(0.0, 65535.0);
-        assembled_final.save(&self.output)?;
+        output::save_buffer(&raw_image.assemble()?, &self.output, output_format)?;
 
         Ok(())
     }
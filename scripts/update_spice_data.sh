@@ -35,6 +35,7 @@ mkdir_if_not_exist download
 
 pushd download
 wget --mirror --no-parent -N -X /pub/naif/JUNO/misc/ https://naif.jpl.nasa.gov/pub/naif/JUNO/kernels/ck
+wget --mirror --no-parent -N -A "spk_rec_*.bsp" https://naif.jpl.nasa.gov/pub/naif/JUNO/kernels/spk/
 curl -O ftp://naif.jpl.nasa.gov/pub/naif/JUNO/kernels/pck/pck00010.tpc
 curl -O ftp://naif.jpl.nasa.gov/pub/naif/JUNO/kernels/fk/juno_v12.tf
 curl -O ftp://naif.jpl.nasa.gov/pub/naif/JUNO/kernels/ik/juno_junocam_v03.ti
@@ -63,4 +64,5 @@ mkdir_if_not_exist sclk
 cp download/JNO_SCLKSCET.00138.tsc sclk/jno_sclkscet_00074.tsc
 
 mkdir_if_not_exist spk
-cp download/juno_struct_v04.bsp spk/
\ No newline at end of file
+cp download/juno_struct_v04.bsp spk/
+cp download/naif.jpl.nasa.gov/pub/naif/JUNO/kernels/spk/spk_rec_* spk/
\ No newline at end of file
@@ -0,0 +1,183 @@
+use crate::{
+    ellipsoid::Ellipsoid,
+    output::{self, OutputFormat, Stretch},
+    vprintln,
+};
+
+use anyhow::Result;
+use sciimg::{imagebuffer::ImageBuffer, vector::Vector};
+
+/// Per-pixel geometry layers written alongside a rendered product
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Backplane {
+    /// Planetocentric latitude, degrees
+    Latitude,
+
+    /// System III (west-positive) longitude, degrees
+    Longitude,
+
+    /// Angle between the surface normal and the Sun, degrees
+    Incidence,
+
+    /// Angle between the surface normal and the spacecraft, degrees
+    Emission,
+
+    /// Angle between the Sun and the spacecraft as seen from the surface, degrees
+    Phase,
+
+    /// Distance from the spacecraft, kilometers
+    Range,
+
+    /// Local solar time, hours
+    LocalSolarTime,
+}
+
+impl Backplane {
+    pub fn all() -> [Backplane; 7] {
+        [
+            Backplane::Latitude,
+            Backplane::Longitude,
+            Backplane::Incidence,
+            Backplane::Emission,
+            Backplane::Phase,
+            Backplane::Range,
+            Backplane::LocalSolarTime,
+        ]
+    }
+
+    /// Short name, used in output file names
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backplane::Latitude => "lat",
+            Backplane::Longitude => "lon",
+            Backplane::Incidence => "incidence",
+            Backplane::Emission => "emission",
+            Backplane::Phase => "phase",
+            Backplane::Range => "range",
+            Backplane::LocalSolarTime => "lst",
+        }
+    }
+}
+
+/// Observation geometry of a single point on the surface
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SurfaceGeometry {
+    pub lat: f64,
+    pub lon_west: f64,
+    pub incidence: f64,
+    pub emission: f64,
+    pub phase: f64,
+    pub range: f64,
+    pub local_solar_time: f64,
+}
+
+impl SurfaceGeometry {
+    /// Geometry at body-fixed surface point `point` given the spacecraft and Sun
+    /// positions, also body-fixed and relative to the body center.
+    pub fn compute(
+        ellipsoid: &Ellipsoid,
+        point: &Vector,
+        spacecraft_position: &Vector,
+        sun_position: &Vector,
+    ) -> SurfaceGeometry {
+        let normal = ellipsoid.surface_normal(point);
+        let to_spacecraft = spacecraft_position.subtract(point);
+        let to_sun = sun_position.subtract(point);
+
+        let ll = ellipsoid.to_lat_lon(point);
+        let sun_ll = ellipsoid.to_lat_lon(sun_position);
+
+        SurfaceGeometry {
+            lat: ll.lat,
+            lon_west: (360.0 - ll.lon).rem_euclid(360.0),
+            incidence: angle_between(&normal, &to_sun),
+            emission: angle_between(&normal, &to_spacecraft),
+            phase: angle_between(&to_sun, &to_spacecraft),
+            range: to_spacecraft.len(),
+            // Noon at the subsolar longitude, with time advancing eastward
+            local_solar_time: (12.0 + (ll.lon - sun_ll.lon) / 15.0).rem_euclid(24.0),
+        }
+    }
+
+    pub fn value(&self, backplane: Backplane) -> f64 {
+        match backplane {
+            Backplane::Latitude => self.lat,
+            Backplane::Longitude => self.lon_west,
+            Backplane::Incidence => self.incidence,
+            Backplane::Emission => self.emission,
+            Backplane::Phase => self.phase,
+            Backplane::Range => self.range,
+            Backplane::LocalSolarTime => self.local_solar_time,
+        }
+    }
+}
+
+fn angle_between(a: &Vector, b: &Vector) -> f64 {
+    (a.dot_product(b) / (a.len() * b.len()))
+        .clamp(-1.0, 1.0)
+        .acos()
+        .to_degrees()
+}
+
+/// Geometry layers for an output image. Pixels that do not land on the surface are NaN.
+pub struct Backplanes {
+    pub width: usize,
+    pub height: usize,
+    planes: Vec<(Backplane, ImageBuffer)>,
+}
+
+impl Backplanes {
+    /// Builds the layers from per-pixel geometry arranged by row
+    pub fn from_rows(rows: &[Vec<Option<SurfaceGeometry>>]) -> Result<Backplanes> {
+        let height = rows.len();
+        let width = rows.first().map(|r| r.len()).unwrap_or(0);
+
+        let mut planes = vec![];
+        for backplane in Backplane::all() {
+            let mut buffer = ImageBuffer::new_with_fill(width, height, f32::NAN)?;
+            rows.iter().enumerate().for_each(|(y, row)| {
+                row.iter().enumerate().for_each(|(x, geom)| {
+                    if let Some(geom) = geom {
+                        buffer.put(x, y, geom.value(backplane) as f32);
+                    }
+                });
+            });
+            planes.push((backplane, buffer));
+        }
+
+        Ok(Backplanes {
+            width,
+            height,
+            planes,
+        })
+    }
+
+    pub fn get(&self, backplane: Backplane) -> Option<&ImageBuffer> {
+        self.planes
+            .iter()
+            .find(|(b, _)| *b == backplane)
+            .map(|(_, buffer)| buffer)
+    }
+
+    /// Writes each layer next to `output_file`, named after the layer. Layers are FITS when
+    /// that is the output format and float TIFFs otherwise, as they are never stretched.
+    pub fn save(&self, output_file: &str, format: OutputFormat) -> Result<()> {
+        for (backplane, buffer) in self.planes.iter() {
+            let extension = if format == OutputFormat::Fits {
+                "fits"
+            } else {
+                "tif"
+            };
+            let filename = output::replace_extension(
+                output_file,
+                &format!("-{}.{}", backplane.name(), extension),
+            );
+            vprintln!("Writing {:?} backplane to {}", backplane, filename);
+            match format {
+                OutputFormat::Fits => output::save_fits(&[buffer], &filename, Stretch::None, None)?,
+                _ => output::save_float_tiff(buffer, &filename)?,
+            }
+        }
+        Ok(())
+    }
+}
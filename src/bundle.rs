@@ -0,0 +1,75 @@
+use crate::constants;
+
+use sciimg::path;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use std::fs::File;
+use std::io::Read;
+
+/// Raw framelet image within a missionjuno `-ImageSet.zip`
+pub const RAW_IMAGE_SUFFIX: &str = "-raw.png";
+
+/// Metadata within a missionjuno `-Data.zip`
+pub const METADATA_SUFFIX: &str = "-Metadata.json";
+
+/// Bundle holding the raw and processed images
+pub const IMAGE_SET_SUFFIX: &str = "-ImageSet.zip";
+
+/// Bundle holding the metadata
+pub const DATA_SUFFIX: &str = "-Data.zip";
+
+/// True if the path looks like a zip bundle
+pub fn is_zip_file(file_path: &str) -> bool {
+    file_path.to_lowercase().ends_with(".zip")
+}
+
+/// Name of the first entry ending with `suffix`, without decompressing anything
+pub fn entry_name(zip_path: &str, suffix: &str) -> Result<String> {
+    find_entry(&open_archive(zip_path)?, zip_path, suffix)
+}
+
+/// Reads the first entry whose name ends with `suffix`, returning its name and contents
+pub fn read_entry(zip_path: &str, suffix: &str) -> Result<(String, Vec<u8>)> {
+    let mut archive = open_archive(zip_path)?;
+    let name = find_entry(&archive, zip_path, suffix)?;
+
+    let mut entry = archive.by_name(&name)?;
+    let mut bytes = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut bytes)?;
+
+    Ok((name, bytes))
+}
+
+fn open_archive(zip_path: &str) -> Result<zip::ZipArchive<File>> {
+    if !path::file_exists(zip_path) {
+        return Err(anyhow!(constants::status::FILE_NOT_FOUND));
+    }
+    Ok(zip::ZipArchive::new(File::open(zip_path)?)?)
+}
+
+fn find_entry(archive: &zip::ZipArchive<File>, zip_path: &str, suffix: &str) -> Result<String> {
+    let lower_suffix = suffix.to_lowercase();
+    archive
+        .file_names()
+        .find(|n| n.to_lowercase().ends_with(&lower_suffix))
+        .map(|n| n.to_string())
+        .ok_or_else(|| anyhow!("No '{}' entry in {}", suffix, zip_path))
+}
+
+/// The `-Data.zip` downloaded alongside an `-ImageSet.zip`, if it exists
+pub fn data_zip_for(image_zip: &str) -> Option<String> {
+    if !image_zip.ends_with(IMAGE_SET_SUFFIX) {
+        return None;
+    }
+    let data_zip = format!(
+        "{}{}",
+        &image_zip[..image_zip.len() - IMAGE_SET_SUFFIX.len()],
+        DATA_SUFFIX
+    );
+    if path::file_exists(&data_zip) {
+        Some(data_zip)
+    } else {
+        None
+    }
+}
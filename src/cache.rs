@@ -6,13 +6,14 @@ pub struct ImageCache {
     red: Option<ImageBuffer>,
     green: Option<ImageBuffer>,
     blue: Option<ImageBuffer>,
+    methane: Option<ImageBuffer>,
 }
 
 impl ImageCache {
     pub fn check_red(&mut self, path: &str) -> Result<ImageBuffer> {
         match &self.red {
             None => {
-                self.red = Some(ImageBuffer::from_file(path).unwrap());
+                self.red = Some(ImageBuffer::from_file(path)?);
                 Ok(self.red.as_ref().unwrap().to_owned())
             }
             Some(b) => Ok(b.to_owned()),
@@ -22,7 +23,7 @@ impl ImageCache {
     pub fn check_green(&mut self, path: &str) -> Result<ImageBuffer> {
         match &self.green {
             None => {
-                self.green = Some(ImageBuffer::from_file(path).unwrap());
+                self.green = Some(ImageBuffer::from_file(path)?);
                 Ok(self.green.as_ref().unwrap().to_owned())
             }
             Some(b) => Ok(b.to_owned()),
@@ -32,10 +33,20 @@ impl ImageCache {
     pub fn check_blue(&mut self, path: &str) -> Result<ImageBuffer> {
         match &self.blue {
             None => {
-                self.blue = Some(ImageBuffer::from_file(path).unwrap());
+                self.blue = Some(ImageBuffer::from_file(path)?);
                 Ok(self.blue.as_ref().unwrap().to_owned())
             }
             Some(b) => Ok(b.to_owned()),
         }
     }
+
+    pub fn check_methane(&mut self, path: &str) -> Result<ImageBuffer> {
+        match &self.methane {
+            None => {
+                self.methane = Some(ImageBuffer::from_file(path)?);
+                Ok(self.methane.as_ref().unwrap().to_owned())
+            }
+            Some(b) => Ok(b.to_owned()),
+        }
+    }
 }
@@ -1,114 +1,125 @@
+use crate::error::{JunocamError, JunocamResult};
 use crate::{cache, config, constants, enums, filelocate};
 
 use sciimg::{inpaint, prelude::*};
 use std::sync::Mutex;
 
-use anyhow::{anyhow, Result};
-
 lazy_static! {
     static ref DARK_CACHE: Mutex<cache::ImageCache> = Mutex::new(cache::ImageCache::default());
     static ref FLAT_CACHE: Mutex<cache::ImageCache> = Mutex::new(cache::ImageCache::default());
     static ref MASK_CACHE: Mutex<cache::ImageCache> = Mutex::new(cache::ImageCache::default());
 }
 
-pub fn load_mask(camera: enums::Camera) -> Result<ImageBuffer> {
-    let c = config::load_configuration().expect("Failed to load configuration");
-    match camera {
-        enums::Camera::RED => Ok(MASK_CACHE
-            .lock()
-            .unwrap()
-            .check_red(&filelocate::locate_calibration_file(&c.calibration.inpaint_red).unwrap())
-            .unwrap()),
-        enums::Camera::GREEN => Ok(MASK_CACHE
-            .lock()
-            .unwrap()
-            .check_green(
-                &filelocate::locate_calibration_file(&c.calibration.inpaint_green).unwrap(),
-            )
-            .unwrap()),
-        enums::Camera::BLUE => Ok(MASK_CACHE
-            .lock()
-            .unwrap()
-            .check_blue(&filelocate::locate_calibration_file(&c.calibration.inpaint_blue).unwrap())
-            .unwrap()),
-        _ => Err(anyhow!(constants::status::UNSUPPORTED_COLOR_CHANNEL)),
-    }
-}
+// Finds a calibration file and loads it through the cache for its camera
+fn load_cached(
+    cache: &Mutex<cache::ImageCache>,
+    camera: enums::Camera,
+    file_name: Option<&str>,
+) -> JunocamResult<ImageBuffer> {
+    let file_name = match (camera, file_name) {
+        (enums::Camera::NONE, _) => {
+            return Err(JunocamError::Calibration(
+                constants::status::UNSUPPORTED_COLOR_CHANNEL.into(),
+            ))
+        }
+        (_, Some(f)) => f,
+        (_, None) => {
+            return Err(JunocamError::Calibration(format!(
+                "No {:?} calibration files configured",
+                camera
+            )))
+        }
+    };
 
-pub fn load_flat_file(camera: enums::Camera) -> Result<ImageBuffer> {
-    let c = config::load_configuration().expect("Failed to load configuration");
+    let file_path = filelocate::locate_calibration_file(&file_name.to_string()).map_err(|_| {
+        JunocamError::Calibration(format!("Unable to locate calibration file {}", file_name))
+    })?;
 
-    let flat = match camera {
-        enums::Camera::RED => Ok(FLAT_CACHE
-            .lock()
-            .unwrap()
-            .check_red(&filelocate::locate_calibration_file(&c.calibration.flat_red).unwrap())
-            .unwrap()),
-        enums::Camera::GREEN => Ok(FLAT_CACHE
-            .lock()
-            .unwrap()
-            .check_green(&filelocate::locate_calibration_file(&c.calibration.flat_green).unwrap())
-            .unwrap()),
-        enums::Camera::BLUE => Ok(FLAT_CACHE
-            .lock()
-            .unwrap()
-            .check_blue(&filelocate::locate_calibration_file(&c.calibration.flat_blue).unwrap())
-            .unwrap()),
-        _ => Err(anyhow!(constants::status::UNSUPPORTED_COLOR_CHANNEL)),
+    let mut cache = cache
+        .lock()
+        .map_err(|_| JunocamError::Calibration("Calibration cache is poisoned".into()))?;
+    match camera {
+        enums::Camera::RED => cache.check_red(&file_path),
+        enums::Camera::GREEN => cache.check_green(&file_path),
+        enums::Camera::METHANE => cache.check_methane(&file_path),
+        _ => cache.check_blue(&file_path),
     }
-    .unwrap();
+    .map_err(|why| JunocamError::Io(format!("{}: {}", file_path, why)))
+}
 
-    let mask = match load_mask(camera) {
-        Ok(m) => m,
-        Err(_) => return Err(anyhow!("Error loading mask")),
-    };
+// Fills the masked pixels of a dark or flat field
+fn inpaint_calibration(buffer: &ImageBuffer, camera: enums::Camera) -> JunocamResult<ImageBuffer> {
+    let mask = load_mask(camera)?;
 
     // Loading our grayscale data into a 3 band RgbImage. Will need to modify the sciimg inpaint method to take in imagebuffer
-    let rgb = Image::new_from_buffers_rgb(&flat, &flat, &flat, ImageMode::U16BIT).unwrap();
+    let rgb = Image::new_from_buffers_rgb(buffer, buffer, buffer, ImageMode::U16BIT)
+        .map_err(|why| JunocamError::Calibration(why.to_string()))?;
 
-    let filled = match inpaint::apply_inpaint_to_buffer(&rgb, &mask) {
-        Ok(b) => b,
-        Err(e) => return Err(e),
-    };
+    let filled = inpaint::apply_inpaint_to_buffer(&rgb, &mask)
+        .map_err(|why| JunocamError::Calibration(format!("Inpainting failed: {}", why)))?;
 
     Ok(filled.get_band(0).clone())
 }
 
-pub fn load_dark_file(camera: enums::Camera) -> Result<ImageBuffer> {
-    let c = config::load_configuration().expect("Failed to load configuration");
+pub fn load_mask(camera: enums::Camera) -> JunocamResult<ImageBuffer> {
+    let c = config::load_configuration()?;
+    load_cached(&MASK_CACHE, camera, c.calibration.inpaint(camera))
+}
 
-    let dark = match camera {
-        enums::Camera::RED => Ok(DARK_CACHE
-            .lock()
-            .unwrap()
-            .check_red(&filelocate::locate_calibration_file(&c.calibration.dark_red).unwrap())
-            .unwrap()),
-        enums::Camera::GREEN => Ok(DARK_CACHE
-            .lock()
-            .unwrap()
-            .check_green(&filelocate::locate_calibration_file(&c.calibration.dark_green).unwrap())
-            .unwrap()),
-        enums::Camera::BLUE => Ok(DARK_CACHE
-            .lock()
-            .unwrap()
-            .check_blue(&filelocate::locate_calibration_file(&c.calibration.dark_blue).unwrap())
-            .unwrap()),
-        _ => Err(anyhow!(constants::status::UNSUPPORTED_COLOR_CHANNEL)),
-    }
-    .unwrap();
+pub fn load_flat_file(camera: enums::Camera) -> JunocamResult<ImageBuffer> {
+    let c = config::load_configuration()?;
+    let flat = load_cached(&FLAT_CACHE, camera, c.calibration.flat(camera))?;
+    inpaint_calibration(&flat, camera)
+}
 
-    let mask = match load_mask(camera) {
-        Ok(m) => m,
-        Err(_) => return Err(anyhow!("Error loading mask")),
-    };
+pub fn load_dark_file(camera: enums::Camera) -> JunocamResult<ImageBuffer> {
+    let c = config::load_configuration()?;
+    let dark = load_cached(&DARK_CACHE, camera, c.calibration.dark(camera))?;
+    inpaint_calibration(&dark, camera)
+}
 
-    // Loading our grayscale data into a 3 band RgbImage. Will need to modify the sciimg inpaint method to take in imagebuffer
-    let rgb = Image::new_from_buffers_rgb(&dark, &dark, &dark, ImageMode::U16BIT).unwrap();
+// Reduces each `sampling_factor` square block of full resolution calibration data to a
+// single pixel
+fn sum_blocks<F>(
+    buffer: &ImageBuffer,
+    sampling_factor: usize,
+    reduce: F,
+) -> JunocamResult<ImageBuffer>
+where
+    F: Fn(&[f32]) -> f32,
+{
+    if sampling_factor <= 1 {
+        return Ok(buffer.clone());
+    }
+    let width = buffer.width / sampling_factor;
+    let height = buffer.height / sampling_factor;
+    let mut summed = ImageBuffer::new(width, height)
+        .map_err(|why| JunocamError::Calibration(why.to_string()))?;
+    let mut block = Vec::with_capacity(sampling_factor * sampling_factor);
+    for y in 0..height {
+        for x in 0..width {
+            block.clear();
+            for by in 0..sampling_factor {
+                for bx in 0..sampling_factor {
+                    block.push(buffer.get(x * sampling_factor + bx, y * sampling_factor + by));
+                }
+            }
+            summed.put(x, y, reduce(&block));
+        }
+    }
+    Ok(summed)
+}
 
-    let filled = match inpaint::apply_inpaint_to_buffer(&rgb, &mask) {
-        Ok(b) => b,
-        Err(e) => return Err(e),
-    };
+/// Dark or flat field for an image summed by `sampling_factor`, summing pixels the same
+/// way the camera does
+pub fn summed(buffer: &ImageBuffer, sampling_factor: usize) -> JunocamResult<ImageBuffer> {
+    sum_blocks(buffer, sampling_factor, |b| b.iter().sum())
+}
 
-    Ok(filled.get_band(0).clone())
+/// Inpainting mask for an image summed by `sampling_factor`. A summed pixel is masked if any
+/// of the pixels summed into it are.
+pub fn summed_mask(buffer: &ImageBuffer, sampling_factor: usize) -> JunocamResult<ImageBuffer> {
+    sum_blocks(buffer, sampling_factor, |b| {
+        b.iter().cloned().fold(0.0, f32::max)
+    })
 }
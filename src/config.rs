@@ -1,11 +1,13 @@
+use crate::enums::Camera;
+use crate::error::{JunocamError, JunocamResult};
 use crate::filelocate;
+use crate::projection::projection::LatLonBounds;
+use crate::radiometry::Radiometry;
 use crate::vprintln;
 
 use std::fs::File;
 use std::io::Read;
 
-use anyhow::Result;
-
 //use serde_derive::Deserialize;
 use serde::Deserialize;
 
@@ -16,6 +18,10 @@ pub struct Defaults {
     pub blue_weight: f32,
     pub camera_lens_projection: String,
     pub fisheye_field_of_view: f64,
+
+    // Perspective cameras can't see 180 degrees, so get a field of view of their own
+    #[serde(default)]
+    pub perspective_field_of_view: Option<f64>,
     pub interframe_delay_correction: f64,
     pub start_time_correction: f64,
     pub apply_calibration: bool,
@@ -25,6 +31,39 @@ pub struct Defaults {
     pub hpc_threshold: f32,
     pub apply_weights: bool,
     pub correlated_color_balancing: bool,
+
+    // Convert calibrated DN to I/F using the [radiometry] constants
+    #[serde(default)]
+    pub apply_radiometric_calibration: bool,
+
+    // Default area and resolution of cylindrical maps. Full globe at the requested output
+    // size if not specified.
+    #[serde(default)]
+    pub map_bounds: Option<LatLonBounds>,
+    #[serde(default)]
+    pub map_degrees_per_pixel: Option<f64>,
+
+    // Photometric model applied to body pixels (none, minnaert, lommel-seeliger) and the
+    // Minnaert limb darkening exponents for each band. No correction if not specified.
+    #[serde(default)]
+    pub photometric_model: Option<String>,
+    #[serde(default)]
+    pub minnaert_k_red: Option<f64>,
+    #[serde(default)]
+    pub minnaert_k_green: Option<f64>,
+    #[serde(default)]
+    pub minnaert_k_blue: Option<f64>,
+}
+
+impl Defaults {
+    /// Minnaert exponents in red, green, blue order. Unspecified bands are Lambertian.
+    pub fn minnaert_k(&self) -> [f64; 3] {
+        [
+            self.minnaert_k_red.unwrap_or(1.0),
+            self.minnaert_k_green.unwrap_or(1.0),
+            self.minnaert_k_blue.unwrap_or(1.0),
+        ]
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -40,6 +79,55 @@ pub struct CalibrationFiles {
     pub inpaint_red: String,
     pub inpaint_green: String,
     pub inpaint_blue: String,
+
+    // Methane band (889 nm). Methane images can't be dark/flat calibrated or infilled
+    // without these.
+    #[serde(default)]
+    pub dark_methane: Option<String>,
+    #[serde(default)]
+    pub flat_methane: Option<String>,
+    #[serde(default)]
+    pub inpaint_methane: Option<String>,
+}
+
+impl CalibrationFiles {
+    pub fn dark(&self, camera: Camera) -> Option<&str> {
+        Self::for_camera(
+            camera,
+            [&self.dark_red, &self.dark_green, &self.dark_blue],
+            &self.dark_methane,
+        )
+    }
+
+    pub fn flat(&self, camera: Camera) -> Option<&str> {
+        Self::for_camera(
+            camera,
+            [&self.flat_red, &self.flat_green, &self.flat_blue],
+            &self.flat_methane,
+        )
+    }
+
+    pub fn inpaint(&self, camera: Camera) -> Option<&str> {
+        Self::for_camera(
+            camera,
+            [&self.inpaint_red, &self.inpaint_green, &self.inpaint_blue],
+            &self.inpaint_methane,
+        )
+    }
+
+    fn for_camera<'a>(
+        camera: Camera,
+        rgb: [&'a String; 3],
+        methane: &'a Option<String>,
+    ) -> Option<&'a str> {
+        match camera {
+            Camera::RED => Some(rgb[0]),
+            Camera::GREEN => Some(rgb[1]),
+            Camera::BLUE => Some(rgb[2]),
+            Camera::METHANE => methane.as_deref(),
+            Camera::NONE => None,
+        }
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -47,6 +135,13 @@ pub struct Spice {
     pub kernels: Vec<String>,
     pub ck_rec_pattern: String,
     pub ck_pre_pattern: String,
+
+    // Spacecraft position kernels, needed for surface projections. If not specified, the
+    // base kernels are expected to cover the image time.
+    #[serde(default)]
+    pub spk_rec_pattern: Option<String>,
+    #[serde(default)]
+    pub spk_pre_pattern: Option<String>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -54,40 +149,36 @@ pub struct JunoConfig {
     pub spice: Spice,
     pub calibration: CalibrationFiles,
     pub defaults: Defaults,
+
+    #[serde(default)]
+    pub radiometry: Option<Radiometry>,
 }
 
 static mut JUNO_CONFIG: Option<JunoConfig> = None;
 
-pub fn load_configuration() -> Result<JunoConfig> {
+pub fn load_configuration() -> JunocamResult<JunoConfig> {
     unsafe {
         if let Some(c) = &JUNO_CONFIG {
             return Ok(c.clone());
         }
     }
 
-    let config_file_path = filelocate::locate_calibration_file(&String::from("config.toml"));
-
-    match config_file_path {
-        Ok(config_toml) => {
-            vprintln!("Loading configuration from {}", config_toml);
+    let config_toml = filelocate::locate_calibration_file(&String::from("config.toml"))
+        .map_err(|_| JunocamError::Config("Unable to locate juno configuration file".into()))?;
+    vprintln!("Loading configuration from {}", config_toml);
 
-            let mut file = match File::open(&config_toml) {
-                Err(why) => panic!("couldn't open {}", why),
-                Ok(file) => file,
-            };
+    let mut file = File::open(&config_toml)
+        .map_err(|why| JunocamError::Io(format!("Couldn't open {}: {}", config_toml, why)))?;
 
-            let mut buf: Vec<u8> = Vec::default();
-            file.read_to_end(&mut buf).unwrap();
-            let toml = String::from_utf8(buf).unwrap();
+    let mut buf: Vec<u8> = Vec::default();
+    file.read_to_end(&mut buf)?;
+    let toml = String::from_utf8(buf)
+        .map_err(|why| JunocamError::Config(format!("{}: {}", config_toml, why)))?;
+    let config: JunoConfig = toml::from_str(&toml)
+        .map_err(|why| JunocamError::Config(format!("{}: {}", config_toml, why)))?;
 
-            unsafe {
-                let config: JunoConfig = toml::from_str(&toml).unwrap();
-                JUNO_CONFIG = Some(config);
-                Ok(JUNO_CONFIG.clone().unwrap())
-            }
-        }
-        Err(_) => {
-            panic!("Unable to locate juno configuration file")
-        }
+    unsafe {
+        JUNO_CONFIG = Some(config.clone());
     }
+    Ok(config)
 }
@@ -3,6 +3,10 @@ pub const _16_BIT_MAX: f32 = std::u16::MAX as f32;
 pub const STRIP_HEIGHT: usize = 128;
 pub const STRIP_WIDTH: usize = 1648;
 
+// Lines beside a run of zero filled lines must have at least this fraction of their
+// samples nonzero for the run to be taken as lost data rather than dark sky
+pub const GAP_NEIGHBOR_SIGNAL_FRACTION: f64 = 0.5;
+
 pub const DEFAULT_RED_WEIGHT: f32 = 0.902;
 pub const DEFAULT_GREEN_WEIGHT: f32 = 1.0;
 pub const DEFAULT_BLUE_WEIGHT: f32 = 1.8889;
@@ -0,0 +1,122 @@
+use anyhow::Result;
+use sciimg::prelude::*;
+
+/// Which pixels of a rendered image received data, for each of its bands. Tells pixels
+/// that are truly black, such as space, apart from those nothing was rendered to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub width: usize,
+    pub height: usize,
+    pub bands: usize,
+    covered: Vec<bool>,
+}
+
+impl Coverage {
+    pub fn new(width: usize, height: usize, bands: usize) -> Coverage {
+        Coverage {
+            width,
+            height,
+            bands,
+            covered: vec![false; width * height * bands],
+        }
+    }
+
+    fn index(&self, x: usize, y: usize, band: usize) -> usize {
+        (band * self.height + y) * self.width + x
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, band: usize) {
+        let i = self.index(x, y, band);
+        self.covered[i] = true;
+    }
+
+    /// Marks the pixels of `band` inside the quad with corners `tl`, `bl`, `br`, `tr`, given
+    /// as x, y. Pixels are tested at their integer positions against the quad's two
+    /// triangles, split along `bl` to `tr` as `Drawable::paint_square()` paints them.
+    pub fn set_quad(
+        &mut self,
+        tl: (f64, f64),
+        bl: (f64, f64),
+        br: (f64, f64),
+        tr: (f64, f64),
+        band: usize,
+    ) {
+        let corners = [tl, bl, br, tr];
+        let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max_x < 0.0 || max_y < 0.0 || min_x >= self.width as f64 || min_y >= self.height as f64 {
+            return;
+        }
+
+        let x_range = min_x.floor().max(0.0) as usize..=(max_x.ceil() as usize).min(self.width - 1);
+        let y_range =
+            min_y.floor().max(0.0) as usize..=(max_y.ceil() as usize).min(self.height - 1);
+        for y in y_range {
+            for x in x_range.clone() {
+                let p = (x as f64, y as f64);
+                if in_triangle(p, tl, bl, tr) || in_triangle(p, tr, bl, br) {
+                    self.set(x, y, band);
+                }
+            }
+        }
+    }
+
+    pub fn is_covered(&self, x: usize, y: usize, band: usize) -> bool {
+        band < self.bands && self.covered[self.index(x, y, band)]
+    }
+
+    /// True if any band received data at `x`, `y`
+    pub fn is_any_covered(&self, x: usize, y: usize) -> bool {
+        (0..self.bands).any(|b| self.is_covered(x, y, b))
+    }
+
+    /// Number of pixels where any band received data
+    pub fn covered_pixels(&self) -> usize {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|(x, y)| self.is_any_covered(*x, *y))
+            .count()
+    }
+
+    /// Turns on the alpha channel of `image`, opaque wherever any band received data
+    pub fn apply_alpha(&self, image: &mut Image) {
+        (0..self.height).for_each(|y| {
+            (0..self.width).for_each(|x| image.put_alpha(x, y, self.is_any_covered(x, y)));
+        });
+        image.set_using_alpha(true);
+    }
+
+    /// Mask image with a band for each rendered band, 65535 where that band received data
+    /// and 0 elsewhere
+    pub fn to_mask(&self) -> Result<Image> {
+        let mut mask =
+            Image::new_with_bands(self.width, self.height, self.bands, ImageMode::U16BIT)?;
+        (0..self.bands).for_each(|b| {
+            (0..self.height).for_each(|y| {
+                (0..self.width).for_each(|x| {
+                    if self.is_covered(x, y, b) {
+                        mask.put(x, y, 65535.0, b);
+                    }
+                });
+            });
+        });
+        Ok(mask)
+    }
+}
+
+// True if `p` is inside or on the edge of the triangle `a`, `b`, `c`, in either winding
+fn in_triangle(p: (f64, f64), a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+    let side = |u: (f64, f64), v: (f64, f64)| (v.0 - u.0) * (p.1 - u.1) - (v.1 - u.1) * (p.0 - u.0);
+    let (d1, d2, d3) = (side(a, b), side(b, c), side(c, a));
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
@@ -0,0 +1,121 @@
+use crate::jcspice;
+
+use anyhow::Result;
+use sciimg::vector::Vector;
+
+/// Planetocentric latitude and east-positive longitude (0 to 360), in degrees.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LatLon {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Triaxial reference ellipsoid in a body-fixed frame. Radii are in kilometers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ellipsoid {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl Ellipsoid {
+    pub fn new(a: f64, b: f64, c: f64) -> Ellipsoid {
+        Ellipsoid { a, b, c }
+    }
+
+    /// Jupiter reference ellipsoid as defined in the loaded PCK
+    pub fn jupiter() -> Result<Ellipsoid> {
+        let radii = jcspice::body_radii("JUPITER")?;
+        Ok(Ellipsoid::new(radii[0], radii[1], radii[2]))
+    }
+
+    pub fn equatorial_radius(&self) -> f64 {
+        self.a
+    }
+
+    pub fn polar_radius(&self) -> f64 {
+        self.c
+    }
+
+    /// Nearest intersection in front of `origin` of the ray along `direction`, if any.
+    pub fn intersect(&self, origin: &Vector, direction: &Vector) -> Option<Vector> {
+        // Scale into a unit sphere and solve |o + t*d| = 1
+        let (ox, oy, oz) = (origin.x / self.a, origin.y / self.b, origin.z / self.c);
+        let (dx, dy, dz) = (
+            direction.x / self.a,
+            direction.y / self.b,
+            direction.z / self.c,
+        );
+
+        let qa = dx * dx + dy * dy + dz * dz;
+        let qb = 2.0 * (ox * dx + oy * dy + oz * dz);
+        let qc = ox * ox + oy * oy + oz * oz - 1.0;
+
+        let disc = qb * qb - 4.0 * qa * qc;
+        if disc < 0.0 || qa == 0.0 {
+            return None;
+        }
+
+        let sq = disc.sqrt();
+        let t0 = (-qb - sq) / (2.0 * qa);
+        let t1 = (-qb + sq) / (2.0 * qa);
+        let t = if t0 > 0.0 {
+            t0
+        } else if t1 > 0.0 {
+            t1
+        } else {
+            return None;
+        };
+
+        Some(Vector::new(
+            origin.x + direction.x * t,
+            origin.y + direction.y * t,
+            origin.z + direction.z * t,
+        ))
+    }
+
+    /// Outward unit normal at a point on the surface
+    pub fn surface_normal(&self, p: &Vector) -> Vector {
+        Vector::new(
+            p.x / (self.a * self.a),
+            p.y / (self.b * self.b),
+            p.z / (self.c * self.c),
+        )
+        .normalized()
+    }
+
+    /// Body-fixed surface point at a planetocentric latitude/longitude
+    pub fn surface_point(&self, ll: &LatLon) -> Vector {
+        let lat = ll.lat.to_radians();
+        let lon = ll.lon.to_radians();
+        let (dx, dy, dz) = (lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin());
+
+        let r =
+            1.0 / ((dx / self.a).powi(2) + (dy / self.b).powi(2) + (dz / self.c).powi(2)).sqrt();
+        Vector::new(dx * r, dy * r, dz * r)
+    }
+
+    /// Planetocentric latitude/longitude of a body-fixed point
+    pub fn to_lat_lon(&self, p: &Vector) -> LatLon {
+        let mut lon = p.y.atan2(p.x).to_degrees();
+        if lon < 0.0 {
+            lon += 360.0;
+        }
+        LatLon {
+            lat: p.z.atan2((p.x * p.x + p.y * p.y).sqrt()).to_degrees(),
+            lon,
+        }
+    }
+
+    /// Converts planetocentric latitude (degrees) to planetographic
+    pub fn planetocentric_to_planetographic(&self, lat: f64) -> f64 {
+        let f = self.equatorial_radius() / self.polar_radius();
+        (lat.to_radians().tan() * f * f).atan().to_degrees()
+    }
+
+    /// Converts planetographic latitude (degrees) to planetocentric
+    pub fn planetographic_to_planetocentric(&self, lat: f64) -> f64 {
+        let f = self.polar_radius() / self.equatorial_radius();
+        (lat.to_radians().tan() * f * f).atan().to_degrees()
+    }
+}
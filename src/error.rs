@@ -0,0 +1,56 @@
+use crate::metadata::MetadataError;
+
+use std::fmt;
+
+/// Errors raised while loading, calibrating and projecting JunoCam images
+#[derive(Debug)]
+pub enum JunocamError {
+    /// A file couldn't be found, read or written
+    Io(String),
+    /// Image metadata is missing or malformed
+    Metadata(MetadataError),
+    /// SPICE kernels couldn't be located or loaded
+    Spice(String),
+    /// The configuration file couldn't be located or parsed
+    Config(String),
+    /// Dark, flat or inpainting masks couldn't be loaded or applied
+    Calibration(String),
+    /// Image dimensions don't fit the framelet layout
+    Geometry(String),
+}
+
+pub type JunocamResult<T> = std::result::Result<T, JunocamError>;
+
+impl fmt::Display for JunocamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JunocamError::Io(s) => write!(f, "I/O error: {}", s),
+            JunocamError::Metadata(e) => write!(f, "Metadata error: {}", e),
+            JunocamError::Spice(s) => write!(f, "SPICE error: {}", s),
+            JunocamError::Config(s) => write!(f, "Configuration error: {}", s),
+            JunocamError::Calibration(s) => write!(f, "Calibration error: {}", s),
+            JunocamError::Geometry(s) => write!(f, "Geometry error: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for JunocamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JunocamError::Metadata(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<MetadataError> for JunocamError {
+    fn from(e: MetadataError) -> Self {
+        JunocamError::Metadata(e)
+    }
+}
+
+impl From<std::io::Error> for JunocamError {
+    fn from(e: std::io::Error) -> Self {
+        JunocamError::Io(e.to_string())
+    }
+}
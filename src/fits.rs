@@ -0,0 +1,406 @@
+use crate::{metadata::Metadata, vprintln};
+
+use anyhow::{anyhow, Result};
+use sciimg::{imagebuffer::ImageBuffer, path};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+// FITS files are made up of 2880 byte blocks of 80 character header cards and data
+const BLOCK_SIZE: usize = 2880;
+const CARD_SIZE: usize = 80;
+
+/// Value of a FITS header keyword
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl HeaderValue {
+    // Fixed format value field. Strings start in column 11, everything else is right
+    // justified to column 30.
+    fn format(&self) -> String {
+        match self {
+            HeaderValue::Bool(b) => format!("{:>20}", if *b { "T" } else { "F" }),
+            HeaderValue::Int(i) => format!("{:>20}", i),
+            HeaderValue::Float(f) => format!("{:>20}", format!("{:.12E}", f)),
+            HeaderValue::Str(s) => format!("'{:<8}'", s.replace('\'', "''")),
+        }
+    }
+
+    // Parses the value field of a card, everything after the "= "
+    fn parse(s: &str) -> Option<HeaderValue> {
+        let s = s.trim_start();
+        if let Some(quoted) = s.strip_prefix('\'') {
+            // Quotes within a string are doubled
+            let mut value = String::new();
+            let mut chars = quoted.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c == '\'' {
+                    if chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        return Some(HeaderValue::Str(value.trim_end().to_string()));
+                    }
+                }
+                value.push(c);
+            }
+            return None;
+        }
+
+        let value = s.split('/').next()?.trim();
+        match value {
+            "T" => Some(HeaderValue::Bool(true)),
+            "F" => Some(HeaderValue::Bool(false)),
+            _ => match value.parse::<i64>() {
+                Ok(i) => Some(HeaderValue::Int(i)),
+                Err(_) => value
+                    .replace('D', "E")
+                    .parse::<f64>()
+                    .ok()
+                    .map(HeaderValue::Float),
+            },
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            HeaderValue::Int(i) => Some(*i as f64),
+            HeaderValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            HeaderValue::Str(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            HeaderValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+// Keywords describing the data layout. These are written from the image itself rather than
+// carried over in a `Header`.
+fn is_structural_keyword(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "SIMPLE" | "BITPIX" | "NAXIS" | "EXTEND" | "BSCALE" | "BZERO" | "END"
+    ) || (keyword.starts_with("NAXIS") && keyword[5..].chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Keyword cards written after the mandatory structural keywords
+#[derive(Debug, Clone, Default)]
+pub struct Header {
+    cards: Vec<(String, HeaderValue, Option<String>)>,
+}
+
+impl Header {
+    pub fn new() -> Header {
+        Header::default()
+    }
+
+    /// Sets a keyword, replacing any existing value. Keywords are limited to eight
+    /// characters and are upper cased.
+    pub fn set(&mut self, keyword: &str, value: HeaderValue, comment: Option<&str>) {
+        let keyword = keyword.to_uppercase();
+        let comment = comment.map(|c| c.to_string());
+        match self.cards.iter_mut().find(|(k, _, _)| *k == keyword) {
+            Some(card) => {
+                card.1 = value;
+                card.2 = comment;
+            }
+            None => self.cards.push((keyword, value, comment)),
+        }
+    }
+
+    pub fn get(&self, keyword: &str) -> Option<&HeaderValue> {
+        let keyword = keyword.to_uppercase();
+        self.cards
+            .iter()
+            .find(|(k, _, _)| *k == keyword)
+            .map(|(_, v, _)| v)
+    }
+
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.cards.iter().map(|(k, _, _)| k.as_str())
+    }
+
+    /// Sets every keyword of `other` in this header
+    pub fn extend(&mut self, other: &Header) {
+        for (keyword, value, comment) in other.cards.iter() {
+            self.set(keyword, value.clone(), comment.as_deref());
+        }
+    }
+}
+
+fn card(keyword: &str, value: &HeaderValue, comment: Option<&str>) -> Result<String> {
+    if keyword.len() > 8 {
+        return Err(anyhow!("FITS keyword too long: {}", keyword));
+    }
+    let mut card = format!("{:<8}= {}", keyword, value.format());
+    if let Some(comment) = comment {
+        card = format!("{} / {}", card, comment);
+    }
+    if !card.is_ascii() {
+        return Err(anyhow!("FITS header card is not ASCII: {}", card));
+    }
+    card.truncate(CARD_SIZE);
+    Ok(format!("{:<80}", card))
+}
+
+/// Writes one or more equally sized bands as a 32-bit floating point FITS primary image.
+/// Rows are written bottom up so that images display upright in FITS viewers.
+pub fn save_fits(bands: &[&ImageBuffer], header: &Header, output_file: &str) -> Result<()> {
+    let first = bands
+        .first()
+        .ok_or_else(|| anyhow!("No image bands to write"))?;
+    if bands
+        .iter()
+        .any(|b| b.width != first.width || b.height != first.height)
+    {
+        return Err(anyhow!("Image bands differ in size"));
+    }
+
+    let mut cards = vec![
+        card("SIMPLE", &HeaderValue::Bool(true), Some("Standard FITS"))?,
+        card(
+            "BITPIX",
+            &HeaderValue::Int(-32),
+            Some("32-bit floating point"),
+        )?,
+        card(
+            "NAXIS",
+            &HeaderValue::Int(if bands.len() > 1 { 3 } else { 2 }),
+            None,
+        )?,
+        card("NAXIS1", &HeaderValue::Int(first.width as i64), None)?,
+        card("NAXIS2", &HeaderValue::Int(first.height as i64), None)?,
+    ];
+    if bands.len() > 1 {
+        cards.push(card("NAXIS3", &HeaderValue::Int(bands.len() as i64), None)?);
+    }
+    for (keyword, value, comment) in header.cards.iter() {
+        cards.push(card(keyword, value, comment.as_deref())?);
+    }
+    cards.push(format!("{:<80}", "END"));
+
+    let mut header_bytes = cards.concat().into_bytes();
+    header_bytes.resize(header_bytes.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, b' ');
+
+    let mut data: Vec<u8> = Vec::with_capacity(bands.len() * first.buffer.len() * 4);
+    for band in bands.iter() {
+        for y in (0..band.height).rev() {
+            for x in 0..band.width {
+                data.extend_from_slice(&band.get(x, y).to_be_bytes());
+            }
+        }
+    }
+    data.resize(data.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+
+    vprintln!("Writing FITS to {}", output_file);
+    let mut writer = BufWriter::new(File::create(output_file)?);
+    writer.write_all(&header_bytes)?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// True if the file name has a FITS extension
+pub fn is_fits_file(file_path: &str) -> bool {
+    let lower = file_path.to_lowercase();
+    lower.ends_with(".fits") || lower.ends_with(".fit") || lower.ends_with(".fts")
+}
+
+/// Header keywords describing an observation, taken from its metadata
+pub fn metadata_header(md: &Metadata) -> Header {
+    let mut header = Header::new();
+    let date_format = "%Y-%m-%dT%H:%M:%S%.3f";
+
+    header.set(
+        "TELESCOP",
+        HeaderValue::Str(md.instrument_host_name.clone()),
+        None,
+    );
+    header.set(
+        "INSTRUME",
+        HeaderValue::Str(md.instrument_name.clone()),
+        None,
+    );
+    if let Some(target) = &md.target_name {
+        header.set("OBJECT", HeaderValue::Str(target.clone()), None);
+    }
+    if let Ok(id) = md.product_id() {
+        header.set(
+            "PRODID",
+            HeaderValue::Str(id.to_string()),
+            Some("Product ID"),
+        );
+    }
+    header.set(
+        "DATE-OBS",
+        HeaderValue::Str(md.start_time.format(date_format).to_string()),
+        Some("Start time (UTC)"),
+    );
+    header.set(
+        "DATE-END",
+        HeaderValue::Str(md.stop_time.format(date_format).to_string()),
+        Some("Stop time (UTC)"),
+    );
+    header.set(
+        "EXPTIME",
+        HeaderValue::Float(md.exposure_duration as f64 / 1000.0),
+        Some("Exposure duration (s)"),
+    );
+    header.set(
+        "INTFRDLY",
+        HeaderValue::Float(md.interframe_delay as f64),
+        Some("Interframe delay (s)"),
+    );
+
+    let filters: Vec<&str> = [
+        (md.filters.blue, "BLUE"),
+        (md.filters.green, "GREEN"),
+        (md.filters.red, "RED"),
+        (md.filters.methane, "METHANE"),
+    ]
+    .iter()
+    .filter(|(present, _)| *present)
+    .map(|(_, name)| *name)
+    .collect();
+    header.set("FILTER", HeaderValue::Str(filters.join(",")), None);
+
+    header.set("PJ", HeaderValue::Str(md.pj.clone()), Some("Perijove"));
+    header.set(
+        "SCALT",
+        HeaderValue::Float(md.spacecraft_altitude as f64),
+        Some("Spacecraft altitude (km)"),
+    );
+    header.set(
+        "SUBSCLAT",
+        HeaderValue::Float(md.sub_spacecraft_latitude as f64),
+        Some("Sub-spacecraft latitude (deg)"),
+    );
+    header.set(
+        "SUBSCLON",
+        HeaderValue::Float(md.sub_spacecraft_longitude as f64),
+        Some("Sub-spacecraft longitude (deg)"),
+    );
+    header.set(
+        "SOLDIST",
+        HeaderValue::Float(md.solar_distance as f64),
+        Some("Distance to the Sun (km)"),
+    );
+    header.set(
+        "FPTEMP",
+        HeaderValue::Float(md.focal_plane_temperature as f64),
+        Some("Focal plane temperature (K)"),
+    );
+    header
+}
+
+/// Image bands and non-structural header keywords read from a FITS primary image
+pub struct FitsImage {
+    pub bands: Vec<ImageBuffer>,
+    pub header: Header,
+}
+
+impl FitsImage {
+    pub fn open(file_path: &str) -> Result<FitsImage> {
+        if !path::file_exists(file_path) {
+            return Err(anyhow!("File not found: {}", file_path));
+        }
+        vprintln!("Reading FITS from {}", file_path);
+        FitsImage::from_bytes(&fs::read(file_path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<FitsImage> {
+        let mut header = Header::new();
+        let mut layout = Header::new();
+        let mut header_end = None;
+
+        for (i, card) in bytes.chunks(CARD_SIZE).enumerate() {
+            let card =
+                std::str::from_utf8(card).map_err(|_| anyhow!("FITS header is not ASCII"))?;
+            let keyword = card.get(..8).unwrap_or(card).trim_end();
+            if keyword == "END" {
+                header_end = Some((i + 1) * CARD_SIZE);
+                break;
+            }
+            if card.get(8..10) != Some("= ") {
+                continue; // COMMENT, HISTORY and blank cards
+            }
+            let value = HeaderValue::parse(&card[10..])
+                .ok_or_else(|| anyhow!("Invalid FITS header card: {}", card))?;
+            if is_structural_keyword(keyword) {
+                layout.set(keyword, value, None);
+            } else {
+                header.set(keyword, value, None);
+            }
+        }
+
+        let header_end = header_end.ok_or_else(|| anyhow!("FITS header has no END card"))?;
+        let data_start = header_end.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+
+        let layout_int = |keyword: &str| -> Result<i64> {
+            layout
+                .get(keyword)
+                .and_then(|v| v.as_f64())
+                .map(|v| v as i64)
+                .ok_or_else(|| anyhow!("FITS header missing {}", keyword))
+        };
+
+        let bitpix = layout_int("BITPIX")?;
+        let naxis = layout_int("NAXIS")?;
+        if !(2..=3).contains(&naxis) {
+            return Err(anyhow!("Unsupported FITS dimensions: NAXIS = {}", naxis));
+        }
+        let width = layout_int("NAXIS1")? as usize;
+        let height = layout_int("NAXIS2")? as usize;
+        let band_count = if naxis == 3 {
+            layout_int("NAXIS3")? as usize
+        } else {
+            1
+        };
+        let bscale = layout.get("BSCALE").and_then(|v| v.as_f64()).unwrap_or(1.0);
+        let bzero = layout.get("BZERO").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+        let sample_size = (bitpix.unsigned_abs() / 8) as usize;
+        let band_size = width * height * sample_size;
+        let data = bytes
+            .get(data_start..data_start + band_size * band_count)
+            .ok_or_else(|| anyhow!("FITS data is truncated"))?;
+
+        let sample = |b: &[u8]| -> Result<f64> {
+            Ok(match bitpix {
+                8 => b[0] as f64,
+                16 => i16::from_be_bytes([b[0], b[1]]) as f64,
+                32 => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                -32 => f32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                -64 => f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]),
+                _ => return Err(anyhow!("Unsupported FITS BITPIX: {}", bitpix)),
+            })
+        };
+
+        let mut bands = vec![];
+        for band_data in data.chunks(band_size) {
+            let mut buffer = ImageBuffer::new(width, height)?;
+            for (i, b) in band_data.chunks(sample_size).enumerate() {
+                // Rows are stored bottom up
+                let value = sample(b)? * bscale + bzero;
+                buffer.put(i % width, height - 1 - i / width, value as f32);
+            }
+            bands.push(buffer);
+        }
+
+        Ok(FitsImage { bands, header })
+    }
+}
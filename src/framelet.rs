@@ -0,0 +1,191 @@
+use crate::{constants, enums, radiometry::Radiometry, strip::Strip};
+
+use anyhow::anyhow;
+use anyhow::Result;
+use sciimg::imagebuffer::ImageBuffer;
+
+/// The strips read out together in one exposure, one for each filter the image was taken
+/// through. Color images have three: a triplet.
+pub struct FrameletSet {
+    pub buffer: ImageBuffer,
+    pub channels: Vec<Strip>, // Will need timing & pointing
+
+    // Cut short by the end of the image and padded out
+    partial: bool,
+}
+
+/// Strip order of a color triplet
+pub const RGB_CAMERAS: [enums::Camera; 3] = [
+    enums::Camera::BLUE,
+    enums::Camera::GREEN,
+    enums::Camera::RED,
+];
+
+impl FrameletSet {
+    /// Splits a color triplet
+    pub fn new_from_imagebuffer(buffer: &ImageBuffer) -> Result<FrameletSet> {
+        FrameletSet::new_from_imagebuffer_with_cameras(buffer, &RGB_CAMERAS)
+    }
+
+    /// Splits a framelet set into one equal height strip for each camera, top to bottom
+    pub fn new_from_imagebuffer_with_cameras(
+        buffer: &ImageBuffer,
+        cameras: &[enums::Camera],
+    ) -> Result<FrameletSet> {
+        let strip_height = buffer.height / cameras.len().max(1);
+        let mut channels = Vec::with_capacity(cameras.len());
+        for (i, camera) in cameras.iter().enumerate() {
+            let data = buffer.get_slice(i * strip_height, strip_height)?;
+            channels.push(Strip::new_from_imagebuffer(&data, *camera)?);
+        }
+
+        Ok(FrameletSet {
+            buffer: buffer.clone(),
+            channels,
+            partial: false,
+        })
+    }
+
+    /// Splits a framelet set cut short by the end of the image, holding `lines` lines of
+    /// data. Strips, or parts of them, past the end are marked as gaps.
+    pub fn new_partial(
+        buffer: &ImageBuffer,
+        cameras: &[enums::Camera],
+        lines: usize,
+    ) -> Result<FrameletSet> {
+        let mut set = FrameletSet::new_from_imagebuffer_with_cameras(buffer, cameras)?;
+        set.channels
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, s)| s.mark_gaps_from(lines.saturating_sub(i * s.buffer.height)));
+        set.partial = true;
+        Ok(set)
+    }
+
+    /// Marks lines that are entirely zero as gaps in each strip. Returns the number of gap
+    /// lines across all strips.
+    pub fn detect_gaps(&mut self) -> usize {
+        self.channels.iter_mut().map(|s| s.detect_gaps()).sum()
+    }
+
+    /// True if the set was cut short by the end of the image
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// True if any strip has gaps
+    pub fn is_incomplete(&self) -> bool {
+        self.channels.iter().any(|s| s.gap_line_count() > 0)
+    }
+
+    /// Cameras of the strips, top to bottom
+    pub fn cameras(&self) -> Vec<enums::Camera> {
+        self.channels.iter().map(|s| s.camera).collect()
+    }
+
+    pub fn paste_into(&self, into: &mut ImageBuffer, y: usize) -> Result<&'static str> {
+        if self.channels.is_empty() {
+            return Err(anyhow!("Empty data, cannot paste"));
+        }
+        self.channels
+            .iter()
+            .enumerate()
+            .for_each(|(i, s)| s.paste_into(into, y + i * s.buffer.height));
+
+        Ok("ok")
+    }
+
+    pub fn apply_darknoise(&mut self) -> Result<&'static str> {
+        for i in self.channels.iter_mut() {
+            match i.apply_darknoise() {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(constants::status::OK)
+    }
+
+    pub fn apply_radiometric(
+        &mut self,
+        radiometry: &Radiometry,
+        exposure_ms: f64,
+        solar_distance_km: f64,
+    ) -> Result<&'static str> {
+        for i in self.channels.iter_mut() {
+            match i.apply_radiometric(radiometry, exposure_ms, solar_distance_km) {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(constants::status::OK)
+    }
+
+    pub fn infill(&mut self) -> Result<&'static str> {
+        for i in self.channels.iter_mut() {
+            match i.infill() {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(constants::status::OK)
+    }
+
+    pub fn apply_hot_pixel_correction(
+        &mut self,
+        window_size: i32,
+        threshold: f32,
+    ) -> Result<&'static str> {
+        for i in self.channels.iter_mut() {
+            match i.apply_hot_pixel_correction(window_size, threshold) {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(constants::status::OK)
+    }
+
+    pub fn decompand(&mut self, ilttype: enums::SampleBitMode) -> Result<&'static str> {
+        for i in self.channels.iter_mut() {
+            match i.decompand(ilttype) {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(constants::status::OK)
+    }
+
+    /// Scales the color strips. Methane is left as it is.
+    pub fn apply_weights(
+        &mut self,
+        red_weight: f32,
+        green_weight: f32,
+        blue_weight: f32,
+    ) -> Result<&'static str> {
+        for strip in self.channels.iter_mut() {
+            let weight = match strip.camera {
+                enums::Camera::RED => red_weight,
+                enums::Camera::GREEN => green_weight,
+                enums::Camera::BLUE => blue_weight,
+                _ => continue,
+            };
+            strip.apply_weight(weight)?;
+        }
+
+        Ok(constants::status::OK)
+    }
+}
@@ -0,0 +1,82 @@
+use sciimg::imagebuffer::ImageBuffer;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    Nearest,
+    Bilinear,
+    Bicubic,
+}
+
+impl Interpolation {
+    pub fn from(s: &str) -> Option<Interpolation> {
+        match s.to_lowercase().as_str() {
+            "nearest" => Some(Interpolation::Nearest),
+            "bilinear" => Some(Interpolation::Bilinear),
+            "bicubic" => Some(Interpolation::Bicubic),
+            _ => None,
+        }
+    }
+
+    /// Samples the buffer at a fractional pixel location. Neighbors that fall off the edge
+    /// of the buffer are clamped to the nearest valid row/column.
+    pub fn sample(&self, buffer: &ImageBuffer, x: f64, y: f64) -> f32 {
+        match self {
+            Interpolation::Nearest => get_clamped(buffer, x.round() as i64, y.round() as i64),
+            Interpolation::Bilinear => sample_bilinear(buffer, x, y),
+            Interpolation::Bicubic => sample_bicubic(buffer, x, y),
+        }
+    }
+}
+
+fn get_clamped(buffer: &ImageBuffer, x: i64, y: i64) -> f32 {
+    let x = x.clamp(0, buffer.width as i64 - 1) as usize;
+    let y = y.clamp(0, buffer.height as i64 - 1) as usize;
+    buffer.get(x, y)
+}
+
+fn sample_bilinear(buffer: &ImageBuffer, x: f64, y: f64) -> f32 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let xf = (x - x0) as f32;
+    let yf = (y - y0) as f32;
+    let x0 = x0 as i64;
+    let y0 = y0 as i64;
+
+    let tl = get_clamped(buffer, x0, y0);
+    let tr = get_clamped(buffer, x0 + 1, y0);
+    let bl = get_clamped(buffer, x0, y0 + 1);
+    let br = get_clamped(buffer, x0 + 1, y0 + 1);
+
+    let top = tl + (tr - tl) * xf;
+    let bottom = bl + (br - bl) * xf;
+    top + (bottom - top) * yf
+}
+
+// Catmull-Rom cubic through p1 and p2 at fraction t
+fn cubic(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    p1 + 0.5
+        * t
+        * (p2 - p0 + t * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3 + t * (3.0 * (p1 - p2) + p3 - p0)))
+}
+
+fn sample_bicubic(buffer: &ImageBuffer, x: f64, y: f64) -> f32 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let xf = (x - x0) as f32;
+    let yf = (y - y0) as f32;
+    let x0 = x0 as i64;
+    let y0 = y0 as i64;
+
+    let mut rows = [0.0_f32; 4];
+    for (i, row) in rows.iter_mut().enumerate() {
+        let yy = y0 - 1 + i as i64;
+        *row = cubic(
+            get_clamped(buffer, x0 - 1, yy),
+            get_clamped(buffer, x0, yy),
+            get_clamped(buffer, x0 + 1, yy),
+            get_clamped(buffer, x0 + 2, yy),
+            xf,
+        );
+    }
+    cubic(rows[0], rows[1], rows[2], rows[3], yf)
+}
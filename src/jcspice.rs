@@ -1,7 +1,8 @@
 use spice;
 
+use crate::error::{JunocamError, JunocamResult};
 use crate::{config, filelocate, veprintln, vprintln};
-use sciimg::matrix::Matrix;
+use sciimg::{matrix::Matrix, vector::Vector};
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -50,18 +51,13 @@ pub fn furnish(kernel_path: &str) -> Result<&str> {
     }
 }
 
-pub fn furnish_base() {
-    match config::load_configuration() {
-        Ok(c) => {
-            for k in c.spice.kernels {
-                furnish(k.as_str()).expect("Failed to load spice kernel");
-            }
-        }
-        Err(why) => {
-            eprintln!("Failed to load configuration file: {}", why);
-            panic!("Failed to load configuration file prior to base kernel loading");
-        }
+pub fn furnish_base() -> JunocamResult<()> {
+    let c = config::load_configuration()?;
+    for k in c.spice.kernels {
+        furnish(k.as_str())
+            .map_err(|why| JunocamError::Spice(format!("Failed to load {}: {}", k, why)))?;
     }
+    Ok(())
 }
 
 fn kernel_name_date_to_et(name_date: &String) -> Result<f64> {
@@ -89,9 +85,13 @@ fn kernel_name_nth_part(ck_file: &String, n: usize) -> Option<String> {
     }
 }
 
-fn get_kernel_range_et(ck_file: &String) -> Option<(f64, f64)> {
-    let start_date_s = kernel_name_nth_part(ck_file, 3).unwrap();
-    let end_date_s = kernel_name_nth_part(ck_file, 4).unwrap();
+fn get_kernel_range_et(
+    kernel_file: &String,
+    start_part: usize,
+    end_part: usize,
+) -> Option<(f64, f64)> {
+    let start_date_s = kernel_name_nth_part(kernel_file, start_part).unwrap();
+    let end_date_s = kernel_name_nth_part(kernel_file, end_part).unwrap();
 
     let kernel_start_et = kernel_name_date_to_et(&start_date_s).unwrap();
     let kernel_end_et = kernel_name_date_to_et(&end_date_s).unwrap();
@@ -99,18 +99,37 @@ fn get_kernel_range_et(ck_file: &String) -> Option<(f64, f64)> {
     Some((kernel_start_et, kernel_end_et))
 }
 
+// CK kernels are named like juno_sc_rec_220814_220820_v01.bc
 pub fn find_kernel_with_date(search_pattern: &String, time_et: f64) -> Result<String> {
+    find_kernel_with_date_parts(search_pattern, time_et, 3, 4)
+}
+
+// SPK kernels are named like spk_rec_220728_220909_220913.bsp
+pub fn find_spk_with_date(search_pattern: &String, time_et: f64) -> Result<String> {
+    find_kernel_with_date_parts(search_pattern, time_et, 2, 3)
+}
+
+fn find_kernel_with_date_parts(
+    search_pattern: &String,
+    time_et: f64,
+    start_part: usize,
+    end_part: usize,
+) -> Result<String> {
     match option_env!("JUNOBASE") {
         Some(v) => {
             let abs_search_pattern = format!("{}/{}", v, search_pattern);
             vprintln!("spice search pattern: {}", abs_search_pattern);
 
-            for entry in glob(&abs_search_pattern).expect("Failed to read glob pattern") {
+            let entries = glob(&abs_search_pattern)
+                .map_err(|e| anyhow!("Invalid kernel pattern {}: {}", search_pattern, e))?;
+            for entry in entries {
                 match entry {
                     Ok(path) => {
-                        if let Some(range) =
-                            get_kernel_range_et(&path.to_str().unwrap().to_string())
-                        {
+                        if let Some(range) = get_kernel_range_et(
+                            &path.to_str().unwrap().to_string(),
+                            start_part,
+                            end_part,
+                        ) {
                             if range.0 <= time_et && time_et <= (range.1 + 86400.0) {
                                 return Ok(path.to_str().unwrap().to_string());
                             }
@@ -149,3 +168,20 @@ pub fn pos_transform_matrix(from: &str, to: &str, et: f64) -> Matrix {
     let mtx = spice::pxform(from, to, et);
     Matrix::from_3x3(&mtx)
 }
+
+/// Reference ellipsoid radii (km) for a body, from the loaded PCK
+pub fn body_radii(body: &str) -> Result<[f64; 3]> {
+    let (dim, values) = spice::bodvrd(body, "RADII", 3);
+    if dim != 3 || values.len() < 3 {
+        return Err(anyhow!("Invalid radii for body {}", body));
+    }
+    Ok([values[0], values[1], values[2]])
+}
+
+/// Position (km) of the observer relative to the target's center in the target's
+/// body-fixed frame, as seen from the target at `et`. Corrected for light time and stellar
+/// aberration, with the frame evaluated at `et` rather than a light time earlier.
+pub fn observer_position_body_fixed(observer: &str, target: &str, frame: &str, et: f64) -> Vector {
+    let (position, _) = spice::spkpos(observer, et, frame, "LT+S", target);
+    Vector::new(position[0], position[1], position[2])
+}
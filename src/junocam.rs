@@ -8,6 +8,7 @@ use sciimg::vector::Vector;
       fl = INS-6150#_FOCAL_LENGTH/INS-6150#_PIXEL_SIZE
 */
 
+#[derive(Debug, Clone, Copy)]
 pub struct FrameletParameters {
     pub id: i32,
     cx: f64,
@@ -23,6 +24,21 @@ impl FrameletParameters {
         self.focal_length / self.pixel_size
     }
 
+    /// Parameters for images with `sampling_factor` pixels summed along each axis. The
+    /// optical center and focal length shrink with the pixel coordinates, while the
+    /// distortion coefficients grow to keep the same distortion at each radius.
+    pub fn summed(&self, sampling_factor: f64) -> FrameletParameters {
+        FrameletParameters {
+            id: self.id,
+            cx: self.cx / sampling_factor,
+            cy: self.cy / sampling_factor,
+            k1: self.k1 * sampling_factor.powi(2),
+            k2: self.k2 * sampling_factor.powi(4),
+            focal_length: self.focal_length,
+            pixel_size: self.pixel_size * sampling_factor,
+        }
+    }
+
     /*
         def undistort(c):
             xd, yd = c[0], c[1]
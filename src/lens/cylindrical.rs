@@ -1,13 +1,28 @@
 use crate::lens::lens::Lens;
+use crate::projection::projection::{lon_span, unwrap_right_lon};
 use sciimg::drawable::Point;
 use sciimg::vector::Vector;
 
+/// Latitude and longitude, in degrees, of a look direction on the celestial sphere
+/// surrounding the spacecraft. Longitude runs 0 to 360.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct LatLon {
-    lat: f64,
-    lon: f64,
+    pub lat: f64,
+    pub lon: f64,
 }
 
-trait VectorToCylindrical {
+impl LatLon {
+    /// Unit look direction for this latitude/longitude. Inverse of `to_cylindrical()`.
+    pub fn to_vector(&self) -> Vector {
+        // Undo the 180 degree offset applied in to_cylindrical()
+        let lat = self.lat.to_radians();
+        let lon = (self.lon - 180.0).to_radians();
+
+        Vector::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+    }
+}
+
+pub trait VectorToCylindrical {
     fn to_cylindrical(&self) -> LatLon;
     fn to_xy(
         &self,
@@ -17,7 +32,7 @@ trait VectorToCylindrical {
         right_lon: f64,
         image_width: usize,
         image_height: usize,
-    ) -> Point;
+    ) -> Option<Point>;
 }
 
 impl VectorToCylindrical for Vector {
@@ -39,31 +54,26 @@ impl VectorToCylindrical for Vector {
         right_lon: f64,
         image_width: usize,
         image_height: usize,
-    ) -> Point {
+    ) -> Option<Point> {
         let ll = self.to_cylindrical();
 
-        let lat = ll.lat;
-        let lon = ll.lon;
+        // Longitude runs east from the box's left edge, wrapping through 0/360 when the
+        // right edge is the smaller of the two
+        let span = lon_span(left_lon, right_lon, false);
+        let lon_offset = (ll.lon - left_lon).rem_euclid(360.0);
 
-        let mut out_y_f = (lat - bottom_lat) / (top_lat - bottom_lat) * image_height as f64;
-        let mut out_x_f = (lon - left_lon) / (right_lon - left_lon) * image_width as f64;
+        let out_y_f = (ll.lat - bottom_lat) / (top_lat - bottom_lat) * image_height as f64;
+        let out_x_f = lon_offset / span * image_width as f64;
 
-        while out_y_f < 0.0 {
-            out_y_f += image_height as f64;
+        if out_x_f < 0.0
+            || out_y_f < 0.0
+            || out_x_f > image_width as f64
+            || out_y_f > image_height as f64
+        {
+            None
+        } else {
+            Some(Point::create_rgb(out_x_f, out_y_f, 0.0, 0.0, 0.0))
         }
-
-        while out_y_f >= image_height as f64 {
-            out_y_f -= image_height as f64;
-        }
-
-        while out_x_f < 0.0 {
-            out_x_f += image_width as f64;
-        }
-
-        while out_x_f >= image_width as f64 {
-            out_x_f -= image_width as f64;
-        }
-        Point::create_rgb(out_x_f, out_y_f, 0.0, 0.0, 0.0)
     }
 }
 
@@ -91,13 +101,21 @@ impl CylindricalLens {
             top_lat,
             bottom_lat,
             left_lon,
-            right_lon,
+            right_lon: unwrap_right_lon(left_lon, right_lon, false),
+        }
+    }
+
+    /// Latitude and longitude under an image pixel location
+    pub fn point_to_lat_lon(&self, x: f64, y: f64) -> LatLon {
+        LatLon {
+            lat: self.bottom_lat + y / self.image_height as f64 * (self.top_lat - self.bottom_lat),
+            lon: self.left_lon + x / self.image_width as f64 * (self.right_lon - self.left_lon),
         }
     }
 }
 
 impl Lens for CylindricalLens {
-    fn vector_to_point(&self, v: &Vector) -> Point {
+    fn vector_to_point(&self, v: &Vector) -> Option<Point> {
         v.to_xy(
             self.top_lat,
             self.bottom_lat,
@@ -107,4 +125,11 @@ impl Lens for CylindricalLens {
             self.image_height,
         )
     }
+
+    fn point_to_vector(&self, x: f64, y: f64) -> Option<Vector> {
+        if x < 0.0 || y < 0.0 || x >= self.image_width as f64 || y >= self.image_height as f64 {
+            return None;
+        }
+        Some(self.point_to_lat_lon(x, y).to_vector())
+    }
 }
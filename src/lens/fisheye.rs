@@ -28,14 +28,8 @@ impl FisheyeEquisolidLens {
     }
 }
 
-impl Lens for FisheyeEquisolidLens {
-    fn vector_to_point(&self, v: &Vector) -> Point {
-        let r = (v.y * v.y + v.z * v.z).sqrt().atan2(v.x) / self.field_of_view.to_radians();
-        let phi = v.z.atan2(v.y);
-
-        let u = r * phi.cos() + 0.5;
-        let v = r * phi.sin() + 0.5;
-
+impl FisheyeEquisolidLens {
+    fn field_width_and_adjust(&self) -> (f64, f64, f64) {
         let field_width = min!(self.image_width, self.image_height);
         let x_adjust = if self.image_width > self.image_height {
             (self.image_width - self.image_height) / 2
@@ -48,13 +42,45 @@ impl Lens for FisheyeEquisolidLens {
         } else {
             0
         };
+        (field_width as f64, x_adjust as f64, y_adjust as f64)
+    }
+}
+
+impl Lens for FisheyeEquisolidLens {
+    fn vector_to_point(&self, v: &Vector) -> Option<Point> {
+        let r = (v.y * v.y + v.z * v.z).sqrt().atan2(v.x) / self.field_of_view.to_radians();
+        let phi = v.z.atan2(v.y);
+
+        let u = r * phi.cos() + 0.5;
+        let v = r * phi.sin() + 0.5;
+
+        let (field_width, x_adjust, y_adjust) = self.field_width_and_adjust();
 
-        Point::create_rgb(
-            u as f64 * (field_width as f64) + x_adjust as f64,
-            (1.0 - v as f64) * (field_width as f64) + y_adjust as f64,
+        Some(Point::create_rgb(
+            u * field_width + x_adjust,
+            (1.0 - v) * field_width + y_adjust,
             0.0,
             0.0,
             0.0,
-        )
+        ))
+    }
+
+    fn point_to_vector(&self, x: f64, y: f64) -> Option<Vector> {
+        let (field_width, x_adjust, y_adjust) = self.field_width_and_adjust();
+
+        let u = (x - x_adjust) / field_width - 0.5;
+        let v = 1.0 - (y - y_adjust) / field_width - 0.5;
+
+        let theta = (u * u + v * v).sqrt() * self.field_of_view.to_radians();
+        if theta > std::f64::consts::PI {
+            return None;
+        }
+        let phi = v.atan2(u);
+
+        Some(Vector::new(
+            theta.cos(),
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+        ))
     }
 }
@@ -1,6 +1,12 @@
 use sciimg::drawable::Point;
 use sciimg::vector::Vector;
 
-pub trait Lens {
-    fn vector_to_point(&self, v: &Vector) -> Point;
+pub trait Lens: Sync + Send {
+    /// Projects a look direction onto the output image, or None if it falls outside of the
+    /// area the lens covers.
+    fn vector_to_point(&self, v: &Vector) -> Option<Point>;
+
+    /// Inverse of `vector_to_point`. Returns the unit look direction for an output image
+    /// location, or None if the location does not map to a direction.
+    fn point_to_vector(&self, x: f64, y: f64) -> Option<Vector>;
 }
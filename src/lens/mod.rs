@@ -1,5 +1,6 @@
 pub mod cylindrical;
 pub mod fisheye;
+pub mod perspective;
 
 #[allow(clippy::module_inception)]
 pub mod lens;
@@ -0,0 +1,80 @@
+use crate::lens::lens::Lens;
+use sciimg::drawable::Point;
+use sciimg::vector::Vector;
+
+/// Rectilinear (pinhole/gnomonic) lens. Straight lines stay straight, which makes for a
+/// more natural view than the fisheye at moderate fields of view. The frame is fit within
+/// the output image at the requested aspect ratio and centered, with the horizontal field
+/// of view spanning its width.
+pub struct PerspectiveLens {
+    image_width: usize,
+    image_height: usize,
+    field_of_view: f64,
+    aspect_ratio: f64,
+}
+
+impl PerspectiveLens {
+    pub fn new(
+        image_width: usize,
+        image_height: usize,
+        field_of_view: f64,
+        aspect_ratio: f64,
+    ) -> PerspectiveLens {
+        PerspectiveLens {
+            image_width,
+            image_height,
+            field_of_view,
+            aspect_ratio,
+        }
+    }
+
+    /// Width and height of the frame, in pixels
+    fn frame_size(&self) -> (f64, f64) {
+        let width = self.image_width as f64;
+        let height = self.image_height as f64;
+        if width / height > self.aspect_ratio {
+            (height * self.aspect_ratio, height)
+        } else {
+            (width, width / self.aspect_ratio)
+        }
+    }
+
+    /// Focal length in pixels
+    fn focal_length(&self) -> f64 {
+        let (frame_width, _) = self.frame_size();
+        frame_width / 2.0 / (self.field_of_view.to_radians() / 2.0).tan()
+    }
+}
+
+impl Lens for PerspectiveLens {
+    fn vector_to_point(&self, v: &Vector) -> Option<Point> {
+        let cx = self.image_width as f64 / 2.0;
+        let cy = self.image_height as f64 / 2.0;
+
+        // Behind the camera
+        if v.x <= 0.0 {
+            return None;
+        }
+
+        let f = self.focal_length();
+        Some(Point::create_rgb(
+            cx + v.y / v.x * f,
+            cy - v.z / v.x * f,
+            0.0,
+            0.0,
+            0.0,
+        ))
+    }
+
+    fn point_to_vector(&self, x: f64, y: f64) -> Option<Vector> {
+        let (frame_width, frame_height) = self.frame_size();
+        let u = x - self.image_width as f64 / 2.0;
+        let v = self.image_height as f64 / 2.0 - y;
+
+        if u.abs() > frame_width / 2.0 || v.abs() > frame_height / 2.0 {
+            return None;
+        }
+
+        Some(Vector::new(self.focal_length(), u, v).normalized())
+    }
+}
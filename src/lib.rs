@@ -5,19 +5,34 @@ extern crate lazy_static;
 
 pub mod print;
 
+pub mod backplane;
+pub mod bundle;
 pub mod cache;
 pub mod calibration;
 pub mod config;
 pub mod constants;
+pub mod coverage;
 pub mod decompanding;
+pub mod ellipsoid;
 pub mod enums;
+pub mod error;
 pub mod filelocate;
+pub mod fits;
+pub mod framelet;
+pub mod interpolate;
 pub mod jcspice;
 pub mod junocam;
 pub mod lens;
 pub mod metadata;
+pub mod mosaic;
+pub mod output;
+pub mod pairing;
+pub mod pds;
+pub mod photometry;
 pub mod process;
+pub mod projection;
+pub mod radiometry;
 pub mod rawimage;
 pub mod rawset;
+pub mod render;
 pub mod strip;
-pub mod triplet;
@@ -1,42 +1,320 @@
-use crate::{constants, enums};
+use crate::{bundle, constants, enums, pds};
 use json;
 
 use sciimg::path;
 
-use anyhow::anyhow;
-use anyhow::Result;
-
 use chrono::prelude::*;
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 
 pub struct Filters {
     pub red: bool,
     pub green: bool,
     pub blue: bool,
-    pub methane: bool, // Gonna ignore methane for now.
+    pub methane: bool,
+
+    // Readout order, as listed in FILTER_NAME
+    order: Vec<enums::Camera>,
 }
 
 impl Filters {
-    pub fn new(r: bool, g: bool, b: bool) -> Self {
+    pub fn new(r: bool, g: bool, b: bool, m: bool) -> Self {
+        let order = [
+            (b, enums::Camera::BLUE),
+            (g, enums::Camera::GREEN),
+            (r, enums::Camera::RED),
+            (m, enums::Camera::METHANE),
+        ]
+        .iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, camera)| *camera)
+        .collect();
         Filters {
             red: r,
             green: g,
             blue: b,
-            methane: false,
+            methane: m,
+            order,
+        }
+    }
+
+    /// Filters in the order they are listed, which is the order their strips appear in
+    /// each framelet set. Returns None for an unknown filter name.
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Option<Self> {
+        let mut order: Vec<enums::Camera> = Vec::new();
+        for name in names.iter() {
+            let camera = match name.as_ref().trim() {
+                constants::filters::RED => enums::Camera::RED,
+                constants::filters::GREEN => enums::Camera::GREEN,
+                constants::filters::BLUE => enums::Camera::BLUE,
+                constants::filters::METHANE => enums::Camera::METHANE,
+                _ => return None,
+            };
+            if !order.contains(&camera) {
+                order.push(camera);
+            }
         }
+
+        Some(Filters {
+            red: order.contains(&enums::Camera::RED),
+            green: order.contains(&enums::Camera::GREEN),
+            blue: order.contains(&enums::Camera::BLUE),
+            methane: order.contains(&enums::Camera::METHANE),
+            order,
+        })
+    }
+
+    /// Cameras in the order their strips appear in each framelet set
+    pub fn cameras(&self) -> Vec<enums::Camera> {
+        self.order.clone()
     }
 }
 
+/// Problems found parsing a product ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProductIdError {
+    /// Not four underscore separated fields
+    Malformed(String),
+
+    /// Neither the EDR (`JNCE`) nor RDR (`JNCR`) prefix
+    Prefix(String),
+
+    /// Year and day of year aren't `YYYYDDD`, or the day is out of range
+    Date(String),
+
+    /// Perijove, mode flag and image number aren't `PPCNNNNN`
+    Sequence(String),
+
+    /// Version isn't `Vnn`
+    Version(String),
+}
+
+impl fmt::Display for ProductIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProductIdError::Malformed(s) => write!(f, "Malformed product ID: '{}'", s),
+            ProductIdError::Prefix(s) => write!(f, "Unknown product ID prefix: '{}'", s),
+            ProductIdError::Date(s) => write!(f, "Invalid product ID date: '{}'", s),
+            ProductIdError::Sequence(s) => {
+                write!(f, "Invalid product ID perijove/image number: '{}'", s)
+            }
+            ProductIdError::Version(s) => write!(f, "Invalid product ID version: '{}'", s),
+        }
+    }
+}
+
+impl std::error::Error for ProductIdError {}
+
+/// A JunoCam product ID, such as `JNCE_2021052_32C00054_V01`: EDR or RDR prefix, year and
+/// day of year, perijove, camera mode flag, image number and version. IDs order by
+/// observation date, then image number and version, with perijove, camera mode and prefix
+/// breaking any remaining tie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProductId {
+    // Field order is the sort order
+    year: u16,
+    day_of_year: u16,
+    image_number: u32,
+    version: u8,
+    perijove: u16,
+    mode: char,
+    prefix: String,
+}
+
+const PRODUCT_ID_PREFIXES: [&str; 2] = ["JNCE", "JNCR"];
+
+impl ProductId {
+    pub fn parse(s: &str) -> Result<ProductId, ProductIdError> {
+        let fields: Vec<&str> = s.split('_').collect();
+        if fields.len() != 4 {
+            return Err(ProductIdError::Malformed(s.to_string()));
+        }
+
+        let prefix = fields[0];
+        if !PRODUCT_ID_PREFIXES.contains(&prefix) {
+            return Err(ProductIdError::Prefix(prefix.to_string()));
+        }
+
+        let date = fields[1];
+        let date_err = || ProductIdError::Date(date.to_string());
+        if date.len() != 7 || !date.chars().all(|c| c.is_ascii_digit()) {
+            return Err(date_err());
+        }
+        let year = date[..4].parse::<u16>().map_err(|_| date_err())?;
+        let day_of_year = date[4..].parse::<u16>().map_err(|_| date_err())?;
+        if NaiveDate::from_yo_opt(year as i32, day_of_year as u32).is_none() {
+            return Err(date_err());
+        }
+
+        // Perijove digits, a single mode letter, then the image number
+        let sequence = fields[2];
+        let sequence_err = || ProductIdError::Sequence(sequence.to_string());
+        let mode_idx = sequence
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(sequence_err)?;
+        let (perijove, rest) = sequence.split_at(mode_idx);
+        let mode = rest.chars().next().ok_or_else(sequence_err)?;
+        let image_number = &rest[1..];
+        if perijove.is_empty()
+            || image_number.len() != 5
+            || !perijove.chars().all(|c| c.is_ascii_digit())
+            || !image_number.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(sequence_err());
+        }
+
+        let version = fields[3];
+        let version_err = || ProductIdError::Version(version.to_string());
+        let version_digits = version.strip_prefix('V').ok_or_else(version_err)?;
+        if version_digits.is_empty() || !version_digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(version_err());
+        }
+
+        Ok(ProductId {
+            year,
+            day_of_year,
+            perijove: perijove.parse().map_err(|_| sequence_err())?,
+            image_number: image_number.parse().map_err(|_| sequence_err())?,
+            version: version_digits.parse().map_err(|_| version_err())?,
+            mode,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// Parses the product ID a file is named for, as in `JNCE_2021052_32C00054_V01-raw.png`
+    /// or `JNCE_2021052_32C00054_V01.IMG`
+    pub fn from_file_name(file_path: &str) -> Result<ProductId, ProductIdError> {
+        let file_name = std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or(file_path);
+        let end = file_name.find(['-', '.']).unwrap_or(file_name.len());
+        ProductId::parse(&file_name[..end])
+    }
+
+    /// `JNCE` for EDRs, `JNCR` for RDRs
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn is_rdr(&self) -> bool {
+        self.prefix == "JNCR"
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn day_of_year(&self) -> u16 {
+        self.day_of_year
+    }
+
+    /// Observation date (UTC)
+    pub fn date(&self) -> NaiveDate {
+        NaiveDate::from_yo_opt(self.year as i32, self.day_of_year as u32).unwrap()
+    }
+
+    pub fn perijove(&self) -> u16 {
+        self.perijove
+    }
+
+    pub fn mode(&self) -> char {
+        self.mode
+    }
+
+    pub fn image_number(&self) -> u32 {
+        self.image_number
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+}
+
+impl FromStr for ProductId {
+    type Err = ProductIdError;
+
+    fn from_str(s: &str) -> Result<ProductId, ProductIdError> {
+        ProductId::parse(s)
+    }
+}
+
+impl fmt::Display for ProductId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}_{:04}{:03}_{:02}{}{:05}_V{:02}",
+            self.prefix,
+            self.year,
+            self.day_of_year,
+            self.perijove,
+            self.mode,
+            self.image_number,
+            self.version
+        )
+    }
+}
+
+/// Why metadata couldn't be loaded, naming the key at fault where there is one
+pub enum MetadataError {
+    FileNotFound(String),
+
+    /// The file or bundle couldn't be read
+    Read(String, String),
+
+    /// Not valid json
+    Json(String),
+
+    /// A key needed for processing is absent, empty or `N/A`
+    MissingKey(String),
+
+    /// A key's value couldn't be parsed
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::FileNotFound(p) => {
+                write!(f, "{}: {}", constants::status::FILE_NOT_FOUND, p)
+            }
+            MetadataError::Read(p, why) => write!(f, "Error reading {}: {}", p, why),
+            MetadataError::Json(why) => {
+                write!(f, "{}: {}", constants::status::ERROR_PARSING_JSON, why)
+            }
+            MetadataError::MissingKey(k) => write!(f, "Metadata is missing {}", k),
+            MetadataError::InvalidValue { key, value } => {
+                write!(f, "Invalid metadata value for {}: {}", key, value)
+            }
+        }
+    }
+}
+
+// Same as Display, so unwrap() failures read like the messages they always have
+impl fmt::Debug for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+/// Observation metadata. The description, stop clock count and token ID are often left
+/// blank, and target name and product ID were not read before, so those are optional.
 pub struct Metadata {
     pub compression_type: String,
     pub data_set_id: String,
-    pub description: String,
-    pub exposure_duration: f32, // Seconds
+    pub description: Option<String>,
+    pub exposure_duration: f32, // Milliseconds
     pub file_name: String,
     pub file_records: u32,
-    pub filters: Filters,             // Derived from FILTER_NAME
-    pub focal_plane_temperature: f32, // Kelvin
-    pub image_time: DateTime<chrono::Utc>,
+    pub filters: Filters,                  // Derived from FILTER_NAME
+    pub focal_plane_temperature: f32,      // Kelvin
+    pub image_time: DateTime<chrono::Utc>, // START_TIME if not given
     pub instrument_host_name: String,
     pub instrument_id: String,
     pub instrument_name: String,
@@ -48,10 +326,11 @@ pub struct Metadata {
     pub line_suffix_bytes: u32,
     pub mission_phase_name: String,
     pub orbit_number: u32,
-    pub pj: String, // String version of orbit_number for some reason
+    pub pj: String, // String version of orbit_number for some reason. ORBIT_NUMBER if not given
     pub processing_level_id: u8,
     pub producer_id: String,
     pub product_creation_time: DateTime<chrono::Utc>,
+    pub product_id: Option<ProductId>,
     pub product_version_id: u8,
     pub rationale_desc: String,
     pub record_bytes: u32,
@@ -64,149 +343,291 @@ pub struct Metadata {
     pub software_name: String,
     pub solar_distance: f32, // kilometers
     pub source_product_id: String,
-    pub spacecraft_altitude: f32,            // kilometers
-    pub spacecraft_clock_start_count: f32,   // seconds
-    pub spacecraft_clock_stop_count: String, // Probably not a string when non-zero (N/A)
+    pub spacecraft_altitude: f32,                    // kilometers
+    pub spacecraft_clock_start_count: f32,           // seconds
+    pub spacecraft_clock_stop_count: Option<String>, // Probably not a string when non-zero (N/A)
     pub spacecaft_name: String,
     pub standard_data_product_id: String,
     pub start_time: DateTime<chrono::Utc>,
     pub stop_time: DateTime<chrono::Utc>,
     pub sub_spacecraft_latitude: f32,
     pub sub_spacecraft_longitude: f32,
+    pub target_name: Option<String>,
     pub title: String,
-    pub token_id: u8, // What even is this?
+    pub token_id: Option<String>, // What even is this?
 }
 
 const DATE_FORMAT_STRING: &str = "%Y-%m-%dT%H:%M:%S%.3f";
 
-fn parse_date(date_str: &str) -> DateTime<chrono::Utc> {
-    Utc.datetime_from_str(date_str, DATE_FORMAT_STRING).unwrap()
+type MetadataResult<T> = Result<T, MetadataError>;
+
+fn parse_date(date_str: &str) -> Option<DateTime<chrono::Utc>> {
+    Utc.datetime_from_str(date_str, DATE_FORMAT_STRING).ok()
 }
 
-fn strip_units(s: &str) -> Result<String> {
-    let idx = s.find('<');
+fn strip_units(s: &str) -> String {
     let r = s.replace(':', ".");
 
-    if let Some(i) = idx {
-        let t = &r[..(i - 1)];
-        Ok(t.to_string())
-    } else {
-        Ok(r)
+    match r.find('<') {
+        Some(i) => r[..i].trim().to_string(),
+        None => r.trim().to_string(),
     }
 }
 
-macro_rules! _S {
-    ($a:expr) => {
-        String::from($a.as_str().unwrap())
+// Absent, null, empty and N/A values are all taken as not given
+fn value<'a>(parsed_json: &'a json::JsonValue, key: &str) -> Option<&'a json::JsonValue> {
+    let v = &parsed_json[key];
+    let blank = match v.as_str() {
+        Some(s) => s.trim().is_empty() || s.trim() == "N/A",
+        None => v.is_null() || (v.is_array() && v.is_empty()),
     };
+    if blank {
+        None
+    } else {
+        Some(v)
+    }
 }
 
-macro_rules! _D {
-    ($a:expr) => {
-        parse_date($a.as_str().unwrap())
-    };
+fn invalid(key: &str, v: &json::JsonValue) -> MetadataError {
+    MetadataError::InvalidValue {
+        key: key.to_string(),
+        value: v.dump(),
+    }
+}
+
+fn required<T>(key: &str, v: Option<T>) -> MetadataResult<T> {
+    v.ok_or_else(|| MetadataError::MissingKey(key.to_string()))
+}
+
+fn opt_string(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<Option<String>> {
+    match value(parsed_json, key) {
+        None => Ok(None),
+        Some(v) if v.is_string() => Ok(v.as_str().map(String::from)),
+        Some(v) if v.is_number() => Ok(Some(v.dump())),
+        Some(v) => Err(invalid(key, v)),
+    }
+}
+
+// FILTER_NAME is a list in missionjuno metadata, but may be a single or comma separated name
+fn filters_from_json(v: &json::JsonValue) -> Option<Filters> {
+    if v.is_array() {
+        let names: Option<Vec<&str>> = v.members().map(|m| m.as_str()).collect();
+        Filters::from_names(&names?)
+    } else {
+        let names: Vec<&str> = v.as_str()?.split(',').collect();
+        Filters::from_names(&names)
+    }
+}
+
+// PDS labels may leave unitless reals unquoted, which come through as numbers
+fn opt_f32(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<Option<f32>> {
+    match value(parsed_json, key) {
+        None => Ok(None),
+        Some(v) => match v.as_f32() {
+            Some(f) => Ok(Some(f)),
+            None => v
+                .as_str()
+                .and_then(|s| strip_units(s).parse::<f32>().ok())
+                .map(Some)
+                .ok_or_else(|| invalid(key, v)),
+        },
+    }
+}
+
+fn opt_u32(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<Option<u32>> {
+    match value(parsed_json, key) {
+        None => Ok(None),
+        Some(v) => match v.as_u32() {
+            Some(u) => Ok(Some(u)),
+            None => v
+                .as_str()
+                .and_then(|s| s.trim().parse::<u32>().ok())
+                .map(Some)
+                .ok_or_else(|| invalid(key, v)),
+        },
+    }
+}
+
+fn opt_u8(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<Option<u8>> {
+    match opt_u32(parsed_json, key)? {
+        None => Ok(None),
+        Some(u) => u8::try_from(u)
+            .map(Some)
+            .map_err(|_| invalid(key, &parsed_json[key])),
+    }
 }
 
-macro_rules! _F32 {
-    ($a:expr) => {
-        strip_units($a.as_str().unwrap())
-            .unwrap()
+fn opt_date(
+    parsed_json: &json::JsonValue,
+    key: &str,
+) -> MetadataResult<Option<DateTime<chrono::Utc>>> {
+    match value(parsed_json, key) {
+        None => Ok(None),
+        Some(v) => v
             .as_str()
-            .parse::<f32>()
-            .unwrap()
-    };
+            .and_then(parse_date)
+            .map(Some)
+            .ok_or_else(|| invalid(key, v)),
+    }
 }
 
-macro_rules! _U32 {
-    ($a:expr) => {
-        $a.as_u32().unwrap()
-    };
+fn req_string(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<String> {
+    required(key, opt_string(parsed_json, key)?)
 }
 
-macro_rules! _U8 {
-    ($a:expr) => {
-        $a.as_u8().unwrap()
-    };
+fn req_f32(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<f32> {
+    required(key, opt_f32(parsed_json, key)?)
+}
+
+fn req_u32(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<u32> {
+    required(key, opt_u32(parsed_json, key)?)
+}
+
+fn req_u8(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<u8> {
+    required(key, opt_u8(parsed_json, key)?)
+}
+
+fn req_date(parsed_json: &json::JsonValue, key: &str) -> MetadataResult<DateTime<chrono::Utc>> {
+    required(key, opt_date(parsed_json, key)?)
 }
 
 impl Metadata {
-    pub fn new_from_file(file_path: &str) -> Result<Metadata> {
+    /// Product ID of the image, from `PRODUCT_ID` or else the name of the file it was
+    /// distributed as
+    pub fn product_id(&self) -> Result<ProductId, ProductIdError> {
+        match &self.product_id {
+            Some(id) => Ok(id.clone()),
+            None => ProductId::from_file_name(&self.file_name),
+        }
+    }
+
+    pub fn new_from_file(file_path: &str) -> MetadataResult<Metadata> {
         if !path::file_exists(file_path) {
-            return Err(anyhow!(constants::status::FILE_NOT_FOUND));
+            return Err(MetadataError::FileNotFound(file_path.to_string()));
+        }
+
+        if pds::is_pds_file(file_path) {
+            let label = pds::Label::open(file_path)
+                .map_err(|e| MetadataError::Read(file_path.to_string(), e.to_string()))?;
+            return Metadata::new_from_json(&label.to_json());
+        }
+
+        if bundle::is_zip_file(file_path) {
+            return Metadata::new_from_zip(file_path);
         }
 
+        let json_string_data = fs::read_to_string(file_path)
+            .map_err(|e| MetadataError::Read(file_path.to_string(), e.to_string()))?;
+        let parsed_json =
+            json::parse(&json_string_data).map_err(|e| MetadataError::Json(e.to_string()))?;
+
+        Metadata::new_from_json(&parsed_json)
+    }
+
+    /// Loads the `-Metadata.json` from a missionjuno `-Data.zip` without extracting it
+    pub fn new_from_zip(zip_path: &str) -> MetadataResult<Metadata> {
+        let read_err = |e: String| MetadataError::Read(zip_path.to_string(), e);
+
+        let (name, bytes) = bundle::read_entry(zip_path, bundle::METADATA_SUFFIX)
+            .map_err(|e| read_err(e.to_string()))?;
         let json_string_data =
-            fs::read_to_string(file_path).expect(constants::status::ERROR_PARSING_JSON);
-        let parsed_json = json::parse(&json_string_data).unwrap();
+            String::from_utf8(bytes).map_err(|_| read_err(format!("{} is not UTF-8", name)))?;
+        let parsed_json =
+            json::parse(&json_string_data).map_err(|e| MetadataError::Json(e.to_string()))?;
 
-        Ok(Metadata {
-            image_time: _D!(parsed_json[constants::metadata::IMAGE_TIME]),
-            start_time: _D!(parsed_json[constants::metadata::START_TIME]),
-            stop_time: _D!(parsed_json[constants::metadata::STOP_TIME]),
-            compression_type: _S!(parsed_json[constants::metadata::COMPRESSION_TYPE]),
-            data_set_id: _S!(parsed_json[constants::metadata::DATA_SET_ID]),
-            description: _S!(parsed_json[constants::metadata::DESCRIPTION]),
-            exposure_duration: _F32!(parsed_json[constants::metadata::EXPOSURE_DURATION]), // Seconds
-            file_name: _S!(parsed_json[constants::metadata::FILE_NAME]),
-            file_records: _U32!(parsed_json[constants::metadata::FILE_RECORDS]),
-            filters: Filters::new(
-                parsed_json[constants::metadata::FILTER_NAME].contains(constants::filters::RED),
-                parsed_json[constants::metadata::FILTER_NAME].contains(constants::filters::GREEN),
-                parsed_json[constants::metadata::FILTER_NAME].contains(constants::filters::BLUE),
-            ), // Derived from FILTER_NAME
-            focal_plane_temperature: _F32!(
-                parsed_json[constants::metadata::FOCAL_PLANE_TEMPERATURE]
-            ), // Kelvin
-            instrument_host_name: _S!(parsed_json[constants::metadata::INSTRUMENT_HOST_NAME]),
-            instrument_id: _S!(parsed_json[constants::metadata::INSTRUMENT_ID]),
-            instrument_name: _S!(parsed_json[constants::metadata::INSTRUMENT_NAME]),
-            interframe_delay: _F32!(parsed_json[constants::metadata::INTERFRAME_DELAY]), // Seconds
-            jno_tdi_stages_count: _U32!(parsed_json[constants::metadata::JNO_TDI_STAGES_COUNT]),
-            lines: _U32!(parsed_json[constants::metadata::LINES]),
-            line_prefix_bytes: _U32!(parsed_json[constants::metadata::LINE_PREFIX_BYTES]),
-            line_samples: _U32!(parsed_json[constants::metadata::LINE_SAMPLES]),
-            line_suffix_bytes: _U32!(parsed_json[constants::metadata::LINE_SUFFIX_BYTES]),
-            mission_phase_name: _S!(parsed_json[constants::metadata::MISSION_PHASE_NAME]),
-            orbit_number: _U32!(parsed_json[constants::metadata::ORBIT_NUMBER]),
-            pj: _S!(parsed_json[constants::metadata::PJ]), // String version of orbit_number for some reason
-            processing_level_id: _U8!(parsed_json[constants::metadata::PROCESSING_LEVEL_ID]),
-            producer_id: _S!(parsed_json[constants::metadata::PRODUCER_ID]),
-            product_creation_time: _D!(parsed_json[constants::metadata::PRODUCT_CREATION_TIME]),
-            product_version_id: _U8!(parsed_json[constants::metadata::PRODUCT_VERSION_ID]),
-            rationale_desc: _S!(parsed_json[constants::metadata::RATIONALE_DESC]),
-            record_bytes: _U32!(parsed_json[constants::metadata::RECORD_BYTES]),
-            sample_bits: _U8!(parsed_json[constants::metadata::SAMPLE_BITS]),
-            sample_bit_mask: _S!(parsed_json[constants::metadata::SAMPLE_BIT_MASK]),
-            sample_bit_mode_id: enums::SampleBitMode::from(
-                parsed_json[constants::metadata::SAMPLE_BIT_MODE_ID]
-                    .as_str()
-                    .unwrap(),
-            ),
-            sample_type: _S!(parsed_json[constants::metadata::SAMPLE_TYPE]),
-            sampling_factor: _U8!(parsed_json[constants::metadata::SAMPLING_FACTOR]),
-            sequence_id: _S!(parsed_json[constants::metadata::SEQUENCE_ID]),
-            software_name: _S!(parsed_json[constants::metadata::SOFTWARE_NAME]),
-            solar_distance: _F32!(parsed_json[constants::metadata::SOLAR_DISTANCE]), // kilometers
-            source_product_id: _S!(parsed_json[constants::metadata::SOURCE_PRODUCT_ID]),
-            spacecraft_altitude: _F32!(parsed_json[constants::metadata::SPACECRAFT_ALTITUDE]), // kilometers
-            spacecraft_clock_start_count: _F32!(
-                parsed_json[constants::metadata::SPACECRAFT_CLOCK_START_COUNT]
-            ), // seconds
-            spacecraft_clock_stop_count: _S!(
-                parsed_json[constants::metadata::SPACECRAFT_CLOCK_STOP_COUNT]
-            ), // Probably not a string when non-zero (N/A)
-            spacecaft_name: _S!(parsed_json[constants::metadata::SPACECRAFT_NAME]),
-            standard_data_product_id: _S!(
-                parsed_json[constants::metadata::STANDARD_DATA_PRODUCT_ID]
-            ),
-            sub_spacecraft_latitude: _F32!(
-                parsed_json[constants::metadata::SUB_SPACECRAFT_LATITUDE]
-            ),
-            sub_spacecraft_longitude: _F32!(
-                parsed_json[constants::metadata::SUB_SPACECRAFT_LONGITUDE]
+        Metadata::new_from_json(&parsed_json)
+    }
+
+    /// Builds metadata from missionjuno style json, or a PDS label converted with
+    /// `pds::Label::to_json()`
+    pub fn new_from_json(parsed_json: &json::JsonValue) -> MetadataResult<Metadata> {
+        use constants::metadata::*;
+        let j = parsed_json;
+
+        let start_time = req_date(j, START_TIME)?;
+        let orbit_number = req_u32(j, ORBIT_NUMBER)?;
+
+        let filter_name = required(FILTER_NAME, value(j, FILTER_NAME))?;
+        let filters = filters_from_json(filter_name)
+            .filter(|f| !f.cameras().is_empty())
+            .ok_or_else(|| invalid(FILTER_NAME, filter_name))?;
+
+        let sample_bit_mode = req_string(j, SAMPLE_BIT_MODE_ID)?;
+        let sample_bit_mode_id = match enums::SampleBitMode::from(&sample_bit_mode) {
+            enums::SampleBitMode::UNKNOWN => {
+                return Err(invalid(SAMPLE_BIT_MODE_ID, &j[SAMPLE_BIT_MODE_ID]))
+            }
+            m => m,
+        };
+
+        let product_id = match opt_string(j, PRODUCT_ID)? {
+            Some(id) => {
+                Some(ProductId::parse(&id).map_err(|_| invalid(PRODUCT_ID, &j[PRODUCT_ID]))?)
+            }
+            None => None,
+        };
+
+        // An array of who knows what
+        let token_id = match value(j, TOKEN_ID) {
+            Some(v) if v.is_array() => Some(
+                v.members()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
             ),
-            title: _S!(parsed_json[constants::metadata::TITLE]),
-            token_id: 0, // What even is this?
+            _ => opt_string(j, TOKEN_ID)?,
+        };
+
+        Ok(Metadata {
+            image_time: opt_date(j, IMAGE_TIME)?.unwrap_or(start_time),
+            start_time,
+            stop_time: req_date(j, STOP_TIME)?,
+            compression_type: req_string(j, COMPRESSION_TYPE)?,
+            data_set_id: req_string(j, DATA_SET_ID)?,
+            description: opt_string(j, DESCRIPTION)?,
+            exposure_duration: req_f32(j, EXPOSURE_DURATION)?,
+            file_name: req_string(j, FILE_NAME)?,
+            file_records: req_u32(j, FILE_RECORDS)?,
+            filters,
+            focal_plane_temperature: req_f32(j, FOCAL_PLANE_TEMPERATURE)?,
+            instrument_host_name: req_string(j, INSTRUMENT_HOST_NAME)?,
+            instrument_id: req_string(j, INSTRUMENT_ID)?,
+            instrument_name: req_string(j, INSTRUMENT_NAME)?,
+            interframe_delay: req_f32(j, INTERFRAME_DELAY)?,
+            jno_tdi_stages_count: req_u32(j, JNO_TDI_STAGES_COUNT)?,
+            lines: req_u32(j, LINES)?,
+            line_prefix_bytes: req_u32(j, LINE_PREFIX_BYTES)?,
+            line_samples: req_u32(j, LINE_SAMPLES)?,
+            line_suffix_bytes: req_u32(j, LINE_SUFFIX_BYTES)?,
+            mission_phase_name: req_string(j, MISSION_PHASE_NAME)?,
+            orbit_number,
+            pj: opt_string(j, PJ)?.unwrap_or_else(|| orbit_number.to_string()),
+            processing_level_id: req_u8(j, PROCESSING_LEVEL_ID)?,
+            producer_id: req_string(j, PRODUCER_ID)?,
+            product_creation_time: req_date(j, PRODUCT_CREATION_TIME)?,
+            product_id,
+            product_version_id: req_u8(j, PRODUCT_VERSION_ID)?,
+            rationale_desc: req_string(j, RATIONALE_DESC)?,
+            record_bytes: req_u32(j, RECORD_BYTES)?,
+            sample_bits: req_u8(j, SAMPLE_BITS)?,
+            sample_bit_mask: req_string(j, SAMPLE_BIT_MASK)?,
+            sample_bit_mode_id,
+            sample_type: req_string(j, SAMPLE_TYPE)?,
+            sampling_factor: req_u8(j, SAMPLING_FACTOR)?,
+            sequence_id: req_string(j, SEQUENCE_ID)?,
+            software_name: req_string(j, SOFTWARE_NAME)?,
+            solar_distance: req_f32(j, SOLAR_DISTANCE)?,
+            source_product_id: req_string(j, SOURCE_PRODUCT_ID)?,
+            spacecraft_altitude: req_f32(j, SPACECRAFT_ALTITUDE)?,
+            spacecraft_clock_start_count: req_f32(j, SPACECRAFT_CLOCK_START_COUNT)?,
+            spacecraft_clock_stop_count: opt_string(j, SPACECRAFT_CLOCK_STOP_COUNT)?,
+            spacecaft_name: req_string(j, SPACECRAFT_NAME)?,
+            standard_data_product_id: req_string(j, STANDARD_DATA_PRODUCT_ID)?,
+            sub_spacecraft_latitude: req_f32(j, SUB_SPACECRAFT_LATITUDE)?,
+            sub_spacecraft_longitude: req_f32(j, SUB_SPACECRAFT_LONGITUDE)?,
+            target_name: opt_string(j, TARGET_NAME)?,
+            title: req_string(j, TITLE)?,
+            token_id,
         })
     }
 }
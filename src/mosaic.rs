@@ -0,0 +1,221 @@
+use crate::{
+    coverage::Coverage,
+    ellipsoid::Ellipsoid,
+    interpolate::Interpolation,
+    photometry::PhotometricModel,
+    process::{self, PreparedImage, ProcessOptions},
+    projection::{georeference::Georeference, projection::MapProjection},
+    rawimage::RawImage,
+    render::{self, Blending, FrameletSetGeometry, WeightedSample},
+    vprintln,
+};
+
+use anyhow::{anyhow, Result};
+use sciimg::{imagebuffer::ImageBuffer, prelude::*};
+
+/// Blends any number of images rendered onto the same map projection. Overlapping
+/// samples are combined as a weighted mean, and a count of the images covering each
+/// pixel is kept alongside.
+pub struct Mosaic {
+    pub width: usize,
+    pub height: usize,
+
+    /// Output bands: three for color images, one for single filter images
+    pub bands: usize,
+    samples: Vec<WeightedSample>,
+    coverage: Vec<u32>,
+
+    /// Placement of the mosaic's projection in a standard reference system, if it has one
+    pub georeference: Option<Georeference>,
+}
+
+impl Mosaic {
+    pub fn new(width: usize, height: usize) -> Mosaic {
+        Mosaic {
+            width,
+            height,
+            bands: 3,
+            samples: vec![WeightedSample::default(); width * height],
+            coverage: vec![0; width * height],
+            georeference: None,
+        }
+    }
+
+    /// Adds the weighted samples of one image, arranged by row
+    pub fn add(&mut self, rows: &[Vec<WeightedSample>]) -> Result<()> {
+        if rows.len() != self.height || rows.iter().any(|r| r.len() != self.width) {
+            return Err(anyhow!(
+                "Image dimensions do not match mosaic ({}x{})",
+                self.width,
+                self.height
+            ));
+        }
+
+        rows.iter().enumerate().for_each(|(y, row)| {
+            row.iter().enumerate().for_each(|(x, sample)| {
+                if !sample.is_empty() {
+                    let i = y * self.width + x;
+                    self.samples[i].add(sample);
+                    self.coverage[i] += 1;
+                }
+            });
+        });
+        Ok(())
+    }
+
+    /// Renders an image onto the mosaic. Requires geometry created with
+    /// `FrameletSetGeometry::at_with_body()`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_image(
+        &mut self,
+        raw_image: &RawImage,
+        geometry: &[FrameletSetGeometry],
+        projection: &dyn MapProjection,
+        ellipsoid: &Ellipsoid,
+        interpolation: Interpolation,
+        photometry: PhotometricModel,
+        blending: Blending,
+    ) -> Result<()> {
+        let rows = render::render_surface_weighted(
+            raw_image,
+            geometry,
+            projection,
+            ellipsoid,
+            interpolation,
+            photometry,
+            blending,
+            self.width,
+            self.height,
+        );
+        self.add(&rows)
+    }
+
+    /// Blended mosaic, RGB for color images. Pixels no image covered are left transparent.
+    pub fn to_image(&self) -> Result<Image> {
+        let mut map =
+            Image::new_with_bands(self.width, self.height, self.bands, ImageMode::U16BIT)?;
+        self.samples.iter().enumerate().for_each(|(i, sample)| {
+            sample
+                .mean()
+                .iter()
+                .take(self.bands)
+                .enumerate()
+                .for_each(|(c, v)| {
+                    if let Some(v) = v {
+                        map.put(i % self.width, i / self.width, *v, c);
+                    }
+                });
+        });
+        self.band_coverage().apply_alpha(&mut map);
+        Ok(map)
+    }
+
+    /// Pixels that received samples in each band
+    pub fn band_coverage(&self) -> Coverage {
+        let mut coverage = Coverage::new(self.width, self.height, self.bands);
+        self.samples.iter().enumerate().for_each(|(i, sample)| {
+            sample
+                .mean()
+                .iter()
+                .take(self.bands)
+                .enumerate()
+                .filter(|(_, v)| v.is_some())
+                .for_each(|(c, _)| coverage.set(i % self.width, i / self.width, c));
+        });
+        coverage
+    }
+
+    /// Number of images contributing to each pixel
+    pub fn coverage(&self) -> Result<ImageBuffer> {
+        let mut buffer = ImageBuffer::new(self.width, self.height)?;
+        self.coverage.iter().enumerate().for_each(|(i, c)| {
+            buffer.put(i % self.width, i / self.width, *c as f32);
+        });
+        Ok(buffer)
+    }
+
+    /// Number of pixels covered by at least one image
+    pub fn covered_pixels(&self) -> usize {
+        self.coverage.iter().filter(|c| **c > 0).count()
+    }
+}
+
+/// Projects every image onto a single shared surface map. Map settings are taken from the
+/// first image's options; where they are left to defaults (sub-observer point, pole) they
+/// follow that image's geometry.
+pub fn mosaic_images(images: &[ProcessOptions], blending: Blending) -> Result<Mosaic> {
+    let first_options = images
+        .first()
+        .ok_or_else(|| anyhow!("No images to mosaic"))?;
+    if !first_options.lens.is_surface_projection() {
+        return Err(anyhow!(
+            "Mosaics require a surface projection, not {:?}",
+            first_options.lens
+        ));
+    }
+
+    let first = process::prepare_image(first_options)?;
+
+    let ellipsoid = Ellipsoid::jupiter()?;
+    let sub_spacecraft = process::sub_spacecraft_point(&ellipsoid, first.mid_time_et);
+    let (width, height) = process::map_size(first_options, Some(&ellipsoid))?;
+    let projection =
+        process::surface_projection(first_options, width, height, ellipsoid, sub_spacecraft)
+            .ok_or_else(|| anyhow!("Unsupported surface projection: {:?}", first_options.lens))?;
+
+    let mut mosaic = Mosaic::new(width, height);
+    mosaic.georeference = projection.georeference();
+    let first_cameras = first.raw_image.cameras();
+    mosaic.bands = render::output_band_count(&first_cameras);
+    let mut first = Some(first);
+
+    for (i, options) in images.iter().enumerate() {
+        let PreparedImage {
+            raw_image,
+            framelet_set_times_et,
+            ..
+        } = match first.take() {
+            Some(prepared) => prepared,
+            None => process::prepare_image(options)?,
+        };
+
+        // Grayscale images of different filters, or grayscale and color, don't blend
+        let cameras = raw_image.cameras();
+        if render::output_band_count(&cameras) != mosaic.bands
+            || (mosaic.bands == 1 && cameras != first_cameras)
+        {
+            return Err(anyhow!(
+                "Cannot mosaic {} with images taken through different filters",
+                options.input
+            ));
+        }
+
+        vprintln!(
+            "Adding image {} of {} to mosaic: {}",
+            i + 1,
+            images.len(),
+            options.input
+        );
+        let geometry: Vec<FrameletSetGeometry> = framelet_set_times_et
+            .iter()
+            .map(|et| FrameletSetGeometry::at_with_body(*et))
+            .collect();
+
+        mosaic.add_image(
+            &raw_image,
+            &geometry,
+            projection.as_ref(),
+            &ellipsoid,
+            options.interpolation,
+            options.photometric_model,
+            blending,
+        )?;
+    }
+
+    vprintln!(
+        "Mosaic covers {} of {} pixels",
+        mosaic.covered_pixels(),
+        width * height
+    );
+    Ok(mosaic)
+}
@@ -0,0 +1,261 @@
+use crate::{fits, vprintln};
+
+use anyhow::{anyhow, Result};
+use sciimg::{image::Image, imagebuffer::ImageBuffer};
+use std::fs::{self, File};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+/// How output values are written
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// Stretched to the 16 bit range and written by extension (PNG, etc)
+    Stretched,
+
+    /// Unstretched 32-bit floating point TIFF
+    FloatTiff,
+
+    /// Unstretched 32-bit floating point FITS
+    Fits,
+}
+
+impl OutputFormat {
+    pub fn from(s: &str) -> Option<OutputFormat> {
+        match s.to_lowercase().as_str() {
+            "png" | "stretched" => Some(OutputFormat::Stretched),
+            "tiff" | "tif" => Some(OutputFormat::FloatTiff),
+            "fits" => Some(OutputFormat::Fits),
+            _ => None,
+        }
+    }
+
+    /// Parses a `--format` argument. None if no format was given.
+    pub fn parse_arg(format: Option<&String>) -> Result<Option<OutputFormat>> {
+        match format {
+            Some(f) => match OutputFormat::from(f) {
+                Some(format) => Ok(Some(format)),
+                None => Err(anyhow!(
+                    "Invalid output format requested: {}. Use either 'png', 'tiff', or 'fits'",
+                    f
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        *self != OutputFormat::Stretched
+    }
+
+    /// File extension, including the dot. None for stretched output, which is written as
+    /// whatever the output file name says.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Stretched => None,
+            OutputFormat::FloatTiff => Some(".tif"),
+            OutputFormat::Fits => Some(".fits"),
+        }
+    }
+}
+
+/// Replaces the extension of `file_path` with `append`, which should include its own
+/// extension. Unlike `sciimg::util::replace_image_extension()`, this handles any extension,
+/// including FITS, rather than leaving unknown ones in place.
+pub fn replace_extension(file_path: &str, append: &str) -> String {
+    let file_name_start = file_path.rfind(['/', '\\']).map(|i| i + 1).unwrap_or(0);
+    match file_path[file_name_start..].rfind('.') {
+        Some(i) if i > 0 => format!("{}{}", &file_path[..file_name_start + i], append),
+        _ => format!("{}{}", file_path, append),
+    }
+}
+
+/// Stretch applied to values before they were written
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stretch {
+    /// Values are as calibrated
+    None,
+
+    /// Values were linearly mapped from `min`..`max` onto `out_min`..`out_max`
+    Linear {
+        min: f32,
+        max: f32,
+        out_min: f32,
+        out_max: f32,
+    },
+}
+
+impl Stretch {
+    pub fn describe(&self) -> String {
+        match self {
+            Stretch::None => "stretch=none".to_string(),
+            Stretch::Linear {
+                min,
+                max,
+                out_min,
+                out_max,
+            } => format!(
+                "stretch=linear min={} max={} out_min={} out_max={}",
+                min, max, out_min, out_max
+            ),
+        }
+    }
+
+    fn fits_header(&self) -> fits::Header {
+        let mut header = fits::Header::new();
+        match self {
+            Stretch::None => header.set(
+                "STRETCH",
+                fits::HeaderValue::Str("NONE".to_string()),
+                Some("Values are unstretched"),
+            ),
+            Stretch::Linear {
+                min,
+                max,
+                out_min,
+                out_max,
+            } => {
+                header.set(
+                    "STRETCH",
+                    fits::HeaderValue::Str("LINEAR".to_string()),
+                    Some("Values were linearly stretched"),
+                );
+                header.set("STRMIN", fits::HeaderValue::Float(*min as f64), None);
+                header.set("STRMAX", fits::HeaderValue::Float(*max as f64), None);
+                header.set("STROMIN", fits::HeaderValue::Float(*out_min as f64), None);
+                header.set("STROMAX", fits::HeaderValue::Float(*out_max as f64), None);
+            }
+        }
+        header
+    }
+}
+
+/// Stretches `buffer` to the 16 bit range, returning the stretch applied
+pub fn stretch_to_16bit(buffer: &mut ImageBuffer) -> Stretch {
+    let mm = buffer.get_min_max();
+    buffer.normalize_mut(0.0, 65535.0);
+    Stretch::Linear {
+        min: mm.min,
+        max: mm.max,
+        out_min: 0.0,
+        out_max: 65535.0,
+    }
+}
+
+/// Records the stretch applied to each band of a stretched output in a
+/// `<output>-stretch.txt` sidecar, as formats like PNG have nowhere to keep it
+pub fn save_stretch_sidecar(output_file: &str, stretches: &[Stretch]) -> Result<()> {
+    let sidecar = replace_extension(output_file, "-stretch.txt");
+    vprintln!("Writing stretch to {}", sidecar);
+    let lines: String = stretches
+        .iter()
+        .enumerate()
+        .map(|(b, s)| format!("band={} {}\n", b, s.describe()))
+        .collect();
+    fs::write(sidecar, lines)?;
+    Ok(())
+}
+
+/// Writes a single band buffer in the requested format. Stretched output is normalized to
+/// the 16 bit range first, with the stretch recorded in a sidecar. Float output is written
+/// as is.
+pub fn save_buffer(buffer: &ImageBuffer, output_file: &str, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Stretched => {
+            let mut stretched = buffer.clone();
+            let stretch = stretch_to_16bit(&mut stretched);
+            vprintln!("Applied {}", stretch.describe());
+            stretched.save(output_file)?;
+            save_stretch_sidecar(output_file, &[stretch])
+        }
+        OutputFormat::FloatTiff => save_float_tiff_with_stretch(buffer, output_file, Stretch::None),
+        OutputFormat::Fits => save_fits(&[buffer], output_file, Stretch::None, None),
+    }
+}
+
+/// Writes an already rendered image as float data, recording the stretch applied to it.
+/// `header` adds keywords to FITS output.
+pub fn save_float_image(
+    image: &Image,
+    output_file: &str,
+    format: OutputFormat,
+    stretch: Stretch,
+    header: Option<&fits::Header>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Stretched => Err(anyhow!("Not a floating point output format")),
+        OutputFormat::FloatTiff if image.num_bands() == 1 => {
+            save_float_tiff_with_stretch(image.get_band(0), output_file, stretch)
+        }
+        OutputFormat::FloatTiff => save_float_tiff_rgb(image, output_file, stretch),
+        OutputFormat::Fits => {
+            let bands: Vec<&ImageBuffer> =
+                (0..image.num_bands()).map(|b| image.get_band(b)).collect();
+            save_fits(&bands, output_file, stretch, header)
+        }
+    }
+}
+
+/// Writes bands as FITS with the stretch recorded in the header, along with any extra
+/// header keywords.
+pub fn save_fits(
+    bands: &[&ImageBuffer],
+    output_file: &str,
+    stretch: Stretch,
+    extra: Option<&fits::Header>,
+) -> Result<()> {
+    let mut header = stretch.fits_header();
+    if let Some(extra) = extra {
+        header.extend(extra);
+    }
+    fits::save_fits(bands, &header, output_file)
+}
+
+/// Writes a single band as a 32-bit floating point TIFF, preserving values as they are
+/// rather than scaling them to an integer range.
+pub fn save_float_tiff(buffer: &ImageBuffer, output_file: &str) -> Result<()> {
+    save_float_tiff_with_stretch(buffer, output_file, Stretch::None)
+}
+
+fn save_float_tiff_with_stretch(
+    buffer: &ImageBuffer,
+    output_file: &str,
+    stretch: Stretch,
+) -> Result<()> {
+    vprintln!("Writing float TIFF to {}", output_file);
+    let file = File::create(output_file)?;
+    let mut encoder = TiffEncoder::new(file)?;
+    let mut image =
+        encoder.new_image::<colortype::Gray32Float>(buffer.width as u32, buffer.height as u32)?;
+    image
+        .encoder()
+        .write_tag(Tag::ImageDescription, stretch.describe().as_str())?;
+    image.write_data(&buffer.buffer)?;
+    Ok(())
+}
+
+/// As with `save_float_tiff()`, for a three band image written as interleaved RGB. The
+/// stretch is recorded in the image description.
+pub fn save_float_tiff_rgb(image: &Image, output_file: &str, stretch: Stretch) -> Result<()> {
+    if image.num_bands() != 3 {
+        return Err(anyhow!(
+            "Expected a three band image, got {}",
+            image.num_bands()
+        ));
+    }
+
+    let (r, g, b) = (image.get_band(0), image.get_band(1), image.get_band(2));
+    let data: Vec<f32> = (0..r.buffer.len())
+        .flat_map(|i| [r.buffer[i], g.buffer[i], b.buffer[i]])
+        .collect();
+
+    vprintln!("Writing float RGB TIFF to {}", output_file);
+    let file = File::create(output_file)?;
+    let mut encoder = TiffEncoder::new(file)?;
+    let mut tiff_image =
+        encoder.new_image::<colortype::RGB32Float>(image.width as u32, image.height as u32)?;
+    tiff_image
+        .encoder()
+        .write_tag(Tag::ImageDescription, stretch.describe().as_str())?;
+    tiff_image.write_data(&data)?;
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use crate::{bundle, constants, metadata::ProductId, output, pds, vprintln};
+
+use anyhow::anyhow;
+use anyhow::Result;
+use glob::glob;
+use std::fs;
+use std::path::Path;
+
+/// An input image and the metadata describing it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pair {
+    pub image: String,
+    pub metadata: String,
+    pub product_id: Option<ProductId>,
+}
+
+impl Pair {
+    /// Output file for the pair, made by appending to the input's name. Inputs that aren't
+    /// named for their product, such as missionjuno zips, are named for the product ID.
+    pub fn output_file(&self, append: &str) -> String {
+        match &self.product_id {
+            Some(id) if ProductId::from_file_name(&self.image).is_err() => Path::new(&self.image)
+                .with_file_name(format!("{}{}", id, append))
+                .to_string_lossy()
+                .to_string(),
+            _ => output::replace_extension(&self.image, append),
+        }
+    }
+}
+
+/// Result of matching a set of files up, with whatever couldn't be matched
+#[derive(Debug, Default)]
+pub struct Pairing {
+    pub pairs: Vec<Pair>,
+    pub orphan_images: Vec<String>,
+    pub orphan_metadata: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    RawImage,
+    ImageSet,
+    Pds,
+    MetadataJson,
+    Data,
+}
+
+// A file, with whichever of the two ids it can be matched on
+struct Candidate {
+    path: String,
+    product_id: Option<ProductId>,
+    numeric_id: Option<String>,
+}
+
+/// Pairs inputs as given on the command line. Explicit metadata is matched to the images
+/// by position, otherwise the inputs are expanded and paired up by ID.
+pub fn pair_inputs(inputs: &[String], metadata: &[String]) -> Result<Pairing> {
+    if metadata.is_empty() {
+        return Ok(pair_files(&expand_inputs(inputs)?));
+    }
+
+    if inputs.len() != metadata.len() {
+        return Err(anyhow!(
+            "{} inputs given with {} metadata files",
+            inputs.len(),
+            metadata.len()
+        ));
+    }
+
+    Ok(Pairing {
+        pairs: inputs
+            .iter()
+            .zip(metadata.iter())
+            .map(|(i, m)| Pair {
+                image: i.clone(),
+                metadata: m.clone(),
+                product_id: ProductId::from_file_name(i).ok(),
+            })
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Expands directories (recursively) and glob patterns into the files they contain.
+/// Anything else is passed through as a file path.
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    for input in inputs.iter() {
+        let pattern = if Path::new(input).is_dir() {
+            format!("{}/**/*", input.trim_end_matches('/'))
+        } else if input.contains(['*', '?', '[']) {
+            input.clone()
+        } else {
+            files.push(input.clone());
+            continue;
+        };
+
+        for entry in glob(&pattern).map_err(|e| anyhow!("Invalid pattern {}: {}", input, e))? {
+            match entry {
+                Ok(p) if p.is_file() => files.push(p.to_string_lossy().to_string()),
+                Ok(_) => {}
+                Err(e) => vprintln!("{:?}", e),
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Matches raw images (`-raw.png`, `-ImageSet.zip`) with their metadata (`-Metadata.json`,
+/// `-Data.zip`) by product ID, falling back to the missionjuno numeric ID. PDS products
+/// are their own metadata. Files that are neither images nor metadata are ignored.
+pub fn pair_files(files: &[String]) -> Pairing {
+    let mut pairing = Pairing::default();
+    let mut images: Vec<Candidate> = Vec::new();
+    let mut metadata: Vec<Candidate> = Vec::new();
+    let mut pds_stems: Vec<(String, String)> = Vec::new();
+
+    for file in files.iter() {
+        let kind = match classify(file) {
+            Some(k) => k,
+            None => continue,
+        };
+        let file_name = file_name(file);
+
+        match kind {
+            Kind::Pds => {
+                // One product per .IMG/.LBL pair, loaded through the label if there is one
+                let stem = Path::new(file)
+                    .with_extension("")
+                    .to_string_lossy()
+                    .to_string();
+                match pds_stems
+                    .iter_mut()
+                    .find(|(s, _)| s.eq_ignore_ascii_case(&stem))
+                {
+                    Some((_, p)) => {
+                        if file.to_lowercase().ends_with(".lbl") {
+                            *p = file.clone();
+                        }
+                    }
+                    None => pds_stems.push((stem, file.clone())),
+                }
+            }
+            Kind::RawImage => images.push(Candidate {
+                path: file.clone(),
+                product_id: ProductId::from_file_name(&file_name).ok(),
+                numeric_id: numeric_id(&file_name),
+            }),
+            Kind::ImageSet => images.push(Candidate {
+                path: file.clone(),
+                product_id: bundle::entry_name(file, bundle::RAW_IMAGE_SUFFIX)
+                    .ok()
+                    .and_then(|n| ProductId::from_file_name(&n).ok()),
+                numeric_id: numeric_id(&file_name),
+            }),
+            Kind::MetadataJson | Kind::Data => {
+                let json = if kind == Kind::Data {
+                    bundle::read_entry(file, bundle::METADATA_SUFFIX)
+                        .ok()
+                        .and_then(|(_, b)| String::from_utf8(b).ok())
+                } else {
+                    fs::read_to_string(file).ok()
+                };
+                metadata.push(Candidate {
+                    path: file.clone(),
+                    product_id: json.and_then(|j| product_id_from_json(&j)),
+                    numeric_id: numeric_id(&file_name),
+                });
+            }
+        }
+    }
+
+    for (stem, file) in pds_stems.into_iter() {
+        pairing.pairs.push(Pair {
+            image: file.clone(),
+            metadata: file,
+            product_id: ProductId::from_file_name(&stem).ok(),
+        });
+    }
+
+    for image in images.into_iter() {
+        let found = metadata
+            .iter()
+            .position(|m| image.product_id.is_some() && m.product_id == image.product_id)
+            .or_else(|| {
+                metadata
+                    .iter()
+                    .position(|m| image.numeric_id.is_some() && m.numeric_id == image.numeric_id)
+            });
+
+        let md_path = match found {
+            Some(i) => Some(metadata.remove(i).path),
+            None => bundle::data_zip_for(&image.path),
+        };
+
+        match md_path {
+            Some(m) => pairing.pairs.push(Pair {
+                image: image.path,
+                metadata: m,
+                product_id: image.product_id,
+            }),
+            None => pairing.orphan_images.push(image.path),
+        }
+    }
+
+    pairing.orphan_metadata = metadata.into_iter().map(|m| m.path).collect();
+
+    // Product IDs start with the observation date, so this runs through a perijove in order
+    pairing
+        .pairs
+        .sort_by(|a, b| (&a.product_id, &a.image).cmp(&(&b.product_id, &b.image)));
+    pairing
+}
+
+fn classify(file: &str) -> Option<Kind> {
+    let lower = file_name(file).to_lowercase();
+    if lower.ends_with(&bundle::RAW_IMAGE_SUFFIX.to_lowercase()) {
+        Some(Kind::RawImage)
+    } else if lower.ends_with(&bundle::IMAGE_SET_SUFFIX.to_lowercase()) {
+        Some(Kind::ImageSet)
+    } else if lower.ends_with(&bundle::METADATA_SUFFIX.to_lowercase()) {
+        Some(Kind::MetadataJson)
+    } else if lower.ends_with(&bundle::DATA_SUFFIX.to_lowercase()) {
+        Some(Kind::Data)
+    } else if pds::is_pds_file(&lower) {
+        Some(Kind::Pds)
+    } else {
+        None
+    }
+}
+
+fn file_name(file: &str) -> String {
+    Path::new(file)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// missionjuno downloads are named for their numeric ID, as in 10124-Metadata.json
+fn numeric_id(file_name: &str) -> Option<String> {
+    let prefix = file_name.split('-').next()?;
+    if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_digit()) {
+        Some(prefix.to_string())
+    } else {
+        None
+    }
+}
+
+fn product_id_from_json(json_string_data: &str) -> Option<ProductId> {
+    let parsed_json = json::parse(json_string_data).ok()?;
+    if let Some(id) = parsed_json[constants::metadata::PRODUCT_ID].as_str() {
+        return ProductId::parse(id).ok();
+    }
+    parsed_json[constants::metadata::FILE_NAME]
+        .as_str()
+        .and_then(|f| ProductId::from_file_name(f).ok())
+}
@@ -0,0 +1,460 @@
+use crate::constants;
+
+use sciimg::path;
+use sciimg::prelude::*;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single ODL value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Quoted text
+    Text(String),
+
+    /// Unquoted literal: numbers, dates, identifiers and based integers such as `2#1111#`
+    Literal(String),
+
+    /// Literal with a unit, such as `3.2 <MS>`
+    WithUnits(String, String),
+
+    /// Sequence `( ... )` or set `{ ... }`
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(s) | Value::Literal(s) | Value::WithUnits(s, _) => Some(s.as_str()),
+            Value::List(_) => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_str().and_then(|s| s.parse::<usize>().ok())
+    }
+
+    /// Converts to the representation used by the missionjuno metadata json: integers
+    /// become numbers, values with units become `"value <unit>"` and everything else text.
+    pub fn to_json(&self) -> json::JsonValue {
+        match self {
+            Value::Text(s) => json::JsonValue::from(s.as_str()),
+            Value::Literal(s) => match s.parse::<i64>() {
+                Ok(i) => json::JsonValue::from(i),
+                Err(_) => json::JsonValue::from(s.as_str()),
+            },
+            Value::WithUnits(v, u) => json::JsonValue::from(format!("{} <{}>", v, u)),
+            Value::List(l) => json::JsonValue::Array(l.iter().map(|v| v.to_json()).collect()),
+        }
+    }
+}
+
+/// Keywords within an `OBJECT = ... END_OBJECT` block
+#[derive(Debug, Clone)]
+pub struct Object {
+    pub name: String,
+    pub keywords: Vec<(String, Value)>,
+}
+
+impl Object {
+    pub fn get(&self, keyword: &str) -> Option<&Value> {
+        self.keywords
+            .iter()
+            .find(|(k, _)| k == keyword)
+            .map(|(_, v)| v)
+    }
+}
+
+/// A parsed PDS3 label, either detached (`.LBL`) or attached to the start of an `.IMG`
+#[derive(Debug, Clone)]
+pub struct Label {
+    /// File the label was read from, used to resolve data file pointers
+    pub path: PathBuf,
+    pub keywords: Vec<(String, Value)>,
+    pub objects: Vec<Object>,
+}
+
+/// True if the path looks like a PDS3 label or image product
+pub fn is_pds_file(file_path: &str) -> bool {
+    let lower = file_path.to_lowercase();
+    lower.ends_with(".lbl") || lower.ends_with(".img")
+}
+
+impl Label {
+    /// Reads the label for a product. `file_path` may be the detached label, an image
+    /// with an attached label, or an image whose `.LBL` sits next to it.
+    pub fn open(file_path: &str) -> Result<Label> {
+        if !path::file_exists(file_path) {
+            return Err(anyhow!(constants::status::FILE_NOT_FOUND));
+        }
+
+        let bytes = fs::read(file_path)?;
+        if bytes.starts_with(b"PDS_VERSION_ID") {
+            return Label::parse(&label_text(&bytes), Path::new(file_path));
+        }
+
+        for ext in ["LBL", "lbl"] {
+            let label_path = Path::new(file_path).with_extension(ext);
+            if label_path.exists() {
+                let bytes = fs::read(&label_path)?;
+                return Label::parse(&label_text(&bytes), &label_path);
+            }
+        }
+
+        Err(anyhow!("No PDS label found for {}", file_path))
+    }
+
+    pub fn parse(text: &str, path: &Path) -> Result<Label> {
+        let mut label = Label {
+            path: path.to_path_buf(),
+            keywords: Vec::new(),
+            objects: Vec::new(),
+        };
+
+        // Open objects, innermost last. Groups only namespace their keywords, so they
+        // are folded into whatever encloses them.
+        let mut stack: Vec<Object> = Vec::new();
+        let mut parser = Parser::new(text);
+
+        while let Some(keyword) = parser.keyword()? {
+            if keyword == "END" {
+                break;
+            }
+            parser.expect('=')?;
+            let value = parser.value()?;
+
+            match keyword.as_str() {
+                "OBJECT" => stack.push(Object {
+                    name: value.as_str().unwrap_or_default().to_string(),
+                    keywords: Vec::new(),
+                }),
+                "END_OBJECT" => match stack.pop() {
+                    Some(o) => label.objects.push(o),
+                    None => return Err(anyhow!("Unbalanced END_OBJECT in PDS label")),
+                },
+                "GROUP" | "END_GROUP" => {}
+                _ => match stack.last_mut() {
+                    Some(o) => o.keywords.push((keyword, value)),
+                    None => label.keywords.push((keyword, value)),
+                },
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err(anyhow!("Unterminated OBJECT in PDS label"));
+        }
+
+        Ok(label)
+    }
+
+    pub fn get(&self, keyword: &str) -> Option<&Value> {
+        self.keywords
+            .iter()
+            .find(|(k, _)| k == keyword)
+            .map(|(_, v)| v)
+    }
+
+    pub fn object(&self, name: &str) -> Option<&Object> {
+        self.objects.iter().find(|o| o.name == name)
+    }
+
+    /// Flattens the label into the same keys as the missionjuno metadata json, so it
+    /// can be loaded by `Metadata`. `IMAGE_TIME` and `PJ` aren't in the archive labels
+    /// and are derived from `START_TIME` and `ORBIT_NUMBER`.
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut obj = json::JsonValue::new_object();
+
+        let image_keywords = self.object("IMAGE").map(|o| o.keywords.iter());
+        for (k, v) in self
+            .keywords
+            .iter()
+            .chain(image_keywords.into_iter().flatten())
+            .filter(|(k, _)| !k.starts_with('^'))
+        {
+            obj[k.as_str()] = v.to_json();
+        }
+
+        if !obj.has_key(constants::metadata::IMAGE_TIME) {
+            obj[constants::metadata::IMAGE_TIME] = obj[constants::metadata::START_TIME].clone();
+        }
+        if !obj.has_key(constants::metadata::PJ) {
+            if let Some(orbit) = obj[constants::metadata::ORBIT_NUMBER].as_u32() {
+                obj[constants::metadata::PJ] = json::JsonValue::from(orbit.to_string());
+            }
+        }
+
+        obj
+    }
+
+    /// Loads the `IMAGE` object as raw DN values. Line prefix and suffix bytes are
+    /// skipped, and the `^IMAGE` pointer may name a detached file or a record (or
+    /// byte) offset into the labelled file.
+    pub fn read_image(&self) -> Result<ImageBuffer> {
+        let image = self
+            .object("IMAGE")
+            .ok_or_else(|| anyhow!("PDS label has no IMAGE object"))?;
+
+        let image_usize = |k: &str| -> Result<usize> {
+            image
+                .get(k)
+                .and_then(|v| v.as_usize())
+                .ok_or_else(|| anyhow!("PDS IMAGE object is missing {}", k))
+        };
+        let optional_usize = |k: &str| image.get(k).and_then(|v| v.as_usize()).unwrap_or(0);
+
+        let lines = image_usize(constants::metadata::LINES)?;
+        let line_samples = image_usize(constants::metadata::LINE_SAMPLES)?;
+        let sample_bits = image_usize(constants::metadata::SAMPLE_BITS)?;
+        let prefix = optional_usize(constants::metadata::LINE_PREFIX_BYTES);
+        let suffix = optional_usize(constants::metadata::LINE_SUFFIX_BYTES);
+        let sample_type = image
+            .get(constants::metadata::SAMPLE_TYPE)
+            .and_then(|v| v.as_str())
+            .unwrap_or("UNSIGNED_INTEGER");
+
+        let sample_bytes = match sample_bits {
+            8 => 1,
+            16 => 2,
+            _ => return Err(anyhow!("Unsupported PDS SAMPLE_BITS: {}", sample_bits)),
+        };
+        let little_endian = sample_type.starts_with("LSB")
+            || sample_type.starts_with("PC_")
+            || sample_type.starts_with("VAX_");
+
+        let (data_path, offset) = self.image_location()?;
+        let data = fs::read(&data_path)?;
+
+        let stride = prefix + line_samples * sample_bytes + suffix;
+        let end = offset + lines * stride;
+        if data.len() < end {
+            return Err(anyhow!(
+                "PDS image {} is truncated: expected {} bytes, found {}",
+                data_path.display(),
+                end,
+                data.len()
+            ));
+        }
+
+        let mut v = DnVec::zeros(line_samples * lines);
+        for y in 0..lines {
+            let row = &data[(offset + y * stride + prefix)..];
+            for x in 0..line_samples {
+                v[y * line_samples + x] = match sample_bytes {
+                    1 => row[x] as f32,
+                    _ => {
+                        let b = [row[x * 2], row[x * 2 + 1]];
+                        if little_endian {
+                            u16::from_le_bytes(b) as f32
+                        } else {
+                            u16::from_be_bytes(b) as f32
+                        }
+                    }
+                };
+            }
+        }
+
+        ImageBuffer::from_vec(&v, line_samples, lines)
+    }
+
+    pub fn sample_bits(&self) -> Option<usize> {
+        self.object("IMAGE")
+            .and_then(|o| o.get(constants::metadata::SAMPLE_BITS))
+            .and_then(|v| v.as_usize())
+    }
+
+    // Resolves ^IMAGE to a data file and byte offset. Record offsets are 1-based.
+    fn image_location(&self) -> Result<(PathBuf, usize)> {
+        let pointer = self
+            .get("^IMAGE")
+            .ok_or_else(|| anyhow!("PDS label has no ^IMAGE pointer"))?;
+        let record_bytes = self
+            .get(constants::metadata::RECORD_BYTES)
+            .and_then(|v| v.as_usize());
+
+        let to_bytes = |v: &Value| -> Result<usize> {
+            let (n, in_bytes) = match v {
+                Value::WithUnits(n, u) if u.eq_ignore_ascii_case("BYTES") => (n, true),
+                Value::Literal(n) => (n, false),
+                _ => return Err(anyhow!("Invalid PDS ^IMAGE offset: {:?}", v)),
+            };
+            let n = n
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid PDS ^IMAGE offset: {}", n))?
+                .max(1);
+            if in_bytes {
+                Ok(n - 1)
+            } else {
+                let record_bytes =
+                    record_bytes.ok_or_else(|| anyhow!("PDS label is missing RECORD_BYTES"))?;
+                Ok((n - 1) * record_bytes)
+            }
+        };
+
+        match pointer {
+            Value::Text(file) => Ok((self.resolve_file(file)?, 0)),
+            Value::List(l) => match l.as_slice() {
+                [Value::Text(file)] => Ok((self.resolve_file(file)?, 0)),
+                [Value::Text(file), offset] => Ok((self.resolve_file(file)?, to_bytes(offset)?)),
+                _ => Err(anyhow!("Invalid PDS ^IMAGE pointer: {:?}", pointer)),
+            },
+            _ => Ok((self.path.clone(), to_bytes(pointer)?)),
+        }
+    }
+
+    // Archive labels name files in upper case, but volumes are often copied lower case
+    fn resolve_file(&self, file: &str) -> Result<PathBuf> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        [file.to_string(), file.to_lowercase(), file.to_uppercase()]
+            .iter()
+            .map(|f| dir.join(f))
+            .find(|p| p.exists())
+            .ok_or_else(|| anyhow!("PDS data file not found: {}", dir.join(file).display()))
+    }
+}
+
+// The label of an attached product ends at its END statement
+fn label_text(bytes: &[u8]) -> String {
+    let mut end = bytes.len();
+    let mut start = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == b'\n' {
+            if String::from_utf8_lossy(&bytes[start..i]).trim() == "END" {
+                end = i;
+                break;
+            }
+            start = i + 1;
+        }
+    }
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    // Skips whitespace and /* */ comments
+    fn skip(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    if ahead.peek() != Some(&'*') {
+                        return;
+                    }
+                    self.chars.nth(1);
+                    let mut prev = ' ';
+                    for c in self.chars.by_ref() {
+                        if prev == '*' && c == '/' {
+                            break;
+                        }
+                        prev = c;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip();
+        match self.chars.next() {
+            Some(n) if n == c => Ok(()),
+            n => Err(anyhow!("Expected '{}' in PDS label, found {:?}", c, n)),
+        }
+    }
+
+    fn keyword(&mut self) -> Result<Option<String>> {
+        self.skip();
+        if self.chars.peek().is_none() {
+            return Ok(None);
+        }
+        let mut k = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == '=' {
+                break;
+            }
+            k.push(c);
+            self.chars.next();
+        }
+        Ok(Some(k))
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip();
+        match self.chars.peek() {
+            Some('"') => {
+                self.chars.next();
+                let mut s = String::new();
+                for c in self.chars.by_ref() {
+                    if c == '"' {
+                        // Collapse the line breaks of wrapped text
+                        return Ok(Value::Text(
+                            s.split_whitespace().collect::<Vec<_>>().join(" "),
+                        ));
+                    }
+                    s.push(c);
+                }
+                Err(anyhow!("Unterminated string in PDS label"))
+            }
+            Some('\'') => {
+                self.chars.next();
+                let s: String = self.chars.by_ref().take_while(|c| *c != '\'').collect();
+                Ok(Value::Literal(s))
+            }
+            Some('(') | Some('{') => {
+                let close = if self.chars.next() == Some('(') {
+                    ')'
+                } else {
+                    '}'
+                };
+                let mut items = Vec::new();
+                loop {
+                    self.skip();
+                    match self.chars.peek() {
+                        Some(&c) if c == close => {
+                            self.chars.next();
+                            return Ok(Value::List(items));
+                        }
+                        Some(',') => {
+                            self.chars.next();
+                        }
+                        Some(_) => items.push(self.value()?),
+                        None => return Err(anyhow!("Unterminated list in PDS label")),
+                    }
+                }
+            }
+            Some(_) => {
+                let mut s = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_whitespace() || c == ',' || c == ')' || c == '}' || c == '<' {
+                        break;
+                    }
+                    s.push(c);
+                    self.chars.next();
+                }
+                self.skip();
+                if self.chars.peek() == Some(&'<') {
+                    self.chars.next();
+                    let units: String = self.chars.by_ref().take_while(|c| *c != '>').collect();
+                    Ok(Value::WithUnits(s, units.trim().to_string()))
+                } else {
+                    Ok(Value::Literal(s))
+                }
+            }
+            None => Err(anyhow!("Missing value in PDS label")),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Samples closer than this to the terminator or limb are dropped rather than corrected, as
+// the correction factors blow up there.
+const MIN_COSINE: f64 = 0.02;
+
+/// Photometric models that can be divided out of the observed values to flatten the
+/// brightness falloff toward the limb and terminator.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PhotometricModel {
+    None,
+
+    /// Minnaert model with a limb darkening exponent for each output channel (red, green,
+    /// blue). k = 1 is Lambertian.
+    Minnaert {
+        k: [f64; 3],
+    },
+
+    /// Lommel-Seeliger model, suited to dark, low albedo surfaces
+    LommelSeeliger,
+}
+
+impl PhotometricModel {
+    /// Parses a model name. Minnaert exponents are taken from `minnaert_k`.
+    pub fn from(s: &str, minnaert_k: [f64; 3]) -> Option<PhotometricModel> {
+        match s.to_lowercase().as_str() {
+            "none" => Some(PhotometricModel::None),
+            "minnaert" => Some(PhotometricModel::Minnaert { k: minnaert_k }),
+            "lommel-seeliger" | "lommelseeliger" => Some(PhotometricModel::LommelSeeliger),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, PhotometricModel::None)
+    }
+
+    /// Model brightness for an output channel given the cosines of the incidence (`mu0`)
+    /// and emission (`mu`) angles. Observed values are divided by this. None where the
+    /// location is too close to the terminator or limb to correct.
+    pub fn factor(&self, channel: usize, mu0: f64, mu: f64) -> Option<f64> {
+        if self.is_none() {
+            return Some(1.0);
+        }
+
+        if mu0 < MIN_COSINE || mu < MIN_COSINE {
+            return None;
+        }
+
+        match self {
+            PhotometricModel::None => Some(1.0),
+            PhotometricModel::Minnaert { k } => {
+                Some(mu0.powf(k[channel]) * mu.powf(k[channel] - 1.0))
+            }
+            // Scaled to one when viewed and lit from directly overhead
+            PhotometricModel::LommelSeeliger => Some(2.0 * mu0 / (mu0 + mu)),
+        }
+    }
+}
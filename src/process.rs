@@ -1,55 +1,260 @@
 use crate::{
-    config, jcspice, junocam as jc, junocam::FrameletParameters,
-    lens::cylindrical::CylindricalLens, lens::fisheye::FisheyeEquisolidLens, lens::lens::Lens,
-    metadata, rawimage, strip::Strip, vprintln,
+    backplane::Backplanes,
+    backplane::SurfaceGeometry,
+    bundle, config,
+    coverage::Coverage,
+    ellipsoid::Ellipsoid,
+    ellipsoid::LatLon,
+    error::JunocamError,
+    fits,
+    interpolate::Interpolation,
+    jcspice,
+    lens::cylindrical::CylindricalLens,
+    lens::fisheye::FisheyeEquisolidLens,
+    lens::lens::Lens,
+    lens::perspective::PerspectiveLens,
+    metadata, output,
+    output::{OutputFormat, Stretch},
+    pds,
+    photometry::PhotometricModel,
+    projection::equirectangular::EquirectangularProjection,
+    projection::georeference::Georeference,
+    projection::orthographic::OrthographicProjection,
+    projection::polar::{AzimuthalMapping, PolarProjection, Pole},
+    projection::projection::{LatLonBounds, MapProjection},
+    rawimage, render,
+    render::FrameletSetGeometry,
+    vprintln,
 };
 
-use itertools::iproduct;
-use sciimg::drawable::{Drawable, Point};
-use sciimg::{matrix::Matrix, prelude::*, quaternion::Quaternion, vector::Vector};
+use sciimg::{prelude::*, quaternion::Quaternion};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum SupportedLens {
     Cylindrical,
     Fisheye,
+    Perspective,
+    Equirectangular,
+    Orthographic,
+    PolarStereographic,
+    LambertAzimuthal,
 }
 
 use colored::{self, Colorize};
 
+// Perspective field of view when none is configured
+const DEFAULT_PERSPECTIVE_FIELD_OF_VIEW: f64 = 60.0;
+
 impl SupportedLens {
     pub fn from(s: &str) -> Option<SupportedLens> {
         match s.to_lowercase().as_str() {
             "cylindrical" => Some(SupportedLens::Cylindrical),
             "fisheye" => Some(SupportedLens::Fisheye),
+            "perspective" => Some(SupportedLens::Perspective),
+            "equirectangular" => Some(SupportedLens::Equirectangular),
+            "orthographic" => Some(SupportedLens::Orthographic),
+            "stereographic" => Some(SupportedLens::PolarStereographic),
+            "lambert" => Some(SupportedLens::LambertAzimuthal),
             _ => None,
         }
     }
+
+    /// Field of view used when none is requested, in degrees
+    pub fn default_field_of_view(&self, defaults: &config::Defaults) -> f64 {
+        match self {
+            SupportedLens::Perspective => defaults
+                .perspective_field_of_view
+                .unwrap_or(DEFAULT_PERSPECTIVE_FIELD_OF_VIEW),
+            _ => defaults.fisheye_field_of_view,
+        }
+    }
+
+    /// True if the lens maps the surface of Jupiter rather than look directions from the
+    /// spacecraft.
+    pub fn is_surface_projection(&self) -> bool {
+        matches!(
+            self,
+            SupportedLens::Equirectangular
+                | SupportedLens::Orthographic
+                | SupportedLens::PolarStereographic
+                | SupportedLens::LambertAzimuthal
+        )
+    }
 }
 
-#[allow(clippy::borrowed_box)]
-fn xy_to_map_point(
-    x: usize,
-    y: usize,
-    framelet: &FrameletParameters,
-    spc_mtx: &Matrix,
-    lens: &Box<dyn Lens>,
-    strip: &Strip,
-    q: &Quaternion,
-    channel: usize,
-) -> Point {
-    let mut v = framelet.xy_to_vector(x as f64, y as f64);
-    v = spc_mtx.multiply_vector(&v);
-    v = q.rotate_vector(&v);
-
-    // Translate from spice coordinates to ours.
-    v = Vector::new(v.x, v.z, v.y);
-
-    let mut pt = lens.vector_to_point(&v);
-    let tl_v = strip.buffer.get(x, y);
-    pt.color.values[channel] = tl_v as f64;
-    pt
+/// Output image dimensions. Cylindrical maps with a requested resolution are sized to
+/// cover their bounds, everything else uses the requested width and height. A scale in
+/// kilometers per pixel needs the ellipsoid, so only applies to surface maps.
+pub fn map_size(context: &ProcessOptions, ellipsoid: Option<&Ellipsoid>) -> Result<(usize, usize)> {
+    if !matches!(
+        context.lens,
+        SupportedLens::Cylindrical | SupportedLens::Equirectangular
+    ) {
+        return Ok((context.width, context.height));
+    }
+
+    let degrees_per_pixel = match (context.degrees_per_pixel, context.map_scale, ellipsoid) {
+        (Some(dpp), _, _) => dpp,
+        (None, Some(km_per_pixel), Some(ellipsoid)) => {
+            // Kilometers per degree along the equator
+            km_per_pixel / ellipsoid.equatorial_radius().to_radians()
+        }
+        (None, Some(_), None) => {
+            return Err(anyhow!(
+                "Map scale in kilometers per pixel requires a surface projection"
+            ))
+        }
+        (None, None, _) => return Ok((context.width, context.height)),
+    };
+
+    let bounds = context.map_bounds.unwrap_or_else(LatLonBounds::global);
+    let west_positive = context.planetographic && context.lens == SupportedLens::Equirectangular;
+    let (width, height) = bounds.image_size(degrees_per_pixel, west_positive);
+    if width == 0 || height == 0 {
+        return Err(anyhow!(
+            "Map bounds {:?} are empty at {} degrees per pixel",
+            bounds,
+            degrees_per_pixel
+        ));
+    }
+    vprintln!(
+        "Map resolution {} degrees per pixel, output size {}x{}",
+        degrees_per_pixel,
+        width,
+        height
+    );
+    Ok((width, height))
+}
+
+fn sky_lens(context: &ProcessOptions, width: usize, height: usize) -> Option<Box<dyn Lens>> {
+    match context.lens {
+        SupportedLens::Cylindrical => {
+            let bounds = context.map_bounds.unwrap_or_else(LatLonBounds::global);
+            Some(Box::new(CylindricalLens::new(
+                width,
+                height,
+                bounds.top_lat,
+                bounds.bottom_lat,
+                bounds.left_lon,
+                bounds.right_lon,
+            )))
+        }
+        SupportedLens::Fisheye => Some(Box::new(FisheyeEquisolidLens::new(
+            width,
+            height,
+            13.0,
+            context.fov,
+        ))),
+        SupportedLens::Perspective => Some(Box::new(PerspectiveLens::new(
+            width,
+            height,
+            context.fov,
+            context.aspect_ratio,
+        ))),
+        _ => None,
+    }
+}
+
+pub fn surface_projection(
+    context: &ProcessOptions,
+    width: usize,
+    height: usize,
+    ellipsoid: Ellipsoid,
+    default_sub_observer: LatLon,
+) -> Option<Box<dyn MapProjection>> {
+    match context.lens {
+        SupportedLens::Equirectangular => match context.map_bounds {
+            Some(bounds) => Some(Box::new(EquirectangularProjection::new(
+                width,
+                height,
+                bounds.top_lat,
+                bounds.bottom_lat,
+                bounds.left_lon,
+                bounds.right_lon,
+                context.planetographic,
+                ellipsoid,
+            ))),
+            None => Some(Box::new(EquirectangularProjection::new_global(
+                width,
+                height,
+                context.planetographic,
+                ellipsoid,
+            ))),
+        },
+        SupportedLens::Orthographic => {
+            // User supplied coordinates follow the --planetographic convention
+            let sub_observer = LatLon {
+                lat: match context.sub_observer_lat {
+                    Some(lat) if context.planetographic => {
+                        ellipsoid.planetographic_to_planetocentric(lat)
+                    }
+                    Some(lat) => lat,
+                    None => default_sub_observer.lat,
+                },
+                lon: match context.sub_observer_lon {
+                    Some(lon) if context.planetographic => (360.0 - lon).rem_euclid(360.0),
+                    Some(lon) => lon,
+                    None => default_sub_observer.lon,
+                },
+            };
+            let scale = context.map_scale.unwrap_or_else(|| {
+                OrthographicProjection::fit_disc_scale(width, height, &ellipsoid)
+            });
+            vprintln!(
+                "Orthographic sub-observer point: {:?}, scale: {} km/pixel",
+                sub_observer,
+                scale
+            );
+            Some(Box::new(OrthographicProjection::new(
+                width,
+                height,
+                &sub_observer,
+                scale,
+                ellipsoid,
+            )))
+        }
+        SupportedLens::PolarStereographic | SupportedLens::LambertAzimuthal => {
+            let mapping = if context.lens == SupportedLens::PolarStereographic {
+                AzimuthalMapping::Stereographic
+            } else {
+                AzimuthalMapping::EqualArea
+            };
+
+            // Default to whichever pole the spacecraft is over
+            let pole = context.pole.unwrap_or(if default_sub_observer.lat < 0.0 {
+                Pole::South
+            } else {
+                Pole::North
+            });
+
+            let bounding_lat = if context.planetographic {
+                ellipsoid.planetographic_to_planetocentric(context.bounding_lat.abs())
+            } else {
+                context.bounding_lat.abs()
+            };
+
+            let projection = PolarProjection::new(
+                width,
+                height,
+                pole,
+                mapping,
+                bounding_lat,
+                context.map_scale,
+                &ellipsoid,
+            );
+            vprintln!(
+                "{:?} polar projection of the {:?} pole to latitude {}, scale: {} km/pixel",
+                mapping,
+                pole,
+                bounding_lat,
+                projection.scale()
+            );
+            Some(Box::new(projection))
+        }
+        _ => None,
+    }
 }
 
 pub struct ProcessOptions {
@@ -63,37 +268,138 @@ pub struct ProcessOptions {
     pub width: usize,
     pub height: usize,
     pub fov: f64,
+    pub aspect_ratio: f64,
     pub pitch: f64,
     pub yaw: f64,
     pub roll: f64,
     pub lens: SupportedLens,
     pub fast: bool,
     pub decorrelated_color_stretch: bool,
+    pub interpolation: Interpolation,
+    pub legacy_renderer: bool,
+    pub planetographic: bool,
+    pub sub_observer_lat: Option<f64>,
+    pub sub_observer_lon: Option<f64>,
+    pub map_scale: Option<f64>,
+    pub pole: Option<Pole>,
+    pub bounding_lat: f64,
+    pub map_bounds: Option<LatLonBounds>,
+    pub degrees_per_pixel: Option<f64>,
+    pub backplanes: bool,
+    pub photometric_model: PhotometricModel,
+    pub radiometric: bool,
+    pub output_format: OutputFormat,
+    pub calibrated_fits: Option<String>,
+    pub coverage_mask: bool,
 }
 
-pub fn process_image(context: &ProcessOptions) -> Result<Image> {
-    let juno_config = match config::load_configuration() {
-        Ok(jc) => jc,
-        Err(why) => return Err(why),
-    };
+impl ProcessOptions {
+    /// True if rendering needs the spacecraft position relative to Jupiter
+    pub fn needs_body_geometry(&self) -> bool {
+        self.lens.is_surface_projection() || self.backplanes || !self.photometric_model.is_none()
+    }
+}
+
+/// Planetocentric latitude/longitude directly beneath the spacecraft
+pub fn sub_spacecraft_point(ellipsoid: &Ellipsoid, et: f64) -> LatLon {
+    ellipsoid.to_lat_lon(&jcspice::observer_position_body_fixed(
+        "JUNO",
+        "JUPITER",
+        "IAU_JUPITER",
+        et,
+    ))
+}
+
+/// A calibrated input image and the times needed to locate each of its framelet sets
+pub struct PreparedImage {
+    pub metadata: metadata::Metadata,
+    pub raw_image: rawimage::RawImage,
+    pub start_time_et: f64,
+    pub mid_time_et: f64,
+    pub framelet_set_times_et: Vec<f64>,
+}
+
+/// Loads and calibrates an input image and furnishes the kernels needed to locate it.
+/// Spacecraft position kernels are only loaded for surface projections.
+pub fn prepare_image(context: &ProcessOptions) -> Result<PreparedImage> {
+    let juno_config = config::load_configuration()?;
 
     vprintln!("Loading metadata from {}", context.metadata);
-    let md = match metadata::Metadata::new_from_file(&context.metadata) {
-        Ok(md) => md,
-        Err(why) => return Err(why),
-    };
+    let md = metadata::Metadata::new_from_file(&context.metadata)?;
 
     vprintln!("Loading image file from {}", context.input);
-    vprintln!("Decompanding with table '{:?}'", md.sample_bit_mode_id);
-    let mut raw_image = match rawimage::RawImage::new_from_image_with_decompand(
-        &context.input,
-        md.sample_bit_mode_id,
-    ) {
-        Ok(img) => img,
-        Err(why) => return Err(why),
+    let from_fits = fits::is_fits_file(&context.input);
+    let mut raw_image = if from_fits {
+        // Already decompanded and calibrated when it was written
+        rawimage::RawImage::new_from_fits(&fits::FitsImage::open(&context.input)?)?
+    } else if bundle::is_zip_file(&context.input) {
+        vprintln!("Decompanding with table '{:?}'", md.sample_bit_mode_id);
+        rawimage::RawImage::new_from_zip_with_decompand(&context.input, md.sample_bit_mode_id)?
+    } else if pds::is_pds_file(&context.input) {
+        vprintln!("Decompanding with table '{:?}'", md.sample_bit_mode_id);
+        rawimage::RawImage::new_from_pds_with_decompand(&context.input, md.sample_bit_mode_id)?
+    } else {
+        vprintln!("Decompanding with table '{:?}'", md.sample_bit_mode_id);
+        match rawimage::RawImage::new_from_image_with_decompand(
+            &context.input,
+            md.sample_bit_mode_id,
+        ) {
+            Ok(img) => img,
+            Err(why) => return Err(why),
+        }
     };
 
-    if !context.fast && juno_config.defaults.apply_calibration {
+    let cameras = md.filters.cameras();
+    vprintln!("Filters: {:?}", cameras);
+    let omitted = render::omitted_cameras(&cameras);
+    if !omitted.is_empty() {
+        vprintln!(
+            "{}: {:?} left out of the color output",
+            "Warning:".bright_yellow(),
+            omitted
+        );
+    }
+    let empty = render::empty_bands(&cameras);
+    if !empty.is_empty() {
+        vprintln!(
+            "{}: No filter for output band(s) {:?}, which will be left empty",
+            "Warning:".bright_yellow(),
+            empty
+        );
+    }
+    vprintln!(
+        "Sampling factor: {}, TDI stages: {}",
+        md.sampling_factor,
+        md.jno_tdi_stages_count
+    );
+    raw_image.set_layout(&cameras, md.sampling_factor as usize)?;
+
+    // Zero filled lines only stand out before calibration. Calibrated FITS inputs only have
+    // their partial last framelet set to report.
+    let gaps = if from_fits {
+        raw_image.gap_report()
+    } else {
+        raw_image.detect_gaps()
+    };
+    if gaps.has_gaps() {
+        vprintln!(
+            "{}: Data gaps left out of projection: {}",
+            "Warning:".bright_yellow(),
+            gaps
+        );
+    } else {
+        vprintln!("No data gaps found");
+    }
+
+    if from_fits {
+        vprintln!(
+            "FITS input, skipping framelet calibration (dark/flat applied: {}, I/F: {})",
+            raw_image.is_darknoise_applied(),
+            raw_image.is_radiometric_applied()
+        );
+    }
+
+    if !context.fast && !from_fits && juno_config.defaults.apply_calibration {
         vprintln!("Applying framelet calibration...");
         match raw_image.apply_darknoise() {
             Ok(_) => {}
@@ -101,7 +407,7 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
         };
     }
 
-    if !context.fast && juno_config.defaults.apply_infill_correction {
+    if !context.fast && !from_fits && juno_config.defaults.apply_infill_correction {
         vprintln!("Applying blemish infill correction...");
         match raw_image.apply_infill_correction() {
             Ok(_) => {}
@@ -109,7 +415,7 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
         };
     }
 
-    if !context.fast && juno_config.defaults.apply_hot_pixel_correction {
+    if !context.fast && !from_fits && juno_config.defaults.apply_hot_pixel_correction {
         vprintln!("Applying hot pixel detection and correction...");
         vprintln!(
             "Hot Pixel Correction Window Size: {}",
@@ -128,7 +434,41 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
         };
     }
 
-    if !context.fast && juno_config.defaults.apply_weights {
+    if context.radiometric && !raw_image.is_radiometric_applied() {
+        if !raw_image.is_darknoise_applied() {
+            return Err(anyhow!(
+                "Radiometric calibration requires dark/flat calibration to be applied"
+            ));
+        }
+
+        let radiometry = juno_config
+            .radiometry
+            .as_ref()
+            .ok_or_else(|| anyhow!("No radiometric constants found in configuration"))?;
+
+        vprintln!(
+            "Applying radiometric calibration to I/F (exposure {} ms, solar distance {} km)...",
+            md.exposure_duration,
+            md.solar_distance
+        );
+        raw_image.apply_radiometric_calibration(
+            radiometry,
+            md.exposure_duration as f64,
+            md.solar_distance as f64,
+        )?;
+    }
+
+    if let Some(calibrated_fits) = &context.calibrated_fits {
+        vprintln!("Writing calibrated framelets to {}", calibrated_fits);
+        raw_image.save_fits(calibrated_fits, &fits::metadata_header(&md))?;
+    }
+
+    // Channel weights are a color balance and would skew calibrated values
+    if !context.fast
+        && juno_config.defaults.apply_weights
+        && !context.radiometric
+        && !raw_image.is_radiometric_applied()
+    {
         vprintln!(
             "Applying channel weight multiples ({}, {}, {} X R, G, B)...",
             context.red_weight,
@@ -146,7 +486,7 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
     }
 
     vprintln!("Loading base kernels...");
-    jcspice::furnish_base();
+    jcspice::furnish_base()?;
 
     let interframe_delay = md.interframe_delay as f64;
     let interframe_delay_correction = juno_config.defaults.interframe_delay_correction;
@@ -165,26 +505,19 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
     let start_time_et = jcspice::string_to_et(&start_time) + start_time_correction;
 
     let kernel_search_pattern = if context.predicted {
-        juno_config.spice.ck_pre_pattern
+        &juno_config.spice.ck_pre_pattern
     } else {
-        juno_config.spice.ck_rec_pattern
+        &juno_config.spice.ck_rec_pattern
     };
 
     vprintln!("Finding spacecraft pointing kernel...");
-    match jcspice::find_kernel_with_date(&kernel_search_pattern, start_time_et) {
-        Ok(kernel_path) => {
-            vprintln!("Found CK kernel with matching time range: {}", kernel_path);
-            // Note: I really don't like embedding match statements within match statements.
-            match jcspice::furnish(&kernel_path) {
-                Ok(_) => {}
-                Err(why) => return Err(why),
-            };
-        }
-        Err(why) => {
-            eprintln!("Error: {:?}", why);
-            return Err(why);
-        }
-    }
+    let kernel_path = jcspice::find_kernel_with_date(kernel_search_pattern, start_time_et)
+        .map_err(|why| {
+            JunocamError::Spice(format!("No pointing kernel covers {}: {}", start_time, why))
+        })?;
+    vprintln!("Found CK kernel with matching time range: {}", kernel_path);
+    jcspice::furnish(&kernel_path)
+        .map_err(|why| JunocamError::Spice(format!("Failed to load {}: {}", kernel_path, why)))?;
 
     let stop_time_utc = md.stop_time;
     vprintln!("Stop time from metadata: {:?}", stop_time_utc);
@@ -193,20 +526,54 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
     let stop_time_et = jcspice::string_to_et(&stop_time) + start_time_correction;
 
     let mid_time_et = (start_time_et + stop_time_et) / 2.0;
+    let framelet_set_times_et: Vec<f64> = (0..raw_image.get_framelet_set_count())
+        .map(|t| start_time_et + (t as f64 * (interframe_delay + interframe_delay_correction)))
+        .collect();
+
+    if context.needs_body_geometry() {
+        let spk_search_pattern = if context.predicted {
+            &juno_config.spice.spk_pre_pattern
+        } else {
+            &juno_config.spice.spk_rec_pattern
+        };
+
+        if let Some(spk_search_pattern) = spk_search_pattern {
+            vprintln!("Finding spacecraft position kernel...");
+            let kernel_path = jcspice::find_spk_with_date(spk_search_pattern, start_time_et)?;
+            vprintln!("Found SPK kernel with matching time range: {}", kernel_path);
+            jcspice::furnish(&kernel_path)?;
+        } else {
+            vprintln!(
+                "No SPK search pattern configured, relying on base kernels for spacecraft position"
+            );
+        }
+    }
+
+    Ok(PreparedImage {
+        metadata: md,
+        raw_image,
+        start_time_et,
+        mid_time_et,
+        framelet_set_times_et,
+    })
+}
+
+pub fn process_image(context: &ProcessOptions) -> Result<Image> {
+    let juno_config = config::load_configuration()?;
+
+    let PreparedImage {
+        metadata: md,
+        raw_image,
+        mid_time_et,
+        framelet_set_times_et,
+        ..
+    } = prepare_image(context)?;
+
     let midtime_matrix = jcspice::pos_transform_matrix("JUNO_JUNOCAM", "J2000", mid_time_et);
 
     let r = Quaternion::from_pitch_roll_yaw(180.0_f64.to_radians(), 0.0, 0.0);
     let p = Quaternion::from_pitch_roll_yaw(0.0, 90.0_f64.to_radians(), 0.0);
 
-    let line_sample_increment: usize = if context.fast {
-        vprintln!(
-            "{}: Fast option enabled. Skipping every other line & sample from source data",
-            "Warning:".bright_yellow()
-        );
-        2
-    } else {
-        1
-    };
     // We flip them to handle Spice's Z-up to our Y-up coordinates
     let user_roll = Quaternion::from_pitch_roll_yaw(context.roll, 0.0, 0.0);
     let user_yaw = Quaternion::from_pitch_roll_yaw(0.0, 0.0, context.pitch);
@@ -216,106 +583,210 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
         &user_pitch.times(&r.times(&p.times(&Quaternion::from_matrix(&midtime_matrix).invert()))),
     ));
 
-    let mut cyl_map = Image::create(context.width, context.height);
-
-    let lens: Box<dyn Lens> = match context.lens {
-        SupportedLens::Cylindrical => Box::new(CylindricalLens::new(
-            cyl_map.width,
-            cyl_map.height,
-            90.0,
-            -90.0,
-            0.0,
-            360.0,
-        )),
-        SupportedLens::Fisheye => Box::new(FisheyeEquisolidLens::new(
-            cyl_map.width,
-            cyl_map.height,
-            13.0,
-            context.fov,
-        )),
-    };
-    //let lens = CylindricalLens::new(cyl_map.width, cyl_map.height, 90.0, -90.0, 0.0, 360.0);
-    //let lens = FisheyeEquisolidLens::new(cyl_map.width, cyl_map.height, 13.0, fov);
-
-    vprintln!("Processing triplets...");
-    (0..raw_image.get_triplet_count()).for_each(|t| {
-        vprintln!("Processing triplet #{}", (t + 1));
-        let triplet = &raw_image.triplets[t as usize];
-
-        let image_time_et =
-            start_time_et + (t as f64 * (interframe_delay + interframe_delay_correction));
-        let spc_mtx = jcspice::pos_transform_matrix("JUNO_JUNOCAM", "J2000", image_time_et);
-
-        iproduct!(
-            (2..(128 - line_sample_increment - 1)).step_by(line_sample_increment),
-            (22..(1648 - line_sample_increment)).step_by(line_sample_increment),
-            0..=2
-        )
-        .for_each(|(y, x, s)| {
-            let strip = &triplet.channels[s];
-
-            let framelet = match s {
-                0 => &jc::JUNO_JUNOCAM_BLUE,
-                1 => &jc::JUNO_JUNOCAM_GREEN,
-                2 => &jc::JUNO_JUNOCAM_RED,
-                4 => &jc::JUNO_JUNOCAM_METHANE,
-                _ => panic!("Invalid filter band"),
+    let (mut cyl_map, georeference, coverage) = if !context.lens.is_surface_projection() {
+        let (width, height) = map_size(context, None)?;
+        let mut cyl_map = output_image(&raw_image, width, height)?;
+        let lens = sky_lens(context, width, height)
+            .ok_or_else(|| anyhow!("Unsupported camera lens: {:?}", context.lens))?;
+
+        // Locating the body is only needed to apply photometry or compute backplanes
+        let ellipsoid = if context.needs_body_geometry() {
+            Some(Ellipsoid::jupiter()?)
+        } else {
+            None
+        };
+
+        vprintln!("Computing framelet set pointing...");
+        let geometry: Vec<FrameletSetGeometry> = framelet_set_times_et
+            .iter()
+            .map(|et| {
+                if ellipsoid.is_some() {
+                    FrameletSetGeometry::at_with_body(*et)
+                } else {
+                    FrameletSetGeometry::at(*et)
+                }
+            })
+            .collect();
+
+        let coverage = if context.legacy_renderer {
+            let line_sample_increment: usize = if context.fast {
+                vprintln!(
+                    "{}: Fast option enabled. Skipping every other line & sample from source data",
+                    "Warning:".bright_yellow()
+                );
+                2
+            } else {
+                1
             };
-            let tl = xy_to_map_point(x, y, framelet, &spc_mtx, &lens, strip, &q, 2 - s);
-            let bl = xy_to_map_point(
-                x,
-                y + line_sample_increment,
-                framelet,
-                &spc_mtx,
-                &lens,
-                strip,
-                &q,
-                2 - s,
-            );
-            let br = xy_to_map_point(
-                x + line_sample_increment,
-                y + line_sample_increment,
-                framelet,
-                &spc_mtx,
-                &lens,
-                strip,
+
+            if !context.photometric_model.is_none() {
+                vprintln!(
+                    "{}: Legacy renderer does not support photometric correction, ignoring",
+                    "Warning:".bright_yellow()
+                );
+            }
+
+            vprintln!("Processing framelet sets with legacy forward renderer...");
+            render::render_forward(
+                &raw_image,
+                &geometry,
                 &q,
-                2 - s,
+                lens.as_ref(),
+                line_sample_increment,
+                &mut cyl_map,
+            )
+        } else {
+            vprintln!(
+                "Rendering output with {:?} interpolation...",
+                context.interpolation
             );
-            let tr = xy_to_map_point(
-                x + line_sample_increment,
-                y,
-                framelet,
-                &spc_mtx,
-                &lens,
-                strip,
+            render::render_backward(
+                &raw_image,
+                &geometry,
                 &q,
-                2 - s,
+                lens.as_ref(),
+                context.interpolation,
+                context.photometric_model,
+                ellipsoid.as_ref(),
+                &mut cyl_map,
+            )
+        };
+
+        if let (true, Some(ellipsoid)) = (context.backplanes, &ellipsoid) {
+            vprintln!("Computing geometry backplanes...");
+            save_backplanes(
+                context,
+                &render::sky_geometry(
+                    &geometry,
+                    &raw_image.cameras(),
+                    &q,
+                    lens.as_ref(),
+                    ellipsoid,
+                    width,
+                    height,
+                ),
+            )?;
+        }
+        (cyl_map, None, coverage)
+    } else {
+        let ellipsoid = Ellipsoid::jupiter()?;
+        vprintln!(
+            "Jupiter radii: {}, {}, {} km",
+            ellipsoid.a,
+            ellipsoid.b,
+            ellipsoid.c
+        );
+
+        // Without a requested viewpoint, look down on the sub-spacecraft point at mid-exposure
+        let sub_spacecraft = sub_spacecraft_point(&ellipsoid, mid_time_et);
+
+        let (width, height) = map_size(context, Some(&ellipsoid))?;
+        let mut cyl_map = output_image(&raw_image, width, height)?;
+        let projection = surface_projection(context, width, height, ellipsoid, sub_spacecraft)
+            .ok_or_else(|| anyhow!("Unsupported surface projection: {:?}", context.lens))?;
+
+        vprintln!("Computing framelet set pointing and spacecraft position...");
+        let geometry: Vec<FrameletSetGeometry> = framelet_set_times_et
+            .iter()
+            .map(|et| FrameletSetGeometry::at_with_body(*et))
+            .collect();
+
+        if context.legacy_renderer {
+            vprintln!(
+                "{}: Legacy renderer does not support surface projections, ignoring",
+                "Warning:".bright_yellow()
             );
+        }
 
-            cyl_map.paint_square_with_channel_rule(&tl, &bl, &br, &tr, true, |c| c == 2 - s);
-        });
-    });
+        vprintln!(
+            "Rendering surface projection with {:?} interpolation...",
+            context.interpolation
+        );
+        let coverage = render::render_surface(
+            &raw_image,
+            &geometry,
+            projection.as_ref(),
+            &ellipsoid,
+            context.interpolation,
+            context.photometric_model,
+            &mut cyl_map,
+        );
 
-    vprintln!("Data range, pre-normalization:");
-    vprintln!("MinMax: {:?}", cyl_map.get_min_max_all_channel());
+        if context.backplanes {
+            vprintln!("Computing geometry backplanes...");
+            save_backplanes(
+                context,
+                &render::surface_geometry(
+                    &geometry,
+                    &raw_image.cameras(),
+                    projection.as_ref(),
+                    &ellipsoid,
+                    width,
+                    height,
+                ),
+            )?;
+        }
 
-    if !context.decorrelated_color_stretch || juno_config.defaults.correlated_color_balancing {
-        vprintln!("Applying color channel correlated value stretching/normalization");
-        cyl_map.normalize_to_16bit();
-    } else {
-        vprintln!("Applying color channel decorrelated value stretching/normalization");
-        cyl_map.normalize_to_16bit_seperate_channels();
+        let georeference = projection.georeference();
+        if georeference.is_none() {
+            vprintln!(
+                "{:?} maps have no standard reference system, not writing georeferencing",
+                context.lens
+            );
+        }
+        (cyl_map, georeference, coverage)
+    };
+
+    vprintln!(
+        "Rendered data covers {} of {} pixels",
+        coverage.covered_pixels(),
+        cyl_map.width * cyl_map.height
+    );
+    if context.coverage_mask {
+        save_coverage_mask(context, &coverage)?;
     }
 
-    vprintln!("Data range, post-normalization:");
-    vprintln!("MinMax: {:?}", cyl_map.get_min_max_all_channel());
+    // Float output keeps values as they were rendered
+    if context.output_format.is_float() {
+        if context.decorrelated_color_stretch {
+            vprintln!(
+                "{}: Decorrelated color stretch is not applied to float output",
+                "Warning:".bright_yellow()
+            );
+        }
+        if let Some(output_file) = &context.output {
+            let mut header = fits::metadata_header(&md);
+            header.set(
+                "PROJECTN",
+                fits::HeaderValue::Str(format!("{:?}", context.lens)),
+                None,
+            );
+            output::save_float_image(
+                &cyl_map,
+                output_file,
+                context.output_format,
+                Stretch::None,
+                Some(&header),
+            )?;
+            save_georeference(georeference, output_file)?;
+        }
+        return Ok(cyl_map);
+    }
+
+    let stretches = normalize_output(
+        &mut cyl_map,
+        context.decorrelated_color_stretch && !juno_config.defaults.correlated_color_balancing,
+    );
+
+    // Pixels nothing was rendered to are left transparent
+    coverage.apply_alpha(&mut cyl_map);
 
-    cyl_map.set_using_alpha(false);
     match &context.output {
         Some(output) => {
             vprintln!("Writing output image to {}", output);
             cyl_map.save(output)?;
+            output::save_stretch_sidecar(output, &stretches)?;
+            save_georeference(georeference, output)?;
         }
         None => {}
     };
@@ -323,6 +794,85 @@ pub fn process_image(context: &ProcessOptions) -> Result<Image> {
     Ok(cyl_map)
 }
 
+// Color images are rendered to red, green and blue bands, methane images to a single band
+fn output_image(raw_image: &rawimage::RawImage, width: usize, height: usize) -> Result<Image> {
+    let bands = render::output_band_count(&raw_image.cameras());
+    vprintln!("Output bands: {}", bands);
+    Image::new_with_bands(width, height, bands, ImageMode::U16BIT)
+}
+
+/// Writes world file and projection sidecars for a map, where it has a reference system
+pub fn save_georeference(georeference: Option<Georeference>, output_file: &str) -> Result<()> {
+    match georeference {
+        Some(georeference) => georeference.save_sidecars(output_file),
+        None => Ok(()),
+    }
+}
+
+fn save_coverage_mask(context: &ProcessOptions, coverage: &Coverage) -> Result<()> {
+    match &context.output {
+        Some(output) => {
+            let mask_filename = output::replace_extension(output, "-mask.png");
+            vprintln!("Writing coverage mask to {}", mask_filename);
+            coverage.to_mask()?.save(&mask_filename)
+        }
+        None => {
+            vprintln!(
+                "{}: No output file specified, coverage mask not written",
+                "Warning:".bright_yellow()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn save_backplanes(context: &ProcessOptions, rows: &[Vec<Option<SurfaceGeometry>>]) -> Result<()> {
+    match &context.output {
+        Some(output) => Backplanes::from_rows(rows)?.save(output, context.output_format),
+        None => {
+            vprintln!(
+                "{}: No output file specified, backplanes not written",
+                "Warning:".bright_yellow()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Stretches the rendered output to the 16 bit range, either across all channels or for
+/// each channel independently. Returns the stretch applied to each channel.
+pub fn normalize_output(map: &mut Image, decorrelated: bool) -> Vec<Stretch> {
+    vprintln!("Data range, pre-normalization:");
+    let mm = map.get_min_max_all_channel();
+    vprintln!("MinMax: {:?}", mm);
+
+    let to_16bit = |min: f32, max: f32| Stretch::Linear {
+        min,
+        max,
+        out_min: 0.0,
+        out_max: 65535.0,
+    };
+    let stretches = if !decorrelated {
+        vprintln!("Applying color channel correlated value stretching/normalization");
+        map.normalize_to_16bit();
+        vec![to_16bit(mm.min, mm.max); map.num_bands()]
+    } else {
+        vprintln!("Applying color channel decorrelated value stretching/normalization");
+        let stretches = (0..map.num_bands())
+            .map(|b| {
+                let mm = map.get_band(b).get_min_max();
+                to_16bit(mm.min, mm.max)
+            })
+            .collect();
+        map.normalize_to_16bit_seperate_channels();
+        stretches
+    };
+
+    vprintln!("Data range, post-normalization:");
+    vprintln!("MinMax: {:?}", map.get_min_max_all_channel());
+    stretches
+}
+
 trait NormSeperateChannel {
     fn normalize_to_16bit_seperate_channels(&mut self);
 }
@@ -0,0 +1,143 @@
+use crate::ellipsoid::{Ellipsoid, LatLon};
+use crate::projection::georeference::{Georeference, JupiterCrs, JUPITER_IAU2015_SPHERE_RADIUS};
+use crate::projection::projection::{unwrap_right_lon, MapProjection};
+
+/// Simple cylindrical (plate carrée) map of the body surface. When `planetographic` is set,
+/// the map's latitudes are planetographic and its longitudes are west-positive, otherwise
+/// they are planetocentric and east-positive. Edge longitudes the other way around make a
+/// map that wraps through 0/360.
+pub struct EquirectangularProjection {
+    image_width: usize,
+    image_height: usize,
+    top_lat: f64,
+    bottom_lat: f64,
+    left_lon: f64,
+    right_lon: f64,
+    planetographic: bool,
+    ellipsoid: Ellipsoid,
+}
+
+impl EquirectangularProjection {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        image_width: usize,
+        image_height: usize,
+        top_lat: f64,
+        bottom_lat: f64,
+        left_lon: f64,
+        right_lon: f64,
+        planetographic: bool,
+        ellipsoid: Ellipsoid,
+    ) -> EquirectangularProjection {
+        EquirectangularProjection {
+            image_width,
+            image_height,
+            top_lat,
+            bottom_lat,
+            left_lon,
+            right_lon: unwrap_right_lon(left_lon, right_lon, planetographic),
+            planetographic,
+            ellipsoid,
+        }
+    }
+
+    /// Full globe in the requested latitude/longitude system
+    pub fn new_global(
+        image_width: usize,
+        image_height: usize,
+        planetographic: bool,
+        ellipsoid: Ellipsoid,
+    ) -> EquirectangularProjection {
+        // West longitudes conventionally increase to the left
+        let (left_lon, right_lon) = if planetographic {
+            (360.0, 0.0)
+        } else {
+            (0.0, 360.0)
+        };
+        EquirectangularProjection::new(
+            image_width,
+            image_height,
+            90.0,
+            -90.0,
+            left_lon,
+            right_lon,
+            planetographic,
+            ellipsoid,
+        )
+    }
+}
+
+impl MapProjection for EquirectangularProjection {
+    fn point_to_lat_lon(&self, x: f64, y: f64) -> Option<LatLon> {
+        if x < 0.0 || y < 0.0 || x >= self.image_width as f64 || y >= self.image_height as f64 {
+            return None;
+        }
+
+        let lat = self.top_lat - y / self.image_height as f64 * (self.top_lat - self.bottom_lat);
+        let lon = self.left_lon + x / self.image_width as f64 * (self.right_lon - self.left_lon);
+
+        let (lat, lon) = if self.planetographic {
+            (
+                self.ellipsoid.planetographic_to_planetocentric(lat),
+                360.0 - lon,
+            )
+        } else {
+            (lat, lon)
+        };
+
+        Some(LatLon {
+            lat,
+            lon: lon.rem_euclid(360.0),
+        })
+    }
+
+    fn lat_lon_to_point(&self, ll: &LatLon) -> Option<(f64, f64)> {
+        let (lat, lon) = if self.planetographic {
+            (
+                self.ellipsoid.planetocentric_to_planetographic(ll.lat),
+                360.0 - ll.lon,
+            )
+        } else {
+            (ll.lat, ll.lon)
+        };
+
+        // Bring the longitude into the map's 360 degree window
+        let min_lon = self.left_lon.min(self.right_lon);
+        let lon = min_lon + (lon - min_lon).rem_euclid(360.0);
+
+        let x = (lon - self.left_lon) / (self.right_lon - self.left_lon) * self.image_width as f64;
+        let y = (self.top_lat - lat) / (self.top_lat - self.bottom_lat) * self.image_height as f64;
+
+        if x < 0.0 || y < 0.0 || x > self.image_width as f64 || y > self.image_height as f64 {
+            None
+        } else {
+            Some((x, y))
+        }
+    }
+
+    // Only planetocentric maps are linear in the reference sphere's coordinates
+    fn georeference(&self) -> Option<Georeference> {
+        if self.planetographic {
+            return None;
+        }
+
+        // Maps reaching past 180 degrees east, such as the default 0 to 360, only fall within
+        // the reference system centered on 180 degrees
+        let crs = if self.left_lon.max(self.right_lon) > 180.0 {
+            JupiterCrs::Equirectangular180
+        } else {
+            JupiterCrs::Equirectangular
+        };
+
+        let meters_per_degree = JUPITER_IAU2015_SPHERE_RADIUS.to_radians();
+        Some(Georeference {
+            crs,
+            origin_x: (self.left_lon - crs.central_meridian()) * meters_per_degree,
+            origin_y: self.top_lat * meters_per_degree,
+            pixel_width: (self.right_lon - self.left_lon) / self.image_width as f64
+                * meters_per_degree,
+            pixel_height: (self.bottom_lat - self.top_lat) / self.image_height as f64
+                * meters_per_degree,
+        })
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{output, vprintln};
+
+use anyhow::Result;
+use std::fs;
+
+/// Radius of the IAU 2015 Jupiter reference sphere, meters
+pub const JUPITER_IAU2015_SPHERE_RADIUS: f64 = 69_911_000.0;
+
+/// IAU 2015 coordinate reference systems for Jupiter, all on the reference sphere with
+/// planetocentric latitude and east-positive longitude
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum JupiterCrs {
+    /// IAU_2015:59910, centered on the 0 degree meridian
+    Equirectangular,
+
+    /// IAU_2015:59915, centered on the 180 degree meridian, for maps running 0 to 360
+    /// degrees east
+    Equirectangular180,
+
+    /// IAU_2015:59930
+    NorthPolarStereographic,
+
+    /// IAU_2015:59935
+    SouthPolarStereographic,
+}
+
+impl JupiterCrs {
+    pub fn code(&self) -> u32 {
+        match self {
+            JupiterCrs::Equirectangular => 59910,
+            JupiterCrs::Equirectangular180 => 59915,
+            JupiterCrs::NorthPolarStereographic => 59930,
+            JupiterCrs::SouthPolarStereographic => 59935,
+        }
+    }
+
+    /// Longitude at the center of the reference system's map coordinates, degrees east
+    pub fn central_meridian(&self) -> f64 {
+        match self {
+            JupiterCrs::Equirectangular180 => 180.0,
+            _ => 0.0,
+        }
+    }
+
+    /// ESRI style WKT, as expected in a `.prj` sidecar
+    pub fn wkt(&self) -> String {
+        let geogcs = format!(
+            "GEOGCS[\"Jupiter (2015) - Sphere / Ocentric\",\
+             DATUM[\"Jupiter (2015) - Sphere\",\
+             SPHEROID[\"Jupiter (2015) - Sphere\",{},0]],\
+             PRIMEM[\"Reference Meridian\",0],\
+             UNIT[\"degree\",0.0174532925199433]]",
+            JUPITER_IAU2015_SPHERE_RADIUS
+        );
+
+        let (name, projection) = match self {
+            JupiterCrs::Equirectangular | JupiterCrs::Equirectangular180 => {
+                let central_meridian = self.central_meridian();
+                (
+                    if central_meridian == 0.0 {
+                        "Equirectangular, clon = 0"
+                    } else {
+                        "Equirectangular, clon = 180"
+                    },
+                    format!(
+                        "PROJECTION[\"Equirectangular\"],\
+                         PARAMETER[\"standard_parallel_1\",0],\
+                         PARAMETER[\"central_meridian\",{}]",
+                        central_meridian
+                    ),
+                )
+            }
+            JupiterCrs::NorthPolarStereographic | JupiterCrs::SouthPolarStereographic => {
+                let (name, lat) = if *self == JupiterCrs::NorthPolarStereographic {
+                    ("North Polar", 90)
+                } else {
+                    ("South Polar", -90)
+                };
+                (
+                    name,
+                    format!(
+                        "PROJECTION[\"Polar_Stereographic\"],\
+                         PARAMETER[\"latitude_of_origin\",{}],\
+                         PARAMETER[\"central_meridian\",0],\
+                         PARAMETER[\"scale_factor\",1]",
+                        lat
+                    ),
+                )
+            }
+        };
+
+        format!(
+            "PROJCS[\"Jupiter (2015) - Sphere / Ocentric / {}\",{},{},\
+             PARAMETER[\"false_easting\",0],\
+             PARAMETER[\"false_northing\",0],\
+             UNIT[\"metre\",1],\
+             AUTHORITY[\"IAU_2015\",\"{}\"]]",
+            name,
+            geogcs,
+            projection,
+            self.code()
+        )
+    }
+}
+
+/// Placement of an output map in a Jupiter coordinate reference system. Map coordinates
+/// are in meters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Georeference {
+    pub crs: JupiterCrs,
+
+    /// Map coordinates of the upper left corner of the upper left pixel
+    pub origin_x: f64,
+    pub origin_y: f64,
+
+    /// Pixel size. Height is negative, as rows run southward.
+    pub pixel_width: f64,
+    pub pixel_height: f64,
+}
+
+impl Georeference {
+    /// The six lines of an ESRI world file. These locate the center of the upper left
+    /// pixel rather than its corner.
+    pub fn world_file(&self) -> String {
+        format!(
+            "{:.10}\n0.0\n0.0\n{:.10}\n{:.10}\n{:.10}\n",
+            self.pixel_width,
+            self.pixel_height,
+            self.origin_x + self.pixel_width / 2.0,
+            self.origin_y + self.pixel_height / 2.0
+        )
+    }
+
+    /// Writes a world file and `.prj` sidecar next to `output_file`
+    pub fn save_sidecars(&self, output_file: &str) -> Result<()> {
+        let world_file = output::replace_extension(output_file, world_file_extension(output_file));
+        vprintln!("Writing world file to {}", world_file);
+        fs::write(&world_file, self.world_file())?;
+
+        let prj_file = output::replace_extension(output_file, ".prj");
+        vprintln!(
+            "Writing IAU_2015:{} projection to {}",
+            self.crs.code(),
+            prj_file
+        );
+        fs::write(&prj_file, self.crs.wkt())?;
+        Ok(())
+    }
+}
+
+// Conventional world file extension for the image format
+fn world_file_extension(output_file: &str) -> &'static str {
+    let lower = output_file.to_lowercase();
+    if lower.ends_with(".png") {
+        ".pgw"
+    } else if lower.ends_with(".tif") || lower.ends_with(".tiff") {
+        ".tfw"
+    } else if lower.ends_with(".jpg") || lower.ends_with(".jpeg") {
+        ".jgw"
+    } else {
+        ".wld"
+    }
+}
@@ -0,0 +1,7 @@
+pub mod equirectangular;
+pub mod georeference;
+pub mod orthographic;
+pub mod polar;
+
+#[allow(clippy::module_inception)]
+pub mod projection;
@@ -0,0 +1,89 @@
+use crate::ellipsoid::{Ellipsoid, LatLon};
+use crate::projection::projection::MapProjection;
+
+use sciimg::vector::Vector;
+
+/// Orthographic view of the globe as seen from infinitely far above a sub-observer point.
+/// The sub-observer point is centered in the image with its meridian pointing up. Scale is
+/// in kilometers per pixel.
+pub struct OrthographicProjection {
+    image_width: usize,
+    image_height: usize,
+    scale: f64,
+    ellipsoid: Ellipsoid,
+
+    // Unit vectors of the view plane in the body-fixed frame
+    view: Vector,
+    east: Vector,
+    north: Vector,
+}
+
+impl OrthographicProjection {
+    pub fn new(
+        image_width: usize,
+        image_height: usize,
+        sub_observer: &LatLon,
+        scale: f64,
+        ellipsoid: Ellipsoid,
+    ) -> OrthographicProjection {
+        let lat = sub_observer.lat.to_radians();
+        let lon = sub_observer.lon.to_radians();
+
+        let view = Vector::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin());
+        let east = Vector::new(-lon.sin(), lon.cos(), 0.0);
+        let north = view.cross_product(&east).normalized();
+
+        OrthographicProjection {
+            image_width,
+            image_height,
+            scale,
+            ellipsoid,
+            view,
+            east,
+            north,
+        }
+    }
+
+    /// Scale at which the full disc fits within the image with a small margin
+    pub fn fit_disc_scale(image_width: usize, image_height: usize, ellipsoid: &Ellipsoid) -> f64 {
+        ellipsoid.equatorial_radius() * 2.0 * 1.05 / image_width.min(image_height) as f64
+    }
+}
+
+impl MapProjection for OrthographicProjection {
+    fn point_to_lat_lon(&self, x: f64, y: f64) -> Option<LatLon> {
+        let u = (x - self.image_width as f64 / 2.0) * self.scale;
+        let v = (self.image_height as f64 / 2.0 - y) * self.scale;
+
+        // Cast a ray toward the body from well outside of it
+        let distance = self.ellipsoid.equatorial_radius() * 10.0;
+        let origin = self
+            .view
+            .scale(distance)
+            .add(&self.east.scale(u))
+            .add(&self.north.scale(v));
+        let direction = self.view.scale(-1.0);
+
+        self.ellipsoid
+            .intersect(&origin, &direction)
+            .map(|p| self.ellipsoid.to_lat_lon(&p))
+    }
+
+    fn lat_lon_to_point(&self, ll: &LatLon) -> Option<(f64, f64)> {
+        let p = self.ellipsoid.surface_point(ll);
+
+        // Far side of the globe
+        if self.ellipsoid.surface_normal(&p).dot_product(&self.view) < 0.0 {
+            return None;
+        }
+
+        let x = p.dot_product(&self.east) / self.scale + self.image_width as f64 / 2.0;
+        let y = self.image_height as f64 / 2.0 - p.dot_product(&self.north) / self.scale;
+
+        if x < 0.0 || y < 0.0 || x > self.image_width as f64 || y > self.image_height as f64 {
+            None
+        } else {
+            Some((x, y))
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::ellipsoid::{Ellipsoid, LatLon};
+use crate::projection::georeference::{Georeference, JupiterCrs, JUPITER_IAU2015_SPHERE_RADIUS};
+use crate::projection::projection::MapProjection;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Pole {
+    North,
+    South,
+}
+
+impl Pole {
+    pub fn from(s: &str) -> Option<Pole> {
+        match s.to_lowercase().as_str() {
+            "north" | "n" => Some(Pole::North),
+            "south" | "s" => Some(Pole::South),
+            _ => None,
+        }
+    }
+}
+
+/// How distance from the pole on the map relates to colatitude
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AzimuthalMapping {
+    /// Conformal. Shapes are preserved locally, which suits cloud morphology.
+    Stereographic,
+
+    /// Lambert azimuthal equal-area. Areas are preserved, which suits coverage and feature
+    /// size statistics.
+    EqualArea,
+}
+
+impl AzimuthalMapping {
+    // Distance from the pole on a sphere of radius r
+    fn colatitude_to_rho(&self, colat: f64, r: f64) -> f64 {
+        match self {
+            AzimuthalMapping::Stereographic => 2.0 * r * (colat / 2.0).tan(),
+            AzimuthalMapping::EqualArea => 2.0 * r * (colat / 2.0).sin(),
+        }
+    }
+
+    fn rho_to_colatitude(&self, rho: f64, r: f64) -> Option<f64> {
+        match self {
+            AzimuthalMapping::Stereographic => Some(2.0 * (rho / (2.0 * r)).atan()),
+            AzimuthalMapping::EqualArea => {
+                let s = rho / (2.0 * r);
+                if s > 1.0 {
+                    None
+                } else {
+                    Some(2.0 * s.asin())
+                }
+            }
+        }
+    }
+}
+
+/// Azimuthal projection centered on either pole, out to a bounding latitude. Uses the
+/// spherical form of the projection on a sphere of the ellipsoid's mean radius with
+/// planetocentric latitudes. Longitude zero points toward the bottom of the image for the
+/// north pole and toward the top for the south pole, the usual convention for polar maps.
+/// Scale is in kilometers per pixel at the pole.
+pub struct PolarProjection {
+    image_width: usize,
+    image_height: usize,
+    pole: Pole,
+    mapping: AzimuthalMapping,
+    bounding_lat: f64,
+    scale: f64,
+    radius: f64,
+}
+
+impl PolarProjection {
+    /// `bounding_lat` is the magnitude of the most equatorward latitude shown. If `scale`
+    /// is None, the bounding latitude circle is fit to the image.
+    pub fn new(
+        image_width: usize,
+        image_height: usize,
+        pole: Pole,
+        mapping: AzimuthalMapping,
+        bounding_lat: f64,
+        scale: Option<f64>,
+        ellipsoid: &Ellipsoid,
+    ) -> PolarProjection {
+        let radius = (ellipsoid.a * ellipsoid.b * ellipsoid.c).cbrt();
+        let bounding_lat = bounding_lat.abs();
+        let scale = scale.unwrap_or_else(|| {
+            let rho = mapping.colatitude_to_rho((90.0 - bounding_lat).to_radians(), radius);
+            rho * 2.0 / image_width.min(image_height) as f64
+        });
+
+        PolarProjection {
+            image_width,
+            image_height,
+            pole,
+            mapping,
+            bounding_lat,
+            scale,
+            radius,
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+impl MapProjection for PolarProjection {
+    fn point_to_lat_lon(&self, x: f64, y: f64) -> Option<LatLon> {
+        if x < 0.0 || y < 0.0 || x >= self.image_width as f64 || y >= self.image_height as f64 {
+            return None;
+        }
+
+        let u = (x - self.image_width as f64 / 2.0) * self.scale;
+        let v = (self.image_height as f64 / 2.0 - y) * self.scale;
+
+        let colat = self
+            .mapping
+            .rho_to_colatitude((u * u + v * v).sqrt(), self.radius)?
+            .to_degrees();
+        if 90.0 - colat < self.bounding_lat {
+            return None;
+        }
+
+        let (lat, lon) = match self.pole {
+            Pole::North => (90.0 - colat, u.atan2(-v)),
+            Pole::South => (colat - 90.0, u.atan2(v)),
+        };
+
+        Some(LatLon {
+            lat,
+            lon: lon.to_degrees().rem_euclid(360.0),
+        })
+    }
+
+    fn lat_lon_to_point(&self, ll: &LatLon) -> Option<(f64, f64)> {
+        let (lat, v_sign) = match self.pole {
+            Pole::North => (ll.lat, -1.0),
+            Pole::South => (-ll.lat, 1.0),
+        };
+        if lat < self.bounding_lat {
+            return None;
+        }
+
+        let rho = self
+            .mapping
+            .colatitude_to_rho((90.0 - lat).to_radians(), self.radius);
+        let lon = ll.lon.to_radians();
+        let u = rho * lon.sin();
+        let v = v_sign * rho * lon.cos();
+
+        let x = u / self.scale + self.image_width as f64 / 2.0;
+        let y = self.image_height as f64 / 2.0 - v / self.scale;
+
+        if x < 0.0 || y < 0.0 || x > self.image_width as f64 || y > self.image_height as f64 {
+            None
+        } else {
+            Some((x, y))
+        }
+    }
+
+    // Only stereographic maps have a standard reference system
+    fn georeference(&self) -> Option<Georeference> {
+        if self.mapping != AzimuthalMapping::Stereographic {
+            return None;
+        }
+
+        let crs = match self.pole {
+            Pole::North => JupiterCrs::NorthPolarStereographic,
+            Pole::South => JupiterCrs::SouthPolarStereographic,
+        };
+
+        // Scale from our sphere, in kilometers, to the reference sphere
+        let pixel_size =
+            self.scale * 1000.0 * JUPITER_IAU2015_SPHERE_RADIUS / (self.radius * 1000.0);
+        Some(Georeference {
+            crs,
+            origin_x: -(self.image_width as f64) / 2.0 * pixel_size,
+            origin_y: self.image_height as f64 / 2.0 * pixel_size,
+            pixel_width: pixel_size,
+            pixel_height: -pixel_size,
+        })
+    }
+}
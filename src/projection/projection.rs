@@ -0,0 +1,81 @@
+use crate::ellipsoid::LatLon;
+use crate::projection::georeference::Georeference;
+
+use serde::Deserialize;
+
+/// Latitude/longitude box covered by a cylindrical map, in degrees. The longitudes are
+/// those at the left and right image edges, so west-positive maps have left > right.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+pub struct LatLonBounds {
+    pub top_lat: f64,
+    pub bottom_lat: f64,
+    pub left_lon: f64,
+    pub right_lon: f64,
+}
+
+impl LatLonBounds {
+    pub fn new(top_lat: f64, bottom_lat: f64, left_lon: f64, right_lon: f64) -> LatLonBounds {
+        LatLonBounds {
+            top_lat,
+            bottom_lat,
+            left_lon,
+            right_lon,
+        }
+    }
+
+    pub fn global() -> LatLonBounds {
+        LatLonBounds::new(90.0, -90.0, 0.0, 360.0)
+    }
+
+    /// Output image dimensions needed to cover the box at the given resolution
+    pub fn image_size(&self, degrees_per_pixel: f64, west_positive: bool) -> (usize, usize) {
+        (
+            (lon_span(self.left_lon, self.right_lon, west_positive) / degrees_per_pixel).round()
+                as usize,
+            ((self.top_lat - self.bottom_lat).abs() / degrees_per_pixel).round() as usize,
+        )
+    }
+}
+
+/// Degrees of longitude from the left edge of a map to its right edge. East-positive maps
+/// run east and west-positive maps run west, wrapping through 0/360 when the edges are the
+/// other way around: an east-positive map from 350 to 10 spans 20 degrees.
+pub fn lon_span(left_lon: f64, right_lon: f64, west_positive: bool) -> f64 {
+    let span = if west_positive {
+        left_lon - right_lon
+    } else {
+        right_lon - left_lon
+    };
+    if span.abs() >= 360.0 {
+        360.0
+    } else {
+        span.rem_euclid(360.0)
+    }
+}
+
+/// Right edge longitude continuing on from the left edge without a jump at 0/360, so that
+/// longitudes interpolate linearly across the map
+pub fn unwrap_right_lon(left_lon: f64, right_lon: f64, west_positive: bool) -> f64 {
+    let span = lon_span(left_lon, right_lon, west_positive);
+    if west_positive {
+        left_lon - span
+    } else {
+        left_lon + span
+    }
+}
+
+/// Maps between output image locations and locations on the surface of the target body.
+pub trait MapProjection: Sync + Send {
+    /// Planetocentric latitude/longitude under an output image location, or None if the
+    /// location is off the map.
+    fn point_to_lat_lon(&self, x: f64, y: f64) -> Option<LatLon>;
+
+    /// Output image location of a planetocentric latitude/longitude, or None if it does not
+    /// fall on the map.
+    fn lat_lon_to_point(&self, ll: &LatLon) -> Option<(f64, f64)>;
+
+    /// Placement of the map in a standard coordinate reference system, if it has one
+    fn georeference(&self) -> Option<Georeference> {
+        None
+    }
+}
@@ -0,0 +1,79 @@
+use crate::enums;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::f64::consts::PI;
+
+/// Kilometers per astronomical unit
+pub const AU_KM: f64 = 149_597_870.7;
+
+/// Per-band constants used to convert calibrated DN to I/F. Responsivity is in DN per
+/// second per unit spectral radiance (W m^-2 sr^-1 nm^-1). Solar irradiance is the solar
+/// spectral irradiance at 1 AU averaged over the band (W m^-2 nm^-1).
+#[derive(Deserialize, Clone)]
+pub struct Radiometry {
+    pub red_responsivity: f64,
+    pub green_responsivity: f64,
+    pub blue_responsivity: f64,
+    #[serde(default)]
+    pub methane_responsivity: Option<f64>,
+
+    pub red_solar_irradiance: f64,
+    pub green_solar_irradiance: f64,
+    pub blue_solar_irradiance: f64,
+    #[serde(default)]
+    pub methane_solar_irradiance: Option<f64>,
+}
+
+impl Radiometry {
+    /// Responsivity and solar irradiance for a band
+    pub fn band(&self, camera: enums::Camera) -> Result<(f64, f64)> {
+        match camera {
+            enums::Camera::RED => Ok((self.red_responsivity, self.red_solar_irradiance)),
+            enums::Camera::GREEN => Ok((self.green_responsivity, self.green_solar_irradiance)),
+            enums::Camera::BLUE => Ok((self.blue_responsivity, self.blue_solar_irradiance)),
+            enums::Camera::METHANE => {
+                match (self.methane_responsivity, self.methane_solar_irradiance) {
+                    (Some(r), Some(f)) => Ok((r, f)),
+                    _ => Err(anyhow!("No radiometric constants configured for methane")),
+                }
+            }
+            enums::Camera::NONE => Err(anyhow!("Cannot radiometrically calibrate unknown band")),
+        }
+    }
+
+    /// Factor converting calibrated DN in `camera`'s band to I/F, given the exposure in
+    /// milliseconds and the distance to the Sun in kilometers.
+    pub fn i_over_f_scale(
+        &self,
+        camera: enums::Camera,
+        exposure_ms: f64,
+        solar_distance_km: f64,
+    ) -> Result<f64> {
+        let (responsivity, solar_irradiance) = self.band(camera)?;
+        if exposure_ms <= 0.0 {
+            return Err(anyhow!("Invalid exposure duration: {} ms", exposure_ms));
+        }
+        if responsivity <= 0.0 || solar_irradiance <= 0.0 {
+            return Err(anyhow!(
+                "Invalid radiometric constants for {:?}: responsivity {}, solar irradiance {}",
+                camera,
+                responsivity,
+                solar_irradiance
+            ));
+        }
+        Ok(dn_to_radiance(1.0, exposure_ms / 1000.0, responsivity)
+            * radiance_to_i_over_f(1.0, solar_irradiance, solar_distance_km / AU_KM))
+    }
+}
+
+/// Spectral radiance from DN given the exposure in seconds
+pub fn dn_to_radiance(dn: f64, exposure_seconds: f64, responsivity: f64) -> f64 {
+    dn / (exposure_seconds * responsivity)
+}
+
+/// Reflectance relative to a perfectly diffusing surface illuminated at normal incidence,
+/// given the solar irradiance at 1 AU and the body's distance to the Sun in AU
+pub fn radiance_to_i_over_f(radiance: f64, solar_irradiance: f64, solar_distance_au: f64) -> f64 {
+    PI * radiance * solar_distance_au * solar_distance_au / solar_irradiance
+}
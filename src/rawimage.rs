@@ -1,10 +1,16 @@
-use crate::{constants, decompanding as ilttables, enums, triplet};
+use crate::{
+    bundle, constants, decompanding as ilttables, enums, fits, framelet, output, pds,
+    radiometry::Radiometry, strip,
+};
+
+use crate::error::{JunocamError, JunocamResult};
 
 use sciimg::prelude::*;
 use sciimg::*;
 
 use anyhow::anyhow;
 use anyhow::Result;
+use std::fmt;
 extern crate image;
 use image::open;
 
@@ -15,55 +21,129 @@ trait FromFile8Bit {
 impl FromFile8Bit for ImageBuffer {
     fn from_file_8bit(file_path: &str) -> Result<ImageBuffer> {
         if !path::file_exists(file_path) {
-            panic!("File not found: {}", file_path);
+            return Err(JunocamError::Io(format!("File not found: {}", file_path)).into());
         }
 
-        let image_data = open(file_path).unwrap().into_luma8();
-        let dims = image_data.dimensions();
+        let image =
+            open(file_path).map_err(|why| JunocamError::Io(format!("{}: {}", file_path, why)))?;
+        buffer_from_luma8(image)
+    }
+}
+
+// Single channel 8-bit DN values, as in the missionjuno raw PNGs
+fn buffer_from_luma8(image: image::DynamicImage) -> Result<ImageBuffer> {
+    let image_data = image.into_luma8();
+    let dims = image_data.dimensions();
 
-        let width = dims.0 as usize;
-        let height = dims.1 as usize;
+    let width = dims.0 as usize;
+    let height = dims.1 as usize;
 
-        let mut v = DnVec::zeros(width * height);
+    let mut v = DnVec::zeros(width * height);
 
-        for y in 0..height {
-            for x in 0..width {
-                let pixel = image_data.get_pixel(x as u32, y as u32);
-                let value = pixel[0] as f32;
-                let idx = y * width + x;
-                v[idx] = value;
-            }
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = image_data.get_pixel(x as u32, y as u32);
+            let value = pixel[0] as f32;
+            let idx = y * width + x;
+            v[idx] = value;
+        }
+    }
+
+    ImageBuffer::from_vec(&v, width, height)
+}
+
+fn decompand_rawdata(rawdata: &mut ImageBuffer, ilttype: enums::SampleBitMode) -> Result<()> {
+    let ilttable = match ilttables::table_from_enum(ilttype) {
+        Ok(t) => t,
+        Err(_) => {
+            return Err(anyhow!("Unknown/unsupported ILT, cannot decompand"));
         }
+    };
+    decompanding::decompand_buffer(rawdata, &ilttable);
+    Ok(())
+}
+
+/// Counts of the gaps found in an image's framelets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapReport {
+    pub strips: usize,
+    pub strips_with_gaps: usize,
+    /// Lines lost across all strips, including padding past the end of the image
+    pub gap_lines: usize,
+    pub sets: usize,
+    /// Framelet sets with a gap in any strip, including a last set cut short
+    pub incomplete_sets: usize,
+}
+
+impl GapReport {
+    pub fn has_gaps(&self) -> bool {
+        self.gap_lines > 0
+    }
+}
 
-        ImageBuffer::from_vec(&v, width, height)
+impl fmt::Display for GapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gap lines in {} of {} strips, {} of {} framelet sets incomplete",
+            self.gap_lines, self.strips_with_gaps, self.strips, self.incomplete_sets, self.sets
+        )
     }
 }
 
 pub struct RawImage {
     pub rawdata: ImageBuffer,
-    pub triplets: Vec<triplet::Triplet>,
+    pub framelet_sets: Vec<framelet::FrameletSet>,
+
+    /// Pixels summed along each axis on the spacecraft. Summed images are 1648 divided by
+    /// this wide, with strips 128 divided by this high.
+    pub sampling_factor: usize,
+}
+
+// Summing factor implied by the width of an image
+fn sampling_factor_for_width(width: usize) -> JunocamResult<usize> {
+    let sampling_factor = constants::STRIP_WIDTH.checked_div(width).unwrap_or(0);
+    if sampling_factor == 0
+        || sampling_factor * width != constants::STRIP_WIDTH
+        || constants::STRIP_HEIGHT.checked_rem(sampling_factor) != Some(0)
+    {
+        return Err(JunocamError::Geometry(format!(
+            "Image width {} is not the framelet width {} or a summed fraction of it",
+            width,
+            constants::STRIP_WIDTH
+        )));
+    }
+    Ok(sampling_factor)
 }
 
 impl RawImage {
+    // Splits into color triplets, summed by whatever factor the width implies.
+    // `set_layout()` re-splits for other filter sets.
+    fn from_rawdata(rawdata: ImageBuffer) -> JunocamResult<RawImage> {
+        let sampling_factor = sampling_factor_for_width(rawdata.width)?;
+        let mut rawimage = RawImage {
+            rawdata,
+            framelet_sets: Vec::new(),
+            sampling_factor,
+        };
+        rawimage.split_framelet_sets(&framelet::RGB_CAMERAS)?;
+        Ok(rawimage)
+    }
+
     pub fn new_from_image(raw_image_path: &str) -> Result<RawImage> {
         if !path::file_exists(raw_image_path) {
             return Err(anyhow!(constants::status::FILE_NOT_FOUND));
         }
 
-        let mut rawimage = RawImage {
-            rawdata: match ImageBuffer::from_file_8bit(raw_image_path) {
-                Ok(b) => b,
-                Err(e) => {
-                    return Err(e);
-                }
-            },
-            triplets: Vec::new(),
+        let rawdata = match ImageBuffer::from_file_8bit(raw_image_path) {
+            Ok(b) => b,
+            Err(e) => {
+                return Err(e);
+            }
         };
         //rawimage.rawdata.normalize_mut(0.0, 65535.0);
 
-        rawimage.split_triplets();
-
-        Ok(rawimage)
+        Ok(RawImage::from_rawdata(rawdata)?)
     }
 
     pub fn new_from_image_with_decompand(
@@ -74,14 +154,11 @@ impl RawImage {
             return Err(anyhow!(constants::status::FILE_NOT_FOUND));
         }
 
-        let mut rawimage = RawImage {
-            rawdata: match ImageBuffer::from_file_8bit(raw_image_path) {
-                Ok(b) => b,
-                Err(e) => {
-                    return Err(e);
-                }
-            },
-            triplets: Vec::new(),
+        let mut rawdata = match ImageBuffer::from_file_8bit(raw_image_path) {
+            Ok(b) => b,
+            Err(e) => {
+                return Err(e);
+            }
         };
 
         let ilttable = match ilttype {
@@ -93,58 +170,313 @@ impl RawImage {
                 return Err(anyhow!("Unknown/unsupported ILT, cannot decompand"));
             }
         };
-        decompanding::decompand_buffer(&mut rawimage.rawdata, &ilttable);
+        decompanding::decompand_buffer(&mut rawdata, &ilttable);
 
         //rawimage.rawdata.normalize_mut(0.0, 65535.0);
-        rawimage.split_triplets();
+        Ok(RawImage::from_rawdata(rawdata)?)
+    }
+
+    /// Loads a PDS3 archive image. `file_path` may be the `.IMG` or its detached `.LBL`.
+    pub fn new_from_pds(file_path: &str) -> Result<RawImage> {
+        let label = pds::Label::open(file_path)?;
+
+        Ok(RawImage::from_rawdata(label.read_image()?)?)
+    }
+
+    /// Loads a PDS3 archive image, decompanding it if it holds 8-bit companded samples.
+    /// Wider samples are already linear.
+    pub fn new_from_pds_with_decompand(
+        file_path: &str,
+        ilttype: enums::SampleBitMode,
+    ) -> Result<RawImage> {
+        let label = pds::Label::open(file_path)?;
+        let mut rawdata = label.read_image()?;
+
+        if label.sample_bits() == Some(8) {
+            decompand_rawdata(&mut rawdata, ilttype)?;
+        }
+
+        Ok(RawImage::from_rawdata(rawdata)?)
+    }
+
+    /// Loads the `-raw.png` from a missionjuno `-ImageSet.zip` without extracting it
+    pub fn new_from_zip(zip_path: &str) -> Result<RawImage> {
+        let (_, bytes) = bundle::read_entry(zip_path, bundle::RAW_IMAGE_SUFFIX)?;
+
+        Ok(RawImage::from_rawdata(buffer_from_luma8(
+            image::load_from_memory(&bytes)?,
+        )?)?)
+    }
+
+    pub fn new_from_zip_with_decompand(
+        zip_path: &str,
+        ilttype: enums::SampleBitMode,
+    ) -> Result<RawImage> {
+        let (_, bytes) = bundle::read_entry(zip_path, bundle::RAW_IMAGE_SUFFIX)?;
+
+        let mut rawdata = buffer_from_luma8(image::load_from_memory(&bytes)?)?;
+        decompand_rawdata(&mut rawdata, ilttype)?;
+
+        Ok(RawImage::from_rawdata(rawdata)?)
+    }
+
+    /// Loads framelets previously written with `save_fits()`. The data is taken to be
+    /// decompanded, with the dark/flat and radiometric state read from the header.
+    pub fn new_from_fits(fits_image: &fits::FitsImage) -> Result<RawImage> {
+        let rawdata = fits_image
+            .bands
+            .first()
+            .ok_or_else(|| anyhow!("FITS image has no data"))?
+            .clone();
+        let darknoise = fits_image
+            .header
+            .get("CALIBRTD")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let radiometric = fits_image
+            .header
+            .get("BUNIT")
+            .and_then(|v| v.as_str())
+            .map(|u| u == "I/F")
+            .unwrap_or(false);
+
+        let mut rawimage = RawImage::from_rawdata(rawdata)?;
+        rawimage.framelet_sets.iter_mut().for_each(|t| {
+            t.channels
+                .iter_mut()
+                .for_each(|s| s.restore_calibration_state(darknoise, radiometric));
+        });
 
         Ok(rawimage)
     }
 
-    pub fn assemble(&self) -> ImageBuffer {
-        let mut assembled_buffer =
-            ImageBuffer::new_with_fill(self.rawdata.width, self.rawdata.height, 0.0).unwrap();
+    /// Writes the assembled framelets as an unstretched float FITS image, adding their
+    /// calibration state to `header`.
+    pub fn save_fits(&self, output_file: &str, header: &fits::Header) -> Result<()> {
+        let mut header = header.clone();
+        strip::set_calibration_keywords(
+            &mut header,
+            self.is_darknoise_applied(),
+            self.is_radiometric_applied(),
+        );
+        output::save_fits(
+            &[&self.assemble()?],
+            output_file,
+            output::Stretch::None,
+            Some(&header),
+        )
+    }
+
+    pub fn is_darknoise_applied(&self) -> bool {
+        !self.framelet_sets.is_empty()
+            && self
+                .framelet_sets
+                .iter()
+                .flat_map(|t| t.channels.iter())
+                .all(|s| s.is_darknoise_applied())
+    }
+
+    pub fn is_radiometric_applied(&self) -> bool {
+        !self.framelet_sets.is_empty()
+            && self
+                .framelet_sets
+                .iter()
+                .flat_map(|t| t.channels.iter())
+                .all(|s| s.is_radiometric_applied())
+    }
+
+    pub fn assemble(&self) -> JunocamResult<ImageBuffer> {
+        // A partial last framelet set is padded out past the end of the image
+        let sets_height: usize = self.framelet_sets.iter().map(|s| s.buffer.height).sum();
+        let mut assembled_buffer = ImageBuffer::new_with_fill(
+            self.rawdata.width,
+            sets_height.max(self.rawdata.height),
+            0.0,
+        )
+        .map_err(|why| {
+            JunocamError::Geometry(format!("Unable to allocate assembled image: {}", why))
+        })?;
         assembled_buffer.mode = ImageMode::U16BIT;
 
         let mut y: usize = 0;
-        for triplet in self.triplets.iter() {
-            triplet
-                .paste_into(&mut assembled_buffer, y)
-                .expect("Failed to paste into assembled buffer");
-            y += constants::STRIP_HEIGHT * 3;
+        for set in self.framelet_sets.iter() {
+            set.paste_into(&mut assembled_buffer, y).map_err(|why| {
+                JunocamError::Geometry(format!("Unable to assemble image: {}", why))
+            })?;
+            y += set.buffer.height;
         }
 
-        assembled_buffer
+        if assembled_buffer.height > self.rawdata.height {
+            assembled_buffer =
+                assembled_buffer
+                    .get_slice(0, self.rawdata.height)
+                    .map_err(|why| {
+                        JunocamError::Geometry(format!("Unable to trim assembled image: {}", why))
+                    })?;
+            assembled_buffer.mode = ImageMode::U16BIT;
+        }
+        Ok(assembled_buffer)
     }
 
-    fn split_triplets(&mut self) {
-        if !self.triplets.is_empty() {
-            panic!("Triplets already split out");
+    fn split_framelet_sets(&mut self, cameras: &[enums::Camera]) -> JunocamResult<()> {
+        if !self.framelet_sets.is_empty() {
+            return Err(JunocamError::Geometry(
+                "Framelet sets already split out".into(),
+            ));
+        }
+        if cameras.is_empty() {
+            return Err(JunocamError::Geometry(
+                "No filters to split framelets for".into(),
+            ));
+        }
+        let set_height = self.strip_height() * cameras.len();
+        let set_count = self.rawdata.height / set_height;
+
+        for i in 0..set_count {
+            let set = self
+                .rawdata
+                .get_slice(i * set_height, set_height)
+                .and_then(|d| framelet::FrameletSet::new_from_imagebuffer_with_cameras(&d, cameras))
+                .map_err(|why| JunocamError::Geometry(format!("Framelet set {}: {}", i, why)))?;
+            self.framelet_sets.push(set);
         }
-        let triplet_count = self.rawdata.height / (constants::STRIP_HEIGHT * 3);
 
-        for i in 0..triplet_count {
-            let triplet_data = self
+        // Keep what there is of a last set cut short, padding it out to full height
+        let leftover = self.rawdata.height % set_height;
+        if leftover > 0 {
+            let set = self
                 .rawdata
-                .get_slice(
-                    i * (constants::STRIP_HEIGHT * 3),
-                    constants::STRIP_HEIGHT * 3,
-                )
-                .unwrap();
-            let triplet = triplet::Triplet::new_from_imagebuffer(&triplet_data).unwrap();
-            self.triplets.push(triplet);
+                .get_slice(set_count * set_height, leftover)
+                .and_then(|d| {
+                    let mut padded = ImageBuffer::new_as_mode(
+                        self.rawdata.width,
+                        set_height,
+                        self.rawdata.mode,
+                    )?;
+                    padded.paste_mut(&d, 0, 0);
+                    framelet::FrameletSet::new_partial(&padded, cameras, leftover)
+                })
+                .map_err(|why| {
+                    JunocamError::Geometry(format!("Partial framelet set {}: {}", set_count, why))
+                })?;
+            self.framelet_sets.push(set);
+        }
+
+        Ok(())
+    }
+
+    /// Marks lines lost in downlink, which arrive as lines of zeros, as gaps to be left out
+    /// of projection. Must be run before any corrections, and again after `set_cameras()`
+    /// or `set_layout()`, which re-split the framelets.
+    pub fn detect_gaps(&mut self) -> GapReport {
+        self.framelet_sets.iter_mut().for_each(|s| {
+            s.detect_gaps();
+        });
+        self.gap_report()
+    }
+
+    /// Counts of the gaps marked so far
+    pub fn gap_report(&self) -> GapReport {
+        let strips: Vec<&strip::Strip> = self
+            .framelet_sets
+            .iter()
+            .flat_map(|s| s.channels.iter())
+            .collect();
+        GapReport {
+            strips: strips.len(),
+            strips_with_gaps: strips.iter().filter(|s| s.gap_line_count() > 0).count(),
+            gap_lines: strips.iter().map(|s| s.gap_line_count()).sum(),
+            incomplete_sets: self
+                .framelet_sets
+                .iter()
+                .filter(|s| s.is_incomplete())
+                .count(),
+            sets: self.framelet_sets.len(),
         }
     }
 
-    pub fn get_triplet_count(&self) -> u8 {
-        self.triplets.len() as u8
+    /// Re-splits the framelets for the filters the image was taken through, in the order
+    /// listed by `Filters::cameras()`. Images are loaded as blue, green, red triplets, so
+    /// this is needed for any other filter set. Must be called before any corrections are
+    /// applied, other than the calibration carried by a FITS input.
+    pub fn set_cameras(&mut self, cameras: &[enums::Camera]) -> JunocamResult<()> {
+        self.set_layout(cameras, self.sampling_factor)
+    }
+
+    /// As with `set_cameras()`, also checking the image against the `SAMPLING_FACTOR`
+    /// given in its metadata
+    pub fn set_layout(
+        &mut self,
+        cameras: &[enums::Camera],
+        sampling_factor: usize,
+    ) -> JunocamResult<()> {
+        if sampling_factor != self.sampling_factor {
+            return Err(JunocamError::Geometry(format!(
+                "Image width {} does not match sampling factor {}",
+                self.rawdata.width, sampling_factor
+            )));
+        }
+        if self.cameras() == cameras {
+            return Ok(());
+        }
+
+        let darknoise = self.is_darknoise_applied();
+        let radiometric = self.is_radiometric_applied();
+
+        self.framelet_sets.clear();
+        self.split_framelet_sets(cameras)?;
+
+        if darknoise || radiometric {
+            self.framelet_sets.iter_mut().for_each(|t| {
+                t.channels
+                    .iter_mut()
+                    .for_each(|s| s.restore_calibration_state(darknoise, radiometric));
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Height of each strip, in the image's summed pixels
+    pub fn strip_height(&self) -> usize {
+        constants::STRIP_HEIGHT / self.sampling_factor
+    }
+
+    /// Cameras of each framelet set's strips, top to bottom
+    pub fn cameras(&self) -> Vec<enums::Camera> {
+        self.framelet_sets
+            .first()
+            .map(|t| t.cameras())
+            .unwrap_or_default()
+    }
+
+    /// Number of framelet sets, which are triplets for color images. Includes a last set
+    /// cut short by the end of the image.
+    pub fn get_framelet_set_count(&self) -> usize {
+        self.framelet_sets.len()
+    }
+
+    /// Number of framelet sets cut short by the end of the image, at most one
+    pub fn get_partial_framelet_set_count(&self) -> usize {
+        self.framelet_sets.iter().filter(|s| s.is_partial()).count()
     }
 
     pub fn apply_darknoise(&mut self) -> Result<&'static str> {
-        for triplet in self.triplets.iter_mut() {
-            triplet
-                .apply_darknoise()
-                .expect("Error adark/flat field correction");
+        for set in self.framelet_sets.iter_mut() {
+            set.apply_darknoise()?;
+        }
+
+        Ok("ok")
+    }
+
+    pub fn apply_radiometric_calibration(
+        &mut self,
+        radiometry: &Radiometry,
+        exposure_ms: f64,
+        solar_distance_km: f64,
+    ) -> Result<&'static str> {
+        for set in self.framelet_sets.iter_mut() {
+            set.apply_radiometric(radiometry, exposure_ms, solar_distance_km)?;
         }
 
         Ok("ok")
@@ -155,28 +487,24 @@ impl RawImage {
         hpc_window_size: i32,
         hpc_threshold: f32,
     ) -> Result<&'static str> {
-        for triplet in self.triplets.iter_mut() {
-            triplet
-                .apply_hot_pixel_correction(hpc_window_size, hpc_threshold)
-                .expect("Error applying hot pixel correction");
+        for set in self.framelet_sets.iter_mut() {
+            set.apply_hot_pixel_correction(hpc_window_size, hpc_threshold)?;
         }
 
         Ok("ok")
     }
 
     pub fn apply_infill_correction(&mut self) -> Result<&'static str> {
-        for triplet in self.triplets.iter_mut() {
-            triplet.infill().expect("Error applying infill correction");
+        for set in self.framelet_sets.iter_mut() {
+            set.infill()?;
         }
 
         Ok("ok")
     }
 
     pub fn appy_decomanding(&mut self, ilttype: enums::SampleBitMode) -> Result<&'static str> {
-        for triplet in self.triplets.iter_mut() {
-            triplet
-                .decompand(ilttype)
-                .expect("Error applying decompanding");
+        for set in self.framelet_sets.iter_mut() {
+            set.decompand(ilttype)?;
         }
 
         Ok("ok")
@@ -188,10 +516,8 @@ impl RawImage {
         green_weight: f32,
         blue_weight: f32,
     ) -> Result<&'static str> {
-        for triplet in self.triplets.iter_mut() {
-            triplet
-                .apply_weights(red_weight, green_weight, blue_weight)
-                .expect("Error applying decompanding");
+        for set in self.framelet_sets.iter_mut() {
+            set.apply_weights(red_weight, green_weight, blue_weight)?;
         }
 
         Ok("ok")
@@ -22,9 +22,27 @@ impl RawSet {
             return Err(anyhow!(constants::status::FILE_NOT_FOUND));
         }
 
-        Ok(RawSet {
-            image: rawimage::RawImage::new_from_image(image_path).unwrap(),
-            metadata: metadata::Metadata::new_from_file(metadata_path).unwrap(),
+        RawSet::split_for_filters(RawSet {
+            image: rawimage::RawImage::new_from_image(image_path)?,
+            metadata: metadata::Metadata::new_from_file(metadata_path)?,
         })
     }
+
+    /// Opens a set straight from the missionjuno `-Data.zip` and `-ImageSet.zip`
+    /// downloads. Both may be the same zip if it holds the image and metadata.
+    pub fn open_zip(metadata_zip: &str, image_zip: &str) -> Result<RawSet> {
+        RawSet::split_for_filters(RawSet {
+            image: rawimage::RawImage::new_from_zip(image_zip)?,
+            metadata: metadata::Metadata::new_from_zip(metadata_zip)?,
+        })
+    }
+
+    // Framelet sets follow the filters and summing given in the metadata
+    fn split_for_filters(mut set: RawSet) -> Result<RawSet> {
+        set.image.set_layout(
+            &set.metadata.filters.cameras(),
+            set.metadata.sampling_factor as usize,
+        )?;
+        Ok(set)
+    }
 }
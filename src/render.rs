@@ -0,0 +1,747 @@
+use crate::{
+    backplane::SurfaceGeometry, coverage::Coverage, ellipsoid::Ellipsoid, enums::Camera,
+    interpolate::Interpolation, jcspice, junocam as jc, junocam::FrameletParameters,
+    lens::lens::Lens, photometry::PhotometricModel, projection::projection::MapProjection,
+    rawimage::RawImage, strip::Strip, vprintln,
+};
+
+use itertools::iproduct;
+use rayon::prelude::*;
+use sciimg::drawable::{Drawable, Point};
+use sciimg::{matrix::Matrix, prelude::*, quaternion::Quaternion, vector::Vector};
+
+// Usable area of a framelet. Samples outside of this are either unexposed or too close to the
+// edge to be trusted.
+const FRAMELET_LEFT: f64 = 22.0;
+const FRAMELET_RIGHT: f64 = 1647.0;
+const FRAMELET_TOP: f64 = 2.0;
+const FRAMELET_BOTTOM: f64 = 125.0;
+
+/// Spacecraft position and camera orientation relative to Jupiter's body-fixed frame
+pub struct BodyGeometry {
+    pub body_to_camera: Matrix,
+    pub j2000_to_body: Matrix,
+    pub spacecraft_position: Vector,
+    pub sun_position: Vector,
+}
+
+/// Spacecraft pointing for a single framelet set, sampled at that set's image time.
+pub struct FrameletSetGeometry {
+    pub image_time_et: f64,
+    pub camera_to_j2000: Matrix,
+    pub j2000_to_camera: Matrix,
+    pub body: Option<BodyGeometry>,
+}
+
+impl FrameletSetGeometry {
+    pub fn at(image_time_et: f64) -> FrameletSetGeometry {
+        FrameletSetGeometry {
+            image_time_et,
+            camera_to_j2000: jcspice::pos_transform_matrix("JUNO_JUNOCAM", "J2000", image_time_et),
+            j2000_to_camera: jcspice::pos_transform_matrix("J2000", "JUNO_JUNOCAM", image_time_et),
+            body: None,
+        }
+    }
+
+    /// As with `at()`, but also locates the spacecraft relative to Jupiter. Requires a
+    /// spacecraft SPK covering the image time.
+    pub fn at_with_body(image_time_et: f64) -> FrameletSetGeometry {
+        let mut geom = FrameletSetGeometry::at(image_time_et);
+        geom.body = Some(BodyGeometry {
+            body_to_camera: jcspice::pos_transform_matrix(
+                "IAU_JUPITER",
+                "JUNO_JUNOCAM",
+                image_time_et,
+            ),
+            j2000_to_body: jcspice::pos_transform_matrix("J2000", "IAU_JUPITER", image_time_et),
+            spacecraft_position: jcspice::observer_position_body_fixed(
+                "JUNO",
+                "JUPITER",
+                "IAU_JUPITER",
+                image_time_et,
+            ),
+            // The Sun as seen from Jupiter at the image time. Evaluating the body-fixed
+            // frame a Sun-Jupiter light time earlier would put it tens of degrees off.
+            sun_position: jcspice::observer_position_body_fixed(
+                "SUN",
+                "JUPITER",
+                "IAU_JUPITER",
+                image_time_et,
+            ),
+        });
+        geom
+    }
+}
+
+fn framelet_for_camera(camera: Camera) -> Option<&'static FrameletParameters> {
+    match camera {
+        Camera::RED => Some(&jc::JUNO_JUNOCAM_RED),
+        Camera::GREEN => Some(&jc::JUNO_JUNOCAM_GREEN),
+        Camera::BLUE => Some(&jc::JUNO_JUNOCAM_BLUE),
+        Camera::METHANE => Some(&jc::JUNO_JUNOCAM_METHANE),
+        Camera::NONE => None,
+    }
+}
+
+// Band of the photometric parameters for a camera, in red, green, blue order. Methane
+// takes red's.
+fn photometric_band(camera: Camera) -> usize {
+    match camera {
+        Camera::GREEN => 1,
+        Camera::BLUE => 2,
+        _ => 0,
+    }
+}
+
+/// Where one strip of each framelet set is rendered
+#[derive(Clone, Copy)]
+struct StripLayout {
+    framelet: FrameletParameters,
+    sampling_factor: f64,
+    channel: usize,
+    photometric_band: usize,
+}
+
+impl StripLayout {
+    // Strip pixel coordinates of a camera frame look vector, and the same location in full
+    // resolution pixels for checking against the usable framelet area.
+    fn vector_to_xy(&self, v: &Vector) -> ((f64, f64), (f64, f64)) {
+        let (x, y) = self.framelet.vector_to_xy(v);
+        ((x, y), (x * self.sampling_factor, y * self.sampling_factor))
+    }
+}
+
+// Layout of each strip in a framelet set. Images with more than one filter are rendered as
+// red, green, blue, leaving out methane (see `omitted_cameras()`). A single filter renders
+// as one grayscale band.
+fn strip_layout(cameras: &[Camera], sampling_factor: usize) -> Vec<Option<StripLayout>> {
+    let grayscale = output_band_count(cameras) == 1;
+    cameras
+        .iter()
+        .map(|c| {
+            let channel = match (grayscale, c) {
+                (true, _) => 0,
+                (false, Camera::RED) => 0,
+                (false, Camera::GREEN) => 1,
+                (false, Camera::BLUE) => 2,
+                _ => return None,
+            };
+            Some(StripLayout {
+                framelet: framelet_for_camera(*c)?.summed(sampling_factor as f64),
+                sampling_factor: sampling_factor as f64,
+                channel,
+                photometric_band: photometric_band(*c),
+            })
+        })
+        .collect()
+}
+
+/// Number of output bands needed to render an image taken through `cameras`: one for a
+/// single filter, otherwise three. Color filters keep their red, green, blue band, so an
+/// image with two of them leaves a band empty (see `empty_bands()`).
+pub fn output_band_count(cameras: &[Camera]) -> usize {
+    if cameras.len() == 1 {
+        1
+    } else {
+        3
+    }
+}
+
+/// Filters of `cameras` that aren't rendered. Methane is left out of color output.
+pub fn omitted_cameras(cameras: &[Camera]) -> Vec<Camera> {
+    cameras
+        .iter()
+        .zip(strip_layout(cameras, 1).iter())
+        .filter(|(_, l)| l.is_none())
+        .map(|(c, _)| *c)
+        .collect()
+}
+
+/// Output bands that no filter of `cameras` is rendered to, such as blue for an image
+/// taken through only red and green
+pub fn empty_bands(cameras: &[Camera]) -> Vec<usize> {
+    let layout = strip_layout(cameras, 1);
+    (0..output_band_count(cameras))
+        .filter(|b| !layout.iter().flatten().any(|l| l.channel == *b))
+        .collect()
+}
+
+fn is_within_framelet(x: f64, y: f64) -> bool {
+    (FRAMELET_LEFT..=FRAMELET_RIGHT).contains(&x) && (FRAMELET_TOP..=FRAMELET_BOTTOM).contains(&y)
+}
+
+/// How framelet samples are weighted where several of them see the same location
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Blending {
+    /// Every sample counts equally
+    None,
+
+    /// Weighted by the cosine of the emission angle, favoring views from overhead
+    Emission,
+
+    /// Weighted by distance to the framelet edge, feathering the seams between framelets
+    EdgeDistance,
+}
+
+impl Blending {
+    pub fn from(s: &str) -> Option<Blending> {
+        match s.to_lowercase().as_str() {
+            "none" => Some(Blending::None),
+            "emission" => Some(Blending::Emission),
+            "edge" => Some(Blending::EdgeDistance),
+            _ => None,
+        }
+    }
+}
+
+/// Weighted sums of the samples for each output channel at a single output location
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WeightedSample {
+    pub sums: [f32; 3],
+    pub weights: [f32; 3],
+}
+
+impl WeightedSample {
+    pub fn add(&mut self, other: &WeightedSample) {
+        (0..3).for_each(|c| {
+            self.sums[c] += other.sums[c];
+            self.weights[c] += other.weights[c];
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.iter().all(|w| *w <= 0.0)
+    }
+
+    /// Weighted mean for each channel, or None where nothing was sampled
+    pub fn mean(&self) -> [Option<f32>; 3] {
+        let mut values = [None; 3];
+        (0..3).for_each(|c| {
+            if self.weights[c] > 0.0 {
+                values[c] = Some(self.sums[c] / self.weights[c]);
+            }
+        });
+        values
+    }
+}
+
+// Weight ramps from near zero at the edge of the usable framelet area up to one at its
+// center line.
+fn edge_distance_weight(x: f64, y: f64) -> f32 {
+    let d = (x - FRAMELET_LEFT)
+        .min(FRAMELET_RIGHT - x)
+        .min(y - FRAMELET_TOP)
+        .min(FRAMELET_BOTTOM - y);
+    let feather = (FRAMELET_BOTTOM - FRAMELET_TOP) / 2.0;
+    (d / feather).clamp(0.001, 1.0) as f32
+}
+
+// How a single framelet set saw the location being rendered
+struct FrameletSetView {
+    // Look vector in the JUNO_JUNOCAM frame
+    look: Vector,
+    weight: f32,
+
+    // Cosines of the incidence and emission angles, where the location is on the body
+    illumination: Option<(f64, f64)>,
+}
+
+/// Samples every framelet into which `to_camera` maps the location being rendered and
+/// accumulates the weighted, interpolated values for each output channel, divided by the
+/// photometric model where the illumination is known. `to_camera` returns None if that
+/// framelet set could not have seen the location.
+fn sample_framelet_sets<F>(
+    raw_image: &RawImage,
+    layout: &[Option<StripLayout>],
+    geometry: &[FrameletSetGeometry],
+    interpolation: Interpolation,
+    photometry: PhotometricModel,
+    feather_edges: bool,
+    to_camera: F,
+) -> WeightedSample
+where
+    F: Fn(&FrameletSetGeometry) -> Option<FrameletSetView>,
+{
+    let mut sample = WeightedSample::default();
+
+    raw_image
+        .framelet_sets
+        .iter()
+        .zip(geometry.iter())
+        .for_each(|(set, geom)| {
+            let FrameletSetView {
+                look: v,
+                weight,
+                illumination,
+            } = match to_camera(geom) {
+                Some(view) => view,
+                None => return,
+            };
+
+            // Behind the camera
+            if v.z <= 0.0 {
+                return;
+            }
+
+            set.channels
+                .iter()
+                .zip(layout.iter())
+                .for_each(|(strip, l)| {
+                    let l = match l {
+                        Some(l) => l,
+                        None => return,
+                    };
+                    let c = l.channel;
+
+                    let factor = match illumination {
+                        Some((mu0, mu)) => match photometry.factor(l.photometric_band, mu0, mu) {
+                            Some(f) => f as f32,
+                            None => return,
+                        },
+                        None => 1.0,
+                    };
+
+                    let ((x, y), (full_x, full_y)) = l.vector_to_xy(&v);
+                    if is_within_framelet(full_x, full_y) && strip.is_valid_at(y) {
+                        let w = if feather_edges {
+                            weight * edge_distance_weight(full_x, full_y)
+                        } else {
+                            weight
+                        };
+                        sample.sums[c] += interpolation.sample(&strip.buffer, x, y) / factor * w;
+                        sample.weights[c] += w;
+                    }
+                });
+        });
+
+    sample
+}
+
+/// Evaluates `sample` at the center of every pixel of a `width` x `height` image, in
+/// parallel, and returns the results by row.
+fn map_pixels<T, F>(width: usize, height: usize, sample: F) -> Vec<Vec<T>>
+where
+    T: Send,
+    F: Fn(f64, f64) -> T + Sync,
+{
+    (0..height)
+        .into_par_iter()
+        .map(|y| {
+            (0..width)
+                .map(|x| sample(x as f64 + 0.5, y as f64 + 0.5))
+                .collect()
+        })
+        .collect()
+}
+
+/// Walks every pixel of the output image and writes whatever `sample` returns for its
+/// center. Returns the pixels written to in each band.
+fn render_pixels<F>(map: &mut Image, sample: F) -> Coverage
+where
+    F: Fn(f64, f64) -> [Option<f32>; 3] + Sync,
+{
+    let rows = map_pixels(map.width, map.height, sample);
+    let mut coverage = Coverage::new(map.width, map.height, map.num_bands());
+
+    rows.iter().enumerate().for_each(|(y, row)| {
+        row.iter().enumerate().for_each(|(x, values)| {
+            values.iter().enumerate().for_each(|(c, v)| {
+                if let Some(v) = v {
+                    map.put(x, y, *v, c);
+                    coverage.set(x, y, c);
+                }
+            });
+        });
+    });
+    coverage
+}
+
+/// Renders by walking the output image and, for each pixel, inverting the lens to a look
+/// direction and sampling whichever framelets saw it. The photometric model is applied
+/// where the look direction lands on `ellipsoid`, which requires geometry created with
+/// `FrameletSetGeometry::at_with_body()`. Returns the pixels rendered to in each band.
+#[allow(clippy::too_many_arguments)]
+pub fn render_backward(
+    raw_image: &RawImage,
+    geometry: &[FrameletSetGeometry],
+    q: &Quaternion,
+    lens: &dyn Lens,
+    interpolation: Interpolation,
+    photometry: PhotometricModel,
+    ellipsoid: Option<&Ellipsoid>,
+    map: &mut Image,
+) -> Coverage {
+    let q_inv = q.invert();
+    let layout = strip_layout(&raw_image.cameras(), raw_image.sampling_factor);
+
+    render_pixels(map, |x, y| match lens.point_to_vector(x, y) {
+        Some(v) => {
+            // Translate from our coordinates back to spice's.
+            let look = q_inv.rotate_vector(&Vector::new(v.x, v.z, v.y));
+            sample_framelet_sets(
+                raw_image,
+                &layout,
+                geometry,
+                interpolation,
+                photometry,
+                false,
+                |geom| {
+                    let illumination = match (geom.body.as_ref(), ellipsoid) {
+                        (Some(body), Some(ellipsoid)) if !photometry.is_none() => {
+                            sky_illumination(body, ellipsoid, &look)
+                        }
+                        _ => None,
+                    };
+                    Some(FrameletSetView {
+                        look: geom.j2000_to_camera.multiply_vector(&look),
+                        weight: 1.0,
+                        illumination,
+                    })
+                },
+            )
+            .mean()
+        }
+        None => [None; 3],
+    })
+}
+
+// Cosines of the incidence and emission angles where a J2000 look direction meets the body
+fn sky_illumination(
+    body: &BodyGeometry,
+    ellipsoid: &Ellipsoid,
+    look: &Vector,
+) -> Option<(f64, f64)> {
+    let look = body.j2000_to_body.multiply_vector(look);
+    let surface_point = ellipsoid.intersect(&body.spacecraft_position, &look)?;
+    let normal = ellipsoid.surface_normal(&surface_point);
+    Some(illumination(
+        &normal,
+        &surface_point,
+        &look,
+        &body.sun_position,
+    ))
+}
+
+// Cosines of the incidence and emission angles at a surface point with unit normal `normal`,
+// seen along body-fixed look direction `look`
+fn illumination(
+    normal: &Vector,
+    surface_point: &Vector,
+    look: &Vector,
+    sun_position: &Vector,
+) -> (f64, f64) {
+    let to_sun = sun_position.subtract(surface_point);
+    (
+        normal.dot_product(&to_sun) / to_sun.len(),
+        -normal.dot_product(look) / look.len(),
+    )
+}
+
+/// Renders a map of the body surface. Each output pixel is located on the reference
+/// ellipsoid and sampled from the framelets whose camera could see that point. Requires
+/// geometry created with `FrameletSetGeometry::at_with_body()`. Returns the pixels rendered to
+/// in each band.
+pub fn render_surface(
+    raw_image: &RawImage,
+    geometry: &[FrameletSetGeometry],
+    projection: &dyn MapProjection,
+    ellipsoid: &Ellipsoid,
+    interpolation: Interpolation,
+    photometry: PhotometricModel,
+    map: &mut Image,
+) -> Coverage {
+    let layout = strip_layout(&raw_image.cameras(), raw_image.sampling_factor);
+    render_pixels(map, |x, y| {
+        sample_surface(
+            raw_image,
+            &layout,
+            geometry,
+            projection,
+            ellipsoid,
+            interpolation,
+            photometry,
+            Blending::None,
+            x,
+            y,
+        )
+        .mean()
+    })
+}
+
+/// As with `render_surface()`, but returns the weighted sums for each pixel rather than
+/// writing them to an image, so the results of several images can be blended together.
+#[allow(clippy::too_many_arguments)]
+pub fn render_surface_weighted(
+    raw_image: &RawImage,
+    geometry: &[FrameletSetGeometry],
+    projection: &dyn MapProjection,
+    ellipsoid: &Ellipsoid,
+    interpolation: Interpolation,
+    photometry: PhotometricModel,
+    blending: Blending,
+    width: usize,
+    height: usize,
+) -> Vec<Vec<WeightedSample>> {
+    let layout = strip_layout(&raw_image.cameras(), raw_image.sampling_factor);
+    map_pixels(width, height, |x, y| {
+        sample_surface(
+            raw_image,
+            &layout,
+            geometry,
+            projection,
+            ellipsoid,
+            interpolation,
+            photometry,
+            blending,
+            x,
+            y,
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sample_surface(
+    raw_image: &RawImage,
+    layout: &[Option<StripLayout>],
+    geometry: &[FrameletSetGeometry],
+    projection: &dyn MapProjection,
+    ellipsoid: &Ellipsoid,
+    interpolation: Interpolation,
+    photometry: PhotometricModel,
+    blending: Blending,
+    x: f64,
+    y: f64,
+) -> WeightedSample {
+    let ll = match projection.point_to_lat_lon(x, y) {
+        Some(ll) => ll,
+        None => return WeightedSample::default(),
+    };
+
+    let surface_point = ellipsoid.surface_point(&ll);
+    let normal = ellipsoid.surface_normal(&surface_point);
+
+    sample_framelet_sets(
+        raw_image,
+        layout,
+        geometry,
+        interpolation,
+        photometry,
+        blending == Blending::EdgeDistance,
+        |geom| {
+            let body = geom.body.as_ref()?;
+            let look = surface_point.subtract(&body.spacecraft_position);
+
+            // Cosine of the emission angle. Not positive when facing away from the spacecraft.
+            let (mu0, mu) = illumination(&normal, &surface_point, &look, &body.sun_position);
+            if mu <= 0.0 {
+                return None;
+            }
+
+            let weight = if blending == Blending::Emission {
+                mu as f32
+            } else {
+                1.0
+            };
+
+            Some(FrameletSetView {
+                look: body.body_to_camera.multiply_vector(&look),
+                weight,
+                illumination: Some((mu0, mu)),
+            })
+        },
+    )
+}
+
+// True if a camera frame look vector lands within the usable area of any of the
+// framelets of `cameras`
+fn seen_by_framelet_set(v: &Vector, cameras: &[Camera]) -> bool {
+    v.z > 0.0
+        && cameras.iter().any(|c| match framelet_for_camera(*c) {
+            Some(framelet) => {
+                let (x, y) = framelet.vector_to_xy(v);
+                is_within_framelet(x, y)
+            }
+            None => false,
+        })
+}
+
+/// Observation geometry under each pixel of a surface map, taken from the first framelet set
+/// that saw each location through one of `cameras`. Requires geometry created with
+/// `FrameletSetGeometry::at_with_body()`.
+pub fn surface_geometry(
+    geometry: &[FrameletSetGeometry],
+    cameras: &[Camera],
+    projection: &dyn MapProjection,
+    ellipsoid: &Ellipsoid,
+    width: usize,
+    height: usize,
+) -> Vec<Vec<Option<SurfaceGeometry>>> {
+    map_pixels(width, height, |x, y| {
+        let surface_point = ellipsoid.surface_point(&projection.point_to_lat_lon(x, y)?);
+        let normal = ellipsoid.surface_normal(&surface_point);
+
+        geometry.iter().find_map(|geom| {
+            let body = geom.body.as_ref()?;
+            let look = surface_point.subtract(&body.spacecraft_position);
+            if normal.dot_product(&look) >= 0.0
+                || !seen_by_framelet_set(&body.body_to_camera.multiply_vector(&look), cameras)
+            {
+                return None;
+            }
+            Some(SurfaceGeometry::compute(
+                ellipsoid,
+                &surface_point,
+                &body.spacecraft_position,
+                &body.sun_position,
+            ))
+        })
+    })
+}
+
+/// Observation geometry under each pixel of a lens rendered image, for pixels whose look
+/// direction intersects the body. Taken from the first framelet set that saw each direction
+/// through one of `cameras`. Requires geometry created with `FrameletSetGeometry::at_with_body()`.
+pub fn sky_geometry(
+    geometry: &[FrameletSetGeometry],
+    cameras: &[Camera],
+    q: &Quaternion,
+    lens: &dyn Lens,
+    ellipsoid: &Ellipsoid,
+    width: usize,
+    height: usize,
+) -> Vec<Vec<Option<SurfaceGeometry>>> {
+    let q_inv = q.invert();
+
+    map_pixels(width, height, |x, y| {
+        let v = lens.point_to_vector(x, y)?;
+        let look = q_inv.rotate_vector(&Vector::new(v.x, v.z, v.y));
+
+        geometry.iter().find_map(|geom| {
+            let body = geom.body.as_ref()?;
+            if !seen_by_framelet_set(&geom.j2000_to_camera.multiply_vector(&look), cameras) {
+                return None;
+            }
+            let surface_point = ellipsoid.intersect(
+                &body.spacecraft_position,
+                &body.j2000_to_body.multiply_vector(&look),
+            )?;
+            Some(SurfaceGeometry::compute(
+                ellipsoid,
+                &surface_point,
+                &body.spacecraft_position,
+                &body.sun_position,
+            ))
+        })
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn xy_to_map_point(
+    x: usize,
+    y: usize,
+    framelet: &FrameletParameters,
+    spc_mtx: &Matrix,
+    lens: &dyn Lens,
+    strip: &Strip,
+    q: &Quaternion,
+    channel: usize,
+) -> Option<Point> {
+    // Lines lost to gaps aren't painted
+    if !strip.is_line_valid(y) {
+        return None;
+    }
+
+    let mut v = framelet.xy_to_vector(x as f64, y as f64);
+    v = spc_mtx.multiply_vector(&v);
+    v = q.rotate_vector(&v);
+
+    // Translate from spice coordinates to ours.
+    v = Vector::new(v.x, v.z, v.y);
+
+    let mut pt = lens.vector_to_point(&v)?;
+    let tl_v = strip.buffer.get(x, y);
+    pt.color.values[channel] = tl_v as f64;
+    Some(pt)
+}
+
+/// Legacy renderer. Forward projects each framelet pixel through the lens and paints the
+/// resulting quads into the output image. Returns the pixels painted in each band.
+pub fn render_forward(
+    raw_image: &RawImage,
+    geometry: &[FrameletSetGeometry],
+    q: &Quaternion,
+    lens: &dyn Lens,
+    line_sample_increment: usize,
+    map: &mut Image,
+) -> Coverage {
+    let mut coverage = Coverage::new(map.width, map.height, map.num_bands());
+    let layout = strip_layout(&raw_image.cameras(), raw_image.sampling_factor);
+
+    // Usable framelet area, in the image's summed pixels
+    let top = FRAMELET_TOP as usize / raw_image.sampling_factor;
+    let left = FRAMELET_LEFT as usize / raw_image.sampling_factor;
+    let strip_height = raw_image.strip_height();
+    let strip_width = raw_image.rawdata.width;
+
+    raw_image
+        .framelet_sets
+        .iter()
+        .zip(geometry.iter())
+        .enumerate()
+        .for_each(|(t, (set, geom))| {
+            vprintln!("Processing framelet set #{}", (t + 1));
+            let spc_mtx = &geom.camera_to_j2000;
+
+            iproduct!(
+                (top..(strip_height - line_sample_increment - 1)).step_by(line_sample_increment),
+                (left..(strip_width - line_sample_increment)).step_by(line_sample_increment),
+                set.channels.iter().zip(layout.iter())
+            )
+            .for_each(|(y, x, (strip, l))| {
+                let (framelet, c) = match l {
+                    Some(l) => (&l.framelet, l.channel),
+                    None => return,
+                };
+
+                let tl = xy_to_map_point(x, y, framelet, spc_mtx, lens, strip, q, c);
+                let bl = xy_to_map_point(
+                    x,
+                    y + line_sample_increment,
+                    framelet,
+                    spc_mtx,
+                    lens,
+                    strip,
+                    q,
+                    c,
+                );
+                let br = xy_to_map_point(
+                    x + line_sample_increment,
+                    y + line_sample_increment,
+                    framelet,
+                    spc_mtx,
+                    lens,
+                    strip,
+                    q,
+                    c,
+                );
+                let tr = xy_to_map_point(
+                    x + line_sample_increment,
+                    y,
+                    framelet,
+                    spc_mtx,
+                    lens,
+                    strip,
+                    q,
+                    c,
+                );
+
+                // Skip quads that fall even partly outside of the lens coverage or on a gap
+                if let (Some(tl), Some(bl), Some(br), Some(tr)) = (tl, bl, br, tr) {
+                    map.paint_square_with_channel_rule(&tl, &bl, &br, &tr, true, |ch| ch == c);
+                    coverage.set_quad((tl.x, tl.y), (bl.x, bl.y), (br.x, br.y), (tr.x, tr.y), c);
+                }
+            });
+        });
+
+    coverage
+}
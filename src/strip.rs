@@ -1,4 +1,7 @@
-use crate::{calibration, constants, decompanding as ilttables, enums};
+use crate::{
+    calibration, constants, decompanding as ilttables, enums, error::JunocamError, fits, output,
+    radiometry::Radiometry,
+};
 
 use sciimg::{
     decompanding, enums::ImageMode, hotpixel, image::Image, imagebuffer::ImageBuffer, inpaint,
@@ -7,6 +10,10 @@ use sciimg::{
 use anyhow::anyhow;
 use anyhow::Result;
 
+fn darkflat_error<E: std::fmt::Display>(why: E) -> JunocamError {
+    JunocamError::Calibration(format!("Dark/flat calibration failed: {}", why))
+}
+
 pub struct Strip {
     pub buffer: ImageBuffer,
     pub camera: enums::Camera,
@@ -14,6 +21,11 @@ pub struct Strip {
     darknoise_applied: bool,
     infill_applied: bool,
     hpc_applied: bool, // Strip should know which band it is along with timing and pointing
+    radiometric_applied: bool,
+
+    // Lines holding image data, as opposed to gaps left by data lost in downlink or padding
+    // past the end of the image
+    valid_lines: Vec<bool>,
 }
 
 impl Strip {
@@ -25,41 +37,176 @@ impl Strip {
             darknoise_applied: false,
             infill_applied: false,
             hpc_applied: false,
+            radiometric_applied: false,
+            valid_lines: vec![true; buffer.height],
         })
     }
 
+    // Fraction of the samples in line `y` that are nonzero
+    fn nonzero_fraction(&self, y: usize) -> f64 {
+        let nonzero = (0..self.buffer.width)
+            .filter(|x| self.buffer.get(*x, y) != 0.0)
+            .count();
+        nonzero as f64 / self.buffer.width as f64
+    }
+
+    /// Marks runs of zero filled lines left by data lost in downlink as gaps. A run counts
+    /// if it fills the whole strip, or if the lines bounding it carry signal. Dark sky and
+    /// limb lines are bordered by lines that are mostly zero too, so they are kept. Run
+    /// before calibration moves the lines off zero. Decompanding keeps zero at zero, so
+    /// either the raw or decompanded data will do. Returns the number of lines now marked
+    /// as gaps.
+    pub fn detect_gaps(&mut self) -> usize {
+        let height = self.buffer.height;
+        let zero: Vec<bool> = (0..height)
+            .map(|y| self.nonzero_fraction(y) == 0.0)
+            .collect();
+
+        let mut y = 0;
+        while y < height {
+            if !zero[y] {
+                y += 1;
+                continue;
+            }
+            let start = y;
+            while y < height && zero[y] {
+                y += 1;
+            }
+
+            // A run filling the whole strip has no neighbors, and counts
+            let bounded_by_signal = [start.checked_sub(1), Some(y).filter(|y| *y < height)]
+                .iter()
+                .flatten()
+                .all(|n| self.nonzero_fraction(*n) >= constants::GAP_NEIGHBOR_SIGNAL_FRACTION);
+            if bounded_by_signal {
+                self.valid_lines[start..y]
+                    .iter_mut()
+                    .for_each(|v| *v = false);
+            }
+        }
+        self.gap_line_count()
+    }
+
+    // Marks the lines from `y` down as gaps
+    pub(crate) fn mark_gaps_from(&mut self, y: usize) {
+        self.valid_lines.iter_mut().skip(y).for_each(|v| *v = false);
+    }
+
+    /// Number of lines marked as gaps
+    pub fn gap_line_count(&self) -> usize {
+        self.valid_lines.iter().filter(|v| !**v).count()
+    }
+
+    /// True if line `y` holds image data
+    pub fn is_line_valid(&self, y: usize) -> bool {
+        self.valid_lines.get(y).copied().unwrap_or(false)
+    }
+
+    /// True if the lines either side of fractional line `y` hold image data, so samples
+    /// interpolated there don't pick up a gap
+    pub fn is_valid_at(&self, y: f64) -> bool {
+        let last = self.buffer.height.saturating_sub(1) as f64;
+        let y0 = y.floor().clamp(0.0, last) as usize;
+        let y1 = (y.floor() + 1.0).clamp(0.0, last) as usize;
+        self.is_line_valid(y0) && self.is_line_valid(y1)
+    }
+
     pub fn apply_darknoise(&mut self) -> Result<&'static str> {
         if self.darknoise_applied {
             return Err(anyhow!("Dark/Noise calibration already applied"));
         }
 
-        let mut dark = match calibration::load_dark_file(self.camera) {
-            Ok(m) => m,
-            Err(_) => return Err(anyhow!("Error loading dark field")),
-        };
+        let mut dark = calibration::load_dark_file(self.camera)?;
+        dark = calibration::summed(&dark, self.sampling_factor())?;
 
-        let mut flat = match calibration::load_flat_file(self.camera) {
-            Ok(m) => m,
-            Err(_) => return Err(anyhow!("Error loading flat field")),
-        };
+        let mut flat = calibration::load_flat_file(self.camera)?;
+        flat = calibration::summed(&flat, self.sampling_factor())?;
 
-        dark = dark.divide_into(65535.0).unwrap();
-        flat = flat.divide_into(65535.0).unwrap();
+        dark = dark.divide_into(65535.0).map_err(darkflat_error)?;
+        flat = flat.divide_into(65535.0).map_err(darkflat_error)?;
 
-        let darkflat = flat.subtract(&dark).unwrap();
+        let darkflat = flat.subtract(&dark).map_err(darkflat_error)?;
         let mean_flat = darkflat.mean();
-        let frame_minus_dark = self.buffer.subtract(&dark).unwrap();
+        let frame_minus_dark = self.buffer.subtract(&dark).map_err(darkflat_error)?;
         self.buffer = frame_minus_dark
             .scale(mean_flat)
-            .unwrap()
-            .divide(&flat)
-            .unwrap();
+            .and_then(|b| b.divide(&flat))
+            .map_err(darkflat_error)?;
 
         self.darknoise_applied = true;
 
         Ok("ok")
     }
 
+    /// Converts dark/flat calibrated DN to I/F
+    pub fn apply_radiometric(
+        &mut self,
+        radiometry: &Radiometry,
+        exposure_ms: f64,
+        solar_distance_km: f64,
+    ) -> Result<&'static str> {
+        if self.radiometric_applied {
+            return Err(anyhow!("Radiometric calibration already applied"));
+        }
+
+        if !self.darknoise_applied {
+            return Err(anyhow!(
+                "Dark/Noise calibration must be applied before radiometric calibration"
+            ));
+        }
+
+        // Summed pixels collect the light of each of the pixels summed into them
+        let pixels_summed = self.sampling_factor().pow(2) as f64;
+        let scale = radiometry.i_over_f_scale(self.camera, exposure_ms, solar_distance_km)?;
+        self.buffer = self.buffer.scale((scale / pixels_summed) as f32)?;
+
+        self.radiometric_applied = true;
+
+        Ok("ok")
+    }
+
+    // Pixels summed along each axis, going by the strip width
+    fn sampling_factor(&self) -> usize {
+        (constants::STRIP_WIDTH / self.buffer.width.max(1)).max(1)
+    }
+
+    pub fn is_darknoise_applied(&self) -> bool {
+        self.darknoise_applied
+    }
+
+    pub fn is_radiometric_applied(&self) -> bool {
+        self.radiometric_applied
+    }
+
+    // Restores calibration state for data that was calibrated before being saved
+    pub(crate) fn restore_calibration_state(&mut self, darknoise: bool, radiometric: bool) {
+        self.ilt_applied = true;
+        self.darknoise_applied = darknoise;
+        self.radiometric_applied = radiometric;
+    }
+
+    /// Writes the strip as an unstretched float FITS image, adding its band and
+    /// calibration state to `header`.
+    pub fn save_fits(&self, output_file: &str, header: &fits::Header) -> Result<()> {
+        let mut header = header.clone();
+        header.set(
+            "FILTER",
+            fits::HeaderValue::Str(format!("{:?}", self.camera)),
+            None,
+        );
+        set_calibration_keywords(
+            &mut header,
+            self.darknoise_applied,
+            self.radiometric_applied,
+        );
+        output::save_fits(
+            &[&self.buffer],
+            output_file,
+            output::Stretch::None,
+            Some(&header),
+        )
+    }
+
     pub fn paste_into(&self, into: &mut ImageBuffer, y: usize) {
         into.paste_mut(&self.buffer, 0, y);
     }
@@ -69,10 +216,8 @@ impl Strip {
             return Err(anyhow!("Infill correction already applied"));
         }
 
-        let mask = match calibration::load_mask(self.camera) {
-            Ok(m) => m,
-            Err(_) => return Err(anyhow!("Error loading mask")),
-        };
+        let mask = calibration::load_mask(self.camera)?;
+        let mask = calibration::summed_mask(&mask, self.sampling_factor())?;
 
         // Loading our grayscale data into a 3 band RgbImage. Will need to modify the sciimg inpaint method to take in imagebuffer
         let rgb = Image::new_from_buffers_rgb(
@@ -81,7 +226,7 @@ impl Strip {
             &self.buffer,
             ImageMode::U16BIT,
         )
-        .unwrap();
+        .map_err(|why| JunocamError::Calibration(format!("Inpainting failed: {}", why)))?;
 
         let filled = match inpaint::apply_inpaint_to_buffer(&rgb, &mask) {
             Ok(b) => b,
@@ -139,8 +284,26 @@ impl Strip {
     }
 
     pub fn apply_weight(&mut self, weight: f32) -> Result<&'static str> {
-        self.buffer = self.buffer.scale(weight).unwrap();
+        self.buffer = self
+            .buffer
+            .scale(weight)
+            .map_err(|why| JunocamError::Calibration(format!("Weighting failed: {}", why)))?;
 
         Ok(constants::status::OK)
     }
 }
+
+/// Records whether dark/flat and radiometric calibration were applied, so the data can be
+/// picked up again without repeating them.
+pub fn set_calibration_keywords(header: &mut fits::Header, darknoise: bool, radiometric: bool) {
+    header.set(
+        "CALIBRTD",
+        fits::HeaderValue::Bool(darknoise),
+        Some("Dark/flat calibration applied"),
+    );
+    header.set(
+        "BUNIT",
+        fits::HeaderValue::Str(if radiometric { "I/F" } else { "DN" }.to_string()),
+        None,
+    );
+}
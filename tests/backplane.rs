@@ -0,0 +1,119 @@
+use junocam::backplane::{Backplane, Backplanes, SurfaceGeometry};
+use junocam::ellipsoid::{Ellipsoid, LatLon};
+use junocam::fits::FitsImage;
+use junocam::output::{self, OutputFormat};
+use sciimg::vector::Vector;
+use std::fs::File;
+use tiff::decoder::{Decoder, DecodingResult};
+
+const EPSILON: f64 = 1.0e-6;
+
+fn jupiter() -> Ellipsoid {
+    Ellipsoid::new(71492.0, 71492.0, 66854.0)
+}
+
+#[test]
+fn test_subsolar_sub_spacecraft_geometry() {
+    let ellipsoid = jupiter();
+    let point = ellipsoid.surface_point(&LatLon { lat: 0.0, lon: 0.0 });
+
+    // Spacecraft directly overhead, Sun 90 degrees to the east
+    let spacecraft = Vector::new(171492.0, 0.0, 0.0);
+    let sun = Vector::new(0.0, 7.8e8, 0.0);
+
+    let geom = SurfaceGeometry::compute(&ellipsoid, &point, &spacecraft, &sun);
+    assert!(geom.lat.abs() < EPSILON);
+    assert!(geom.lon_west.abs() < EPSILON || (geom.lon_west - 360.0).abs() < EPSILON);
+    assert!(geom.emission.abs() < EPSILON);
+    assert!((geom.range - 100000.0).abs() < EPSILON);
+    assert!((geom.incidence - 90.0).abs() < 0.01);
+    assert!((geom.phase - 90.0).abs() < 0.01);
+
+    // Sun is at east longitude 90, so this point is six hours before noon
+    assert!((geom.local_solar_time - 6.0).abs() < 0.01);
+}
+
+#[test]
+fn test_system_iii_longitude_is_west() {
+    let ellipsoid = jupiter();
+    let point = ellipsoid.surface_point(&LatLon {
+        lat: -30.0,
+        lon: 90.0,
+    });
+    let geom = SurfaceGeometry::compute(
+        &ellipsoid,
+        &point,
+        &point.scale(2.0),
+        &Vector::new(7.8e8, 0.0, 0.0),
+    );
+    assert!((geom.lat + 30.0).abs() < EPSILON);
+    assert!((geom.lon_west - 270.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_backplanes_from_rows_and_save() {
+    let ellipsoid = jupiter();
+    let point = ellipsoid.surface_point(&LatLon {
+        lat: 10.0,
+        lon: 20.0,
+    });
+    let geom = SurfaceGeometry::compute(
+        &ellipsoid,
+        &point,
+        &point.scale(1.5),
+        &Vector::new(7.8e8, 0.0, 0.0),
+    );
+
+    let rows = vec![vec![Some(geom), None], vec![None, Some(geom)]];
+    let backplanes = Backplanes::from_rows(&rows).unwrap();
+
+    let lat = backplanes.get(Backplane::Latitude).unwrap();
+    assert!((lat.get(0, 0) - 10.0).abs() < 1.0e-4);
+    assert!(lat.get(1, 0).is_nan());
+
+    let path = std::env::temp_dir().join("junocam_test_backplane_lat.tif");
+    let path = path.to_str().unwrap();
+    output::save_float_tiff(lat, path).unwrap();
+
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 2));
+    match decoder.read_image().unwrap() {
+        DecodingResult::F32(values) => {
+            assert!((values[3] - 10.0).abs() < 1.0e-4);
+            assert!(values[2].is_nan());
+        }
+        _ => panic!("Expected 32-bit float samples"),
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_backplanes_save_fits() {
+    let rows = vec![vec![
+        None,
+        Some(SurfaceGeometry {
+            lat: 10.0,
+            lon_west: 340.0,
+            incidence: 30.0,
+            emission: 40.0,
+            phase: 50.0,
+            range: 1.0e5,
+            local_solar_time: 12.0,
+        }),
+    ]];
+    let backplanes = Backplanes::from_rows(&rows).unwrap();
+
+    let base = std::env::temp_dir().join("junocam_test_backplanes.fits");
+    let base = base.to_str().unwrap();
+    backplanes.save(base, OutputFormat::Fits).unwrap();
+
+    for backplane in Backplane::all() {
+        let path = output::replace_extension(base, &format!("-{}.fits", backplane.name()));
+        let fits = FitsImage::open(&path).unwrap();
+        assert_eq!(fits.bands.len(), 1);
+        assert!(fits.bands[0].get(0, 0).is_nan());
+        let expected = backplanes.get(backplane).unwrap().get(1, 0);
+        assert!((fits.bands[0].get(1, 0) - expected).abs() < 1.0e-4);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+use junocam::{bundle, metadata::Metadata, rawset::RawSet};
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+
+mod common;
+
+#[test]
+fn test_metadata_from_data_zip() {
+    let md = Metadata::new_from_file(common::constants::TEST_DATA_ZIP).unwrap();
+    assert_eq!(md.pj, "32");
+    assert_eq!(md.lines, 9984);
+
+    assert!(bundle::read_entry(common::constants::TEST_DATA_ZIP, "-raw.png").is_err());
+}
+
+#[test]
+fn test_open_image_set_zip() {
+    let dir = std::env::temp_dir().join("junocam_test_bundle");
+    fs::create_dir_all(&dir).unwrap();
+
+    // One triplet's worth of framelets
+    let raw = image::GrayImage::from_fn(1648, 384, |x, y| image::Luma([((x + y) % 256) as u8]));
+    let mut png = Vec::new();
+    image::DynamicImage::ImageLuma8(raw)
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let image_zip = dir.join("10124-ImageSet.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&image_zip).unwrap());
+    let options = zip::write::FileOptions::default();
+    writer
+        .start_file(
+            "ImageSet/JNCE_2021052_32C00054_V01-mapprojected.png",
+            options,
+        )
+        .unwrap();
+    writer.write_all(b"not this one").unwrap();
+    writer
+        .start_file("ImageSet/JNCE_2021052_32C00054_V01-raw.png", options)
+        .unwrap();
+    writer.write_all(&png).unwrap();
+    writer.finish().unwrap();
+
+    let image_zip = image_zip.to_str().unwrap();
+    assert_eq!(bundle::data_zip_for(image_zip), None);
+    let data_zip = dir.join("10124-Data.zip");
+    fs::copy(common::constants::TEST_DATA_ZIP, &data_zip).unwrap();
+    assert_eq!(
+        bundle::data_zip_for(image_zip),
+        Some(data_zip.to_str().unwrap().to_string())
+    );
+
+    let rs = RawSet::open_zip(data_zip.to_str().unwrap(), image_zip).unwrap();
+    assert_eq!(rs.image.get_framelet_set_count(), 1);
+    assert_eq!(rs.image.rawdata.get(5, 7), 12.0);
+    assert_eq!(rs.metadata.orbit_number, 32);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+use junocam::coverage::Coverage;
+use sciimg::prelude::*;
+
+#[test]
+fn test_coverage_per_band() {
+    let mut coverage = Coverage::new(3, 2, 3);
+    coverage.set(0, 0, 0);
+    coverage.set(0, 0, 2);
+    coverage.set(2, 1, 1);
+
+    assert!(coverage.is_covered(0, 0, 2));
+    assert!(!coverage.is_covered(0, 0, 1));
+    assert!(!coverage.is_covered(0, 0, 3));
+    assert!(coverage.is_any_covered(2, 1));
+    assert!(!coverage.is_any_covered(1, 1));
+    assert_eq!(coverage.covered_pixels(), 2);
+
+    let mask = coverage.to_mask().unwrap();
+    assert_eq!(mask.num_bands(), 3);
+    assert_eq!(mask.get(0, 0, 0), 65535.0);
+    assert_eq!(mask.get(0, 0, 1), 0.0);
+    assert_eq!(mask.get(2, 1, 1), 65535.0);
+}
+
+#[test]
+fn test_coverage_alpha() {
+    let mut coverage = Coverage::new(2, 2, 1);
+    coverage.set(1, 0, 0);
+
+    let mut image = Image::new_with_bands(2, 2, 1, ImageMode::U16BIT).unwrap();
+    coverage.apply_alpha(&mut image);
+    assert!(image.is_using_alpha());
+    assert!(image.get_alpha_at(1, 0));
+    assert!(!image.get_alpha_at(0, 0));
+    assert!(!image.get_alpha_at(1, 1));
+}
+
+#[test]
+fn test_coverage_quad() {
+    // A diamond, so its bounding box corners are left out
+    let mut coverage = Coverage::new(6, 6, 2);
+    coverage.set_quad((2.0, 0.0), (0.0, 2.0), (2.0, 4.0), (4.0, 2.0), 1);
+
+    assert!(coverage.is_covered(2, 2, 1));
+    assert!(coverage.is_covered(2, 0, 1));
+    assert!(coverage.is_covered(4, 2, 1));
+    assert!(!coverage.is_covered(0, 0, 1));
+    assert!(!coverage.is_covered(4, 4, 1));
+    assert!(!coverage.is_covered(2, 2, 0));
+    assert_eq!(coverage.covered_pixels(), 13);
+
+    // Entirely off the image
+    coverage.set_quad((-3.0, 0.0), (-3.0, 2.0), (-1.0, 2.0), (-1.0, 0.0), 0);
+    assert_eq!(coverage.covered_pixels(), 13);
+}
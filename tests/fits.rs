@@ -0,0 +1,80 @@
+use junocam::fits::{self, FitsImage, Header, HeaderValue};
+use junocam::metadata::Metadata;
+use junocam::output::{self, Stretch};
+use sciimg::imagebuffer::ImageBuffer;
+use std::fs;
+
+mod common;
+
+#[test]
+fn test_fits_round_trip() {
+    let mut red = ImageBuffer::new(5, 3).unwrap();
+    let mut blue = ImageBuffer::new(5, 3).unwrap();
+    (0..15).for_each(|i| {
+        red.put(i % 5, i / 5, i as f32 * 1.5);
+        blue.put(i % 5, i / 5, -(i as f32));
+    });
+
+    let mut header = Header::new();
+    header.set("PJ", HeaderValue::Str("32".to_string()), Some("Perijove"));
+    header.set("OBSERVER", HeaderValue::Str("Kevin's".to_string()), None);
+    header.set("EXPTIME", HeaderValue::Float(0.0032), None);
+
+    let path = std::env::temp_dir().join("junocam_test_round_trip.fits");
+    let path = path.to_str().unwrap();
+    output::save_fits(&[&red, &blue], path, Stretch::None, Some(&header)).unwrap();
+
+    let fits_image = FitsImage::open(path).unwrap();
+    assert_eq!(fits_image.bands.len(), 2);
+    assert_eq!(fits_image.bands[0].width, 5);
+    assert_eq!(fits_image.bands[0].height, 3);
+    assert_eq!(fits_image.bands[0].get(3, 2), 19.5);
+    assert_eq!(fits_image.bands[1].get(4, 0), -4.0);
+
+    let h = &fits_image.header;
+    assert_eq!(h.get("PJ").and_then(|v| v.as_str()), Some("32"));
+    assert_eq!(h.get("OBSERVER").and_then(|v| v.as_str()), Some("Kevin's"));
+    assert!((h.get("EXPTIME").and_then(|v| v.as_f64()).unwrap() - 0.0032).abs() < 1e-12);
+    assert_eq!(h.get("STRETCH").and_then(|v| v.as_str()), Some("NONE"));
+
+    // Layout keywords are not carried in the header
+    assert!(h.get("NAXIS1").is_none());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_fits_metadata_header() {
+    let md = Metadata::new_from_file(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let header = fits::metadata_header(&md);
+
+    assert_eq!(header.get("PJ").and_then(|v| v.as_str()), Some("32"));
+    assert_eq!(
+        header.get("FILTER").and_then(|v| v.as_str()),
+        Some("BLUE,GREEN,RED")
+    );
+    assert!(header
+        .get("DATE-OBS")
+        .and_then(|v| v.as_str())
+        .unwrap()
+        .starts_with("2021-02-21T"));
+    // Exposure is converted from milliseconds to seconds
+    assert!((header.get("EXPTIME").and_then(|v| v.as_f64()).unwrap() - 0.0032).abs() < 1e-9);
+    assert!(header.get("SUBSCLAT").is_some());
+    assert!(header.get("SCALT").is_some());
+}
+
+#[test]
+fn test_replace_extension() {
+    assert_eq!(
+        output::replace_extension("data/image-raw.png", "-processed.png"),
+        "data/image-raw-processed.png"
+    );
+    assert_eq!(
+        output::replace_extension("image-calibrated.fits", "-processed.fits"),
+        "image-calibrated-processed.fits"
+    );
+    assert_eq!(
+        output::replace_extension("my.dir/image", ".tif"),
+        "my.dir/image.tif"
+    );
+}
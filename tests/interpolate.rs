@@ -0,0 +1,51 @@
+use junocam::interpolate::Interpolation;
+use sciimg::imagebuffer::ImageBuffer;
+
+// 4x4 buffer with values of 10x + y
+fn ramp() -> ImageBuffer {
+    let mut buffer = ImageBuffer::new(4, 4).unwrap();
+    (0..16).for_each(|i| buffer.put(i % 4, i / 4, (10 * (i % 4) + i / 4) as f32));
+    buffer
+}
+
+#[test]
+fn test_interpolation_from() {
+    assert_eq!(Interpolation::from("Bicubic"), Some(Interpolation::Bicubic));
+    assert_eq!(Interpolation::from("lanczos"), None);
+}
+
+#[test]
+fn test_nearest() {
+    let buffer = ramp();
+    assert_eq!(Interpolation::Nearest.sample(&buffer, 1.4, 2.6), 13.0);
+    assert_eq!(Interpolation::Nearest.sample(&buffer, 1.6, 2.4), 22.0);
+
+    // Clamped to the edge
+    assert_eq!(Interpolation::Nearest.sample(&buffer, -3.0, 7.0), 3.0);
+}
+
+#[test]
+fn test_bilinear() {
+    let buffer = ramp();
+    assert_eq!(Interpolation::Bilinear.sample(&buffer, 2.0, 1.0), 21.0);
+    assert!((Interpolation::Bilinear.sample(&buffer, 1.25, 2.5) - 15.0).abs() < 1.0e-5);
+
+    // Neighbors past the edge repeat the last column
+    assert!((Interpolation::Bilinear.sample(&buffer, 3.5, 0.0) - 30.0).abs() < 1.0e-5);
+}
+
+#[test]
+fn test_bicubic() {
+    let buffer = ramp();
+    assert_eq!(Interpolation::Bicubic.sample(&buffer, 1.0, 2.0), 12.0);
+
+    // Exact on a linear ramp where all four neighbors are inside the buffer
+    assert!((Interpolation::Bicubic.sample(&buffer, 1.25, 1.5) - 14.0).abs() < 1.0e-5);
+
+    // Overshoots a step, unlike bilinear
+    let mut step = ImageBuffer::new(4, 1).unwrap();
+    step.put(2, 0, 1.0);
+    step.put(3, 0, 1.0);
+    assert!(Interpolation::Bicubic.sample(&step, 0.5, 0.0) < 0.0);
+    assert!(Interpolation::Bilinear.sample(&step, 0.5, 0.0) >= 0.0);
+}
@@ -0,0 +1,220 @@
+use junocam::config::Defaults;
+use junocam::lens::{
+    cylindrical::CylindricalLens, fisheye::FisheyeEquisolidLens, lens::Lens,
+    perspective::PerspectiveLens,
+};
+use junocam::process::SupportedLens;
+use sciimg::vector::Vector;
+
+const EPSILON: f64 = 1.0e-6;
+
+fn assert_same_direction(a: &Vector, b: &Vector) {
+    let a = a.normalized();
+    let b = b.normalized();
+    assert!(
+        (a.x - b.x).abs() < EPSILON && (a.y - b.y).abs() < EPSILON && (a.z - b.z).abs() < EPSILON,
+        "Directions differ: {:?} vs {:?}",
+        a,
+        b
+    );
+}
+
+fn test_vectors() -> Vec<Vector> {
+    vec![
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(1.0, 0.2, 0.1),
+        Vector::new(0.8, -0.3, 0.4),
+        Vector::new(0.5, 0.5, -0.5),
+        Vector::new(0.2, -0.6, -0.1),
+        Vector::new(0.9, 0.01, -0.3),
+    ]
+}
+
+#[test]
+fn test_cylindrical_vector_round_trip() {
+    let lens = CylindricalLens::new(2048, 1024, 90.0, -90.0, 0.0, 360.0);
+
+    for v in test_vectors() {
+        let pt = lens.vector_to_point(&v).unwrap();
+        let v2 = lens.point_to_vector(pt.x, pt.y).unwrap();
+        assert_same_direction(&v, &v2);
+    }
+}
+
+#[test]
+fn test_cylindrical_point_round_trip() {
+    let lens = CylindricalLens::new(1000, 500, 90.0, -90.0, 0.0, 360.0);
+
+    for (x, y) in [(10.5, 10.5), (500.0, 250.0), (999.5, 1.5), (123.25, 456.75)] {
+        let v = lens.point_to_vector(x, y).unwrap();
+        let pt = lens.vector_to_point(&v).unwrap();
+        assert!((pt.x - x).abs() < EPSILON);
+        assert!((pt.y - y).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn test_fisheye_vector_round_trip() {
+    // Non-square output to exercise the centering adjustment
+    let lens = FisheyeEquisolidLens::new(1024, 768, 13.0, 180.0);
+
+    for v in test_vectors() {
+        let pt = lens.vector_to_point(&v).unwrap();
+        let v2 = lens.point_to_vector(pt.x, pt.y).unwrap();
+        assert_same_direction(&v, &v2);
+    }
+}
+
+#[test]
+fn test_fisheye_point_round_trip() {
+    let lens = FisheyeEquisolidLens::new(768, 1024, 13.0, 90.0);
+
+    for (x, y) in [
+        (384.0, 512.0),
+        (100.5, 400.5),
+        (700.0, 900.0),
+        (20.0, 150.0),
+    ] {
+        let v = lens.point_to_vector(x, y).unwrap();
+        let pt = lens.vector_to_point(&v).unwrap();
+        assert!((pt.x - x).abs() < EPSILON);
+        assert!((pt.y - y).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn test_fisheye_center_is_boresight() {
+    let lens = FisheyeEquisolidLens::new(1024, 1024, 13.0, 120.0);
+    let v = lens.point_to_vector(512.0, 512.0).unwrap();
+    assert_same_direction(&v, &Vector::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_fisheye_outside_sphere() {
+    // Far enough off the image, locations fall more than 180 degrees off axis.
+    let lens = FisheyeEquisolidLens::new(1024, 1024, 13.0, 60.0);
+    assert!(lens.point_to_vector(-2048.0, -2048.0).is_none());
+}
+
+#[test]
+fn test_perspective_point_round_trip() {
+    let lens = PerspectiveLens::new(1600, 900, 60.0, 16.0 / 9.0);
+
+    for (x, y) in [
+        (800.0, 450.0),
+        (0.5, 0.5),
+        (1599.5, 899.5),
+        (300.25, 700.75),
+    ] {
+        let v = lens.point_to_vector(x, y).unwrap();
+        let pt = lens.vector_to_point(&v).unwrap();
+        assert!((pt.x - x).abs() < EPSILON);
+        assert!((pt.y - y).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn test_perspective_field_of_view() {
+    let lens = PerspectiveLens::new(1000, 1000, 90.0, 1.0);
+    assert_same_direction(
+        &lens.point_to_vector(500.0, 500.0).unwrap(),
+        &Vector::new(1.0, 0.0, 0.0),
+    );
+
+    // Right edge of the frame is half the field of view off axis
+    assert_same_direction(
+        &lens.point_to_vector(1000.0, 500.0).unwrap(),
+        &Vector::new(1.0, 1.0, 0.0),
+    );
+}
+
+// [defaults] as in the shipped config.toml, with an optional perspective field of view
+fn defaults(perspective_field_of_view: Option<f64>) -> Defaults {
+    let mut toml = String::from(
+        r#"
+        red_weight = 0.902
+        green_weight = 1.0
+        blue_weight = 1.8879
+        camera_lens_projection = "fisheye"
+        fisheye_field_of_view = 180.0
+        interframe_delay_correction = 0.001
+        start_time_correction = 0.06188
+        apply_calibration = true
+        apply_infill_correction = true
+        apply_hot_pixel_correction = true
+        hpc_window_size = 5
+        hpc_threshold = 2.0
+        apply_weights = true
+        correlated_color_balancing = false
+        "#,
+    );
+    if let Some(fov) = perspective_field_of_view {
+        toml.push_str(&format!("perspective_field_of_view = {:?}\n", fov));
+    }
+    toml::from_str(&toml).unwrap()
+}
+
+#[test]
+fn test_perspective_default_field_of_view() {
+    let lens = SupportedLens::from("perspective").unwrap();
+    assert_eq!(lens, SupportedLens::Perspective);
+
+    // Perspective lenses don't inherit the fisheye's 180 degrees
+    assert_eq!(
+        SupportedLens::Fisheye.default_field_of_view(&defaults(None)),
+        180.0
+    );
+    assert_eq!(lens.default_field_of_view(&defaults(None)), 60.0);
+    assert_eq!(lens.default_field_of_view(&defaults(Some(45.0))), 45.0);
+
+    // The default makes a usable lens, with the right edge of the frame half of it off axis
+    let fov = lens.default_field_of_view(&defaults(None));
+    let lens = PerspectiveLens::new(1000, 1000, fov, 1.0);
+    assert_same_direction(
+        &lens.point_to_vector(1000.0, 500.0).unwrap(),
+        &Vector::new(1.0, (fov / 2.0).to_radians().tan(), 0.0),
+    );
+}
+
+#[test]
+fn test_perspective_letterbox() {
+    // 2:1 frame inside a square image leaves bands above and below
+    let lens = PerspectiveLens::new(1000, 1000, 60.0, 2.0);
+    assert!(lens.point_to_vector(500.0, 500.0).is_some());
+    assert!(lens.point_to_vector(500.0, 100.0).is_none());
+    assert!(lens.point_to_vector(500.0, 900.0).is_none());
+}
+
+#[test]
+fn test_cylindrical_subset_skips_outside() {
+    // 60 x 20 degree box, 10 pixels per degree
+    let lens = CylindricalLens::new(600, 200, -20.0, -40.0, 0.0, 60.0);
+
+    let inside = lens.point_to_vector(300.0, 100.0).unwrap();
+    let pt = lens.vector_to_point(&inside).unwrap();
+    assert!((pt.x - 300.0).abs() < EPSILON);
+    assert!((pt.y - 100.0).abs() < EPSILON);
+
+    // Directions outside of the box are dropped rather than wrapped into it
+    let outside = CylindricalLens::new(600, 200, 90.0, -90.0, 0.0, 360.0)
+        .point_to_vector(300.0, 100.0)
+        .unwrap();
+    assert!(lens.vector_to_point(&outside).is_none());
+    assert!(lens.point_to_vector(-1.0, 100.0).is_none());
+}
+
+#[test]
+fn test_cylindrical_wraps_through_zero() {
+    // 350 to 10 degrees, 10 pixels per degree
+    let lens = CylindricalLens::new(200, 100, 5.0, -5.0, 350.0, 10.0);
+    for x in [10.0, 50.0, 100.0, 150.0, 190.0] {
+        let v = lens.point_to_vector(x, 50.0).unwrap();
+        let pt = lens.vector_to_point(&v).unwrap();
+        assert!((pt.x - x).abs() < EPSILON, "{} vs {}", pt.x, x);
+    }
+
+    // Longitude 0 looks along -x
+    let pt = lens.vector_to_point(&Vector::new(-1.0, 0.0, 0.0)).unwrap();
+    assert!((pt.x - 100.0).abs() < EPSILON);
+    assert!(lens.vector_to_point(&Vector::new(1.0, 0.0, 0.0)).is_none());
+}
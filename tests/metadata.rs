@@ -87,3 +87,182 @@ fn test_load_metadata() {
 fn test_load_nonexistant_metadata() {
     metadata::Metadata::new_from_file("foo").unwrap();
 }
+
+#[test]
+fn test_product_id() {
+    let id = metadata::ProductId::parse("JNCE_2021052_32C00054_V01").unwrap();
+    assert_eq!(id.prefix(), "JNCE");
+    assert!(!id.is_rdr());
+    assert_eq!(id.year(), 2021);
+    assert_eq!(id.day_of_year(), 52);
+    assert_eq!(id.date(), NaiveDate::from_ymd_opt(2021, 2, 21).unwrap());
+    assert_eq!(id.perijove(), 32);
+    assert_eq!(id.mode(), 'C');
+    assert_eq!(id.image_number(), 54);
+    assert_eq!(id.version(), 1);
+    assert_eq!(id.to_string(), "JNCE_2021052_32C00054_V01");
+
+    // Parsed from metadata and file names
+    let md = metadata::Metadata::new_from_file(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    assert_eq!(md.product_id().unwrap(), id);
+    assert_eq!(
+        metadata::ProductId::from_file_name("PJ32/JNCE_2021052_32C00054_V01.IMG").unwrap(),
+        id
+    );
+
+    // Ordered by date, then image number and version. Perijove only breaks a tie.
+    let mut ids: Vec<metadata::ProductId> = [
+        "JNCE_2021052_32C00054_V02",
+        "JNCE_2021052_32C00054_V01",
+        "JNCE_2020365_31C00120_V01",
+        "JNCE_2021052_32C00010_V01",
+        "JNCE_2021052_33C00020_V01",
+        "JNCE_2021052_31C00054_V01",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect();
+    ids.sort();
+    let sorted: Vec<String> = ids.iter().map(|i| i.to_string()).collect();
+    assert_eq!(
+        sorted,
+        vec![
+            "JNCE_2020365_31C00120_V01",
+            "JNCE_2021052_32C00010_V01",
+            "JNCE_2021052_33C00020_V01",
+            "JNCE_2021052_31C00054_V01",
+            "JNCE_2021052_32C00054_V01",
+            "JNCE_2021052_32C00054_V02",
+        ]
+    );
+}
+
+#[test]
+fn test_malformed_product_id() {
+    use metadata::{ProductId, ProductIdError};
+
+    assert!(matches!(
+        ProductId::parse("10124-Metadata"),
+        Err(ProductIdError::Malformed(_))
+    ));
+    assert!(matches!(
+        ProductId::parse("JNOX_2021052_32C00054_V01"),
+        Err(ProductIdError::Prefix(_))
+    ));
+    assert!(matches!(
+        ProductId::parse("JNCE_2021367_32C00054_V01"),
+        Err(ProductIdError::Date(_))
+    ));
+    assert!(matches!(
+        ProductId::parse("JNCE_2021052_32C054_V01"),
+        Err(ProductIdError::Sequence(_))
+    ));
+    assert!(matches!(
+        ProductId::parse("JNCE_2021052_32C00054_01"),
+        Err(ProductIdError::Version(_))
+    ));
+}
+
+#[test]
+fn test_metadata_tolerant_fields() {
+    let md = metadata::Metadata::new_from_file(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    assert_eq!(md.target_name.as_deref(), Some("JUPITER"));
+    assert_eq!(
+        md.product_id.as_ref().map(|p| p.to_string()).as_deref(),
+        Some("JNCE_2021052_32C00054_V01")
+    );
+
+    // N/A, empty strings and empty arrays are taken as not given
+    assert_eq!(md.spacecraft_clock_stop_count, None);
+    assert_eq!(md.description, None);
+    assert_eq!(md.token_id, None);
+
+    // As are missing optional keys
+    let json_test_data = fs::read_to_string(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let mut parsed_json = json::parse(&json_test_data).unwrap();
+    parsed_json.remove(constants::metadata::DESCRIPTION);
+    parsed_json.remove(constants::metadata::TARGET_NAME);
+    parsed_json.remove(constants::metadata::IMAGE_TIME);
+    let md = metadata::Metadata::new_from_json(&parsed_json).unwrap();
+    assert_eq!(md.description, None);
+    assert_eq!(md.target_name, None);
+    assert_eq!(md.image_time, md.start_time);
+
+    // Others are still required
+    parsed_json.remove(constants::metadata::TITLE);
+    match metadata::Metadata::new_from_json(&parsed_json) {
+        Err(metadata::MetadataError::MissingKey(k)) => assert_eq!(k, "TITLE"),
+        _ => panic!("Expected a missing key error"),
+    }
+}
+
+#[test]
+fn test_metadata_errors_name_key() {
+    let json_test_data = fs::read_to_string(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let parsed_json = json::parse(&json_test_data).unwrap();
+
+    let mut missing = parsed_json.clone();
+    missing.remove(constants::metadata::INTERFRAME_DELAY);
+    match metadata::Metadata::new_from_json(&missing) {
+        Err(metadata::MetadataError::MissingKey(k)) => assert_eq!(k, "INTERFRAME_DELAY"),
+        _ => panic!("Expected a missing key error"),
+    }
+
+    let mut invalid = parsed_json.clone();
+    invalid[constants::metadata::EXPOSURE_DURATION] = "fast <ms>".into();
+    let err = metadata::Metadata::new_from_json(&invalid).err().unwrap();
+    assert!(err.to_string().contains("EXPOSURE_DURATION"));
+
+    let mut invalid = parsed_json;
+    invalid[constants::metadata::PRODUCT_ID] = "JNCE_2021052".into();
+    assert!(matches!(
+        metadata::Metadata::new_from_json(&invalid),
+        Err(metadata::MetadataError::InvalidValue { .. })
+    ));
+}
+
+#[test]
+fn test_metadata_methane_filter() {
+    let md = metadata::Metadata::new_from_file(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    assert_eq!(
+        md.filters.cameras(),
+        vec![
+            enums::Camera::BLUE,
+            enums::Camera::GREEN,
+            enums::Camera::RED
+        ]
+    );
+
+    let json_test_data = fs::read_to_string(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let mut parsed_json = json::parse(&json_test_data).unwrap();
+    parsed_json[constants::metadata::FILTER_NAME] = json::array!["METHANE"];
+    let md = metadata::Metadata::new_from_json(&parsed_json).unwrap();
+    assert!(md.filters.methane);
+    assert!(!md.filters.red);
+    assert_eq!(md.filters.cameras(), vec![enums::Camera::METHANE]);
+}
+
+#[test]
+fn test_metadata_filter_order() {
+    let json_test_data = fs::read_to_string(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let mut parsed_json = json::parse(&json_test_data).unwrap();
+
+    // Strips are read out in the order the filters are listed
+    parsed_json[constants::metadata::FILTER_NAME] = json::array!["RED", "BLUE"];
+    let md = metadata::Metadata::new_from_json(&parsed_json).unwrap();
+    assert!(md.filters.red && md.filters.blue && !md.filters.green);
+    assert_eq!(
+        md.filters.cameras(),
+        vec![enums::Camera::RED, enums::Camera::BLUE]
+    );
+
+    parsed_json[constants::metadata::FILTER_NAME] = "GREEN".into();
+    let md = metadata::Metadata::new_from_json(&parsed_json).unwrap();
+    assert_eq!(md.filters.cameras(), vec![enums::Camera::GREEN]);
+
+    parsed_json[constants::metadata::FILTER_NAME] = json::array!["RED", "INFRARED"];
+    assert!(matches!(
+        metadata::Metadata::new_from_json(&parsed_json),
+        Err(metadata::MetadataError::InvalidValue { .. })
+    ));
+}
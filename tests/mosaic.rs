@@ -0,0 +1,81 @@
+use junocam::mosaic::Mosaic;
+use junocam::render::WeightedSample;
+
+fn rows(width: usize, height: usize, sample: WeightedSample) -> Vec<Vec<WeightedSample>> {
+    vec![vec![sample; width]; height]
+}
+
+#[test]
+fn test_mosaic_weighted_blend() {
+    let mut mosaic = Mosaic::new(4, 2);
+
+    mosaic
+        .add(&rows(
+            4,
+            2,
+            WeightedSample {
+                sums: [100.0, 100.0, 100.0],
+                weights: [1.0, 1.0, 1.0],
+            },
+        ))
+        .unwrap();
+
+    // Second image has a quarter the weight, and only the red channel
+    mosaic
+        .add(&rows(
+            4,
+            2,
+            WeightedSample {
+                sums: [50.0, 0.0, 0.0],
+                weights: [0.25, 0.0, 0.0],
+            },
+        ))
+        .unwrap();
+
+    let map = mosaic.to_image().unwrap();
+    assert!((map.get(1, 1, 0) - 120.0).abs() < 1.0e-4);
+    assert!((map.get(1, 1, 1) - 100.0).abs() < 1.0e-4);
+
+    let coverage = mosaic.coverage().unwrap();
+    assert_eq!(coverage.get(0, 0), 2.0);
+    assert_eq!(mosaic.covered_pixels(), 8);
+}
+
+#[test]
+fn test_mosaic_empty_samples_not_counted() {
+    let mut mosaic = Mosaic::new(3, 3);
+    mosaic.add(&rows(3, 3, WeightedSample::default())).unwrap();
+    assert_eq!(mosaic.covered_pixels(), 0);
+    assert_eq!(mosaic.coverage().unwrap().get(1, 1), 0.0);
+}
+
+#[test]
+fn test_mosaic_size_mismatch() {
+    let mut mosaic = Mosaic::new(3, 3);
+    assert!(mosaic.add(&rows(2, 3, WeightedSample::default())).is_err());
+}
+
+#[test]
+fn test_mosaic_band_coverage() {
+    let mut mosaic = Mosaic::new(2, 1);
+    mosaic
+        .add(&[vec![
+            WeightedSample {
+                sums: [10.0, 0.0, 0.0],
+                weights: [1.0, 0.0, 0.0],
+            },
+            WeightedSample::default(),
+        ]])
+        .unwrap();
+
+    let coverage = mosaic.band_coverage();
+    assert!(coverage.is_covered(0, 0, 0));
+    assert!(!coverage.is_covered(0, 0, 1));
+    assert!(!coverage.is_any_covered(1, 0));
+
+    // Black where nothing was rendered, but transparent rather than opaque
+    let map = mosaic.to_image().unwrap();
+    assert!(map.is_using_alpha());
+    assert!(map.get_alpha_at(0, 0));
+    assert!(!map.get_alpha_at(1, 0));
+}
@@ -0,0 +1,101 @@
+use junocam::output::{self, OutputFormat, Stretch};
+use sciimg::{image::Image, imagebuffer::ImageBuffer};
+use std::fs::{self, File};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+fn test_buffer() -> ImageBuffer {
+    let mut buffer = ImageBuffer::new(3, 2).unwrap();
+    (0..6).for_each(|i| buffer.put(i % 3, i / 3, i as f32 * 0.25 - 0.5));
+    buffer
+}
+
+#[test]
+fn test_output_format_from() {
+    assert_eq!(OutputFormat::from("png"), Some(OutputFormat::Stretched));
+    assert_eq!(OutputFormat::from("TIFF"), Some(OutputFormat::FloatTiff));
+    assert_eq!(OutputFormat::from("fits"), Some(OutputFormat::Fits));
+    assert_eq!(OutputFormat::from("jpeg"), None);
+    assert!(!OutputFormat::Stretched.is_float());
+    assert_eq!(OutputFormat::Fits.extension(), Some(".fits"));
+}
+
+#[test]
+fn test_float_tiff_records_stretch() {
+    let buffer = test_buffer();
+    let image =
+        Image::new_from_buffers_rgb(&buffer, &buffer, &buffer, sciimg::enums::ImageMode::U16BIT)
+            .unwrap();
+
+    let path = std::env::temp_dir().join("junocam_test_output_rgb.tif");
+    let path = path.to_str().unwrap();
+    output::save_float_image(&image, path, OutputFormat::FloatTiff, Stretch::None, None).unwrap();
+
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (3, 2));
+    assert_eq!(
+        decoder.get_tag_ascii_string(Tag::ImageDescription).unwrap(),
+        "stretch=none"
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::F32(values) => {
+            assert_eq!(values.len(), 18);
+            // Negative values survive, unlike a 16 bit stretch
+            assert_eq!(values[0], -0.5);
+            assert_eq!(values[15], 0.75);
+        }
+        _ => panic!("Expected 32-bit float samples"),
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_fits_layout() {
+    let path = std::env::temp_dir().join("junocam_test_output.fits");
+    let path = path.to_str().unwrap();
+    output::save_buffer(&test_buffer(), path, OutputFormat::Fits).unwrap();
+
+    let bytes = fs::read(path).unwrap();
+    assert_eq!(bytes.len(), 2880 * 2);
+
+    let header = String::from_utf8(bytes[..2880].to_vec()).unwrap();
+    assert!(header.starts_with("SIMPLE  =                    T"));
+    assert_eq!(&header[80..110], "BITPIX  =                  -32");
+    assert!(header.contains("STRETCH = 'NONE    '"));
+    assert!(header.contains(&format!("{:<80}", "END")));
+
+    // Rows are stored bottom up, big endian
+    let first = f32::from_be_bytes([bytes[2880], bytes[2881], bytes[2882], bytes[2883]]);
+    assert_eq!(first, 0.25);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_output_format_parse_arg() {
+    assert_eq!(OutputFormat::parse_arg(None).unwrap(), None);
+    assert_eq!(
+        OutputFormat::parse_arg(Some(&"fits".to_string())).unwrap(),
+        Some(OutputFormat::Fits)
+    );
+    assert!(OutputFormat::parse_arg(Some(&"jpeg".to_string())).is_err());
+}
+
+#[test]
+fn test_stretch_sidecar() {
+    let path = std::env::temp_dir().join("junocam_test_output_stretch.png");
+    let path = path.to_str().unwrap();
+    let stretch = Stretch::Linear {
+        min: -0.5,
+        max: 0.75,
+        out_min: 0.0,
+        out_max: 65535.0,
+    };
+    output::save_stretch_sidecar(path, &[stretch, Stretch::None]).unwrap();
+
+    let sidecar = path.replace(".png", "-stretch.txt");
+    assert_eq!(
+        fs::read_to_string(&sidecar).unwrap(),
+        "band=0 stretch=linear min=-0.5 max=0.75 out_min=0 out_max=65535\nband=1 stretch=none\n"
+    );
+    fs::remove_file(sidecar).unwrap();
+}
@@ -0,0 +1,101 @@
+use junocam::pairing;
+use std::fs;
+
+mod common;
+
+#[test]
+fn test_pair_directory() {
+    let dir = std::env::temp_dir().join("junocam_test_pairing");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("ImageSet")).unwrap();
+    fs::create_dir_all(dir.join("DataSet")).unwrap();
+
+    let json = fs::read_to_string(common::constants::TEST_JSON_FILE_PATH).unwrap();
+    let other_json = json.replace("JNCE_2021052_32C00054_V01", "JNCE_2021052_32C00060_V01");
+    fs::write(dir.join("DataSet/10124-Metadata.json"), json).unwrap();
+    fs::write(dir.join("DataSet/10130-Metadata.json"), other_json).unwrap();
+    fs::write(dir.join("ImageSet/JNCE_2021052_32C00054_V01-raw.png"), b"").unwrap();
+    fs::write(dir.join("ImageSet/JNCE_2021052_32C00054_V01-blue.png"), b"").unwrap();
+    fs::write(dir.join("ImageSet/JNCE_2021052_32C00055_V01-raw.png"), b"").unwrap();
+    fs::write(dir.join("JNCE_2021052_32C00001_V01.IMG"), b"").unwrap();
+    fs::write(dir.join("JNCE_2021052_32C00001_V01.LBL"), b"").unwrap();
+    fs::write(dir.join("notes.txt"), b"").unwrap();
+
+    let dir_str = dir.to_str().unwrap().to_string();
+    let pairing = pairing::pair_inputs(std::slice::from_ref(&dir_str), &[]).unwrap();
+
+    assert_eq!(pairing.pairs.len(), 2);
+
+    // Sorted by product ID, with PDS products read through their label
+    let pds = &pairing.pairs[0];
+    assert_eq!(
+        pds.product_id.as_ref().map(|p| p.to_string()).as_deref(),
+        Some("JNCE_2021052_32C00001_V01")
+    );
+    assert!(pds.image.ends_with(".LBL"));
+    assert_eq!(pds.image, pds.metadata);
+
+    let raw = &pairing.pairs[1];
+    assert!(raw.image.ends_with("JNCE_2021052_32C00054_V01-raw.png"));
+    assert!(raw.metadata.ends_with("10124-Metadata.json"));
+
+    assert_eq!(pairing.orphan_images.len(), 1);
+    assert!(pairing.orphan_images[0].ends_with("JNCE_2021052_32C00055_V01-raw.png"));
+    assert_eq!(pairing.orphan_metadata.len(), 1);
+    assert!(pairing.orphan_metadata[0].ends_with("10130-Metadata.json"));
+
+    // Glob patterns are expanded the same way
+    let files = pairing::expand_inputs(&[format!("{}/ImageSet/*-raw.png", dir_str)]).unwrap();
+    assert_eq!(files.len(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pair_by_numeric_id() {
+    let files = vec![
+        "downloads/10124-ImageSet.zip".to_string(),
+        "downloads/10124-Data.zip".to_string(),
+    ];
+
+    // Neither zip exists to look inside, so they can only be matched by the missionjuno ID
+    let pairing = pairing::pair_files(&files);
+    assert_eq!(pairing.pairs.len(), 1);
+    assert_eq!(pairing.pairs[0].image, "downloads/10124-ImageSet.zip");
+    assert_eq!(pairing.pairs[0].metadata, "downloads/10124-Data.zip");
+}
+
+#[test]
+fn test_pair_explicit_metadata() {
+    let inputs = vec!["a-raw.png".to_string(), "b-raw.png".to_string()];
+    let metadata = vec!["1-Metadata.json".to_string()];
+    assert!(pairing::pair_inputs(&inputs, &metadata).is_err());
+
+    let metadata = vec!["2-Metadata.json".to_string(), "1-Metadata.json".to_string()];
+    let pairing = pairing::pair_inputs(&inputs, &metadata).unwrap();
+    assert_eq!(pairing.pairs[0].metadata, "2-Metadata.json");
+    assert_eq!(pairing.pairs[1].metadata, "1-Metadata.json");
+}
+
+#[test]
+fn test_pair_output_file() {
+    let pair = pairing::Pair {
+        image: "PJ32/10124-ImageSet.zip".to_string(),
+        metadata: "PJ32/10124-Data.zip".to_string(),
+        product_id: Some("JNCE_2021052_32C00054_V01".parse().unwrap()),
+    };
+    assert_eq!(
+        pair.output_file("-processed.png"),
+        "PJ32/JNCE_2021052_32C00054_V01-processed.png"
+    );
+
+    // Inputs already named for their product keep their name
+    let pair = pairing::Pair {
+        image: "PJ32/JNCE_2021052_32C00054_V01-raw.png".to_string(),
+        ..pair
+    };
+    assert_eq!(
+        pair.output_file("-processed.png"),
+        "PJ32/JNCE_2021052_32C00054_V01-raw-processed.png"
+    );
+}
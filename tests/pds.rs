@@ -0,0 +1,170 @@
+use junocam::enums::SampleBitMode;
+use junocam::metadata::Metadata;
+use junocam::pds::{self, Label, Value};
+use junocam::rawimage::RawImage;
+use std::fs;
+
+// Trimmed from a JNOJNC_0013 EDR label
+const EDR_LABEL: &str = r#"PDS_VERSION_ID = PDS3
+
+/* FILE FORMAT AND LENGTH */
+RECORD_TYPE = FIXED_LENGTH
+RECORD_BYTES = 1648
+FILE_RECORDS = 384
+^IMAGE = "JNCE_2021052_32C00054_V01.IMG"
+
+DATA_SET_ID = "JNO-J-JUNOCAM-2-EDR-L0-V1.0"
+PRODUCT_ID = "JNCE_2021052_32C00054_V01"
+PRODUCT_VERSION_ID = 1
+PRODUCT_CREATION_TIME = 2021-03-02T02:32:59
+PRODUCER_ID = "JUNO_JUNOCAM_TEAM"
+SOFTWARE_NAME = "JUNOMAKEPDS.PY 0.9"
+STANDARD_DATA_PRODUCT_ID = "JUNOCAM-EDR"
+FILE_NAME = "JNCE_2021052_32C00054_V01.IMG"
+SOURCE_PRODUCT_ID = "3D-0900200036-2021-058T21.19.47"
+PROCESSING_LEVEL_ID = 2
+MISSION_PHASE_NAME = "PERIJOVE 32"
+ORBIT_NUMBER = 32
+SEQUENCE_ID = "jm32"
+RATIONALE_DESC = "Jupiter
+                  imaging"
+DESCRIPTION = ""
+TITLE = "PJ32 South Polar Region"
+INSTRUMENT_HOST_NAME = "JUNO"
+SPACECRAFT_NAME = "JUNO"
+INSTRUMENT_NAME = "JUNO EPO CAMERA"
+INSTRUMENT_ID = JNC
+TARGET_NAME = JUPITER
+START_TIME = 2021-02-21T18:29:46.903
+STOP_TIME = 2021-02-21T18:29:56.523
+SPACECRAFT_CLOCK_START_COUNT = "667204540:183"
+SPACECRAFT_CLOCK_STOP_COUNT = "N/A"
+
+GROUP = INSTRUMENT_STATE_PARAMETERS
+  EXPOSURE_DURATION = 3.2 <ms>
+  INTERFRAME_DELAY = 0.370 <s>
+  JNO:TDI_STAGES_COUNT = 1
+  FILTER_NAME = (BLUE, GREEN, RED)
+  FOCAL_PLANE_TEMPERATURE = 273.0 <K>
+  SAMPLING_FACTOR = 1
+  SAMPLE_BIT_MODE_ID = SQROOT
+  COMPRESSION_TYPE = "INTEGER COSINE TRANSFORM"
+END_GROUP = INSTRUMENT_STATE_PARAMETERS
+
+SOLAR_DISTANCE = 7.5973e+08 <km>
+SPACECRAFT_ALTITUDE = 66525.5 <km>
+SUB_SPACECRAFT_LATITUDE = -55.8434
+SUB_SPACECRAFT_LONGITUDE = 79.5828
+
+OBJECT = IMAGE
+  LINES = 384
+  LINE_SAMPLES = 1648
+  SAMPLE_TYPE = UNSIGNED_INTEGER
+  SAMPLE_BITS = 8
+  SAMPLE_BIT_MASK = 2#11111111#
+  LINE_PREFIX_BYTES = 0
+  LINE_SUFFIX_BYTES = 0
+END_OBJECT = IMAGE
+END
+"#;
+
+#[test]
+fn test_pds_label_to_metadata() {
+    let label = Label::parse(EDR_LABEL, std::path::Path::new("edr.lbl")).unwrap();
+
+    assert_eq!(
+        label.get("RECORD_BYTES"),
+        Some(&Value::Literal("1648".into()))
+    );
+    assert_eq!(
+        label.get("RATIONALE_DESC"),
+        Some(&Value::Text("Jupiter imaging".into()))
+    );
+    assert_eq!(
+        label.get("EXPOSURE_DURATION"),
+        Some(&Value::WithUnits("3.2".into(), "ms".into()))
+    );
+    assert_eq!(
+        label.object("IMAGE").unwrap().get("SAMPLE_BIT_MASK"),
+        Some(&Value::Literal("2#11111111#".into()))
+    );
+
+    let md = Metadata::new_from_json(&label.to_json()).unwrap();
+    assert_eq!(md.pj, "32");
+    assert_eq!(md.lines, 384);
+    assert_eq!(md.line_samples, 1648);
+    assert_eq!(md.exposure_duration, 3.2);
+    assert_eq!(md.interframe_delay, 0.37);
+    assert_eq!(md.solar_distance, 7.5973e+08);
+    assert_eq!(md.sub_spacecraft_latitude, -55.8434);
+    assert_eq!(md.image_time, md.start_time);
+    assert!(md.filters.red && md.filters.green && md.filters.blue);
+    assert!(matches!(md.sample_bit_mode_id, SampleBitMode::SQROOT));
+}
+
+#[test]
+fn test_pds_detached_edr() {
+    let dir = std::env::temp_dir().join("junocam_test_pds_detached");
+    fs::create_dir_all(&dir).unwrap();
+
+    let data: Vec<u8> = (0..1648 * 384).map(|i| (i % 251) as u8).collect();
+    fs::write(dir.join("JNCE_2021052_32C00054_V01.IMG"), &data).unwrap();
+    let label_path = dir.join("JNCE_2021052_32C00054_V01.LBL");
+    fs::write(&label_path, EDR_LABEL).unwrap();
+
+    // Either half of the product can be given
+    let img_path = dir.join("JNCE_2021052_32C00054_V01.IMG");
+    assert!(pds::is_pds_file(img_path.to_str().unwrap()));
+    let md = Metadata::new_from_file(img_path.to_str().unwrap()).unwrap();
+    assert_eq!(md.orbit_number, 32);
+
+    let raw = RawImage::new_from_pds(label_path.to_str().unwrap()).unwrap();
+    assert_eq!(raw.get_framelet_set_count(), 1);
+    assert_eq!(raw.rawdata.width, 1648);
+    assert_eq!(raw.rawdata.get(10, 2), ((2 * 1648 + 10) % 251) as f32);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pds_attached_16bit_with_prefix() {
+    // One record of label, then three lines of four LSB samples behind a 2 byte prefix
+    let label = "PDS_VERSION_ID = PDS3\r\n\
+                 RECORD_TYPE = FIXED_LENGTH\r\n\
+                 RECORD_BYTES = 512\r\n\
+                 ^IMAGE = 2\r\n\
+                 OBJECT = IMAGE\r\n\
+                 \x20 LINES = 3\r\n\
+                 \x20 LINE_SAMPLES = 4\r\n\
+                 \x20 SAMPLE_TYPE = LSB_UNSIGNED_INTEGER\r\n\
+                 \x20 SAMPLE_BITS = 16\r\n\
+                 \x20 LINE_PREFIX_BYTES = 2 /* frame counter */\r\n\
+                 END_OBJECT = IMAGE\r\n\
+                 END\r\n";
+
+    let mut bytes = label.as_bytes().to_vec();
+    bytes.resize(512, b' ');
+    for y in 0..3u16 {
+        bytes.extend_from_slice(&[0xff, 0xff]);
+        for x in 0..4u16 {
+            bytes.extend_from_slice(&(y * 1000 + x).to_le_bytes());
+        }
+    }
+
+    let path = std::env::temp_dir().join("junocam_test_attached.img");
+    fs::write(&path, &bytes).unwrap();
+
+    let label = Label::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(label.sample_bits(), Some(16));
+    let buffer = label.read_image().unwrap();
+    assert_eq!(buffer.width, 4);
+    assert_eq!(buffer.height, 3);
+    assert_eq!(buffer.get(0, 0), 0.0);
+    assert_eq!(buffer.get(3, 2), 2003.0);
+
+    // A short file is reported rather than read past
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(label.read_image().is_err());
+
+    fs::remove_file(&path).unwrap();
+}
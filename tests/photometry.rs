@@ -0,0 +1,53 @@
+use junocam::photometry::PhotometricModel;
+
+const EPSILON: f64 = 1e-9;
+
+#[test]
+fn test_photometry_from() {
+    let k = [0.9, 0.8, 0.7];
+    assert_eq!(
+        PhotometricModel::from("none", k),
+        Some(PhotometricModel::None)
+    );
+    assert_eq!(
+        PhotometricModel::from("Minnaert", k),
+        Some(PhotometricModel::Minnaert { k })
+    );
+    assert_eq!(
+        PhotometricModel::from("lommel-seeliger", k),
+        Some(PhotometricModel::LommelSeeliger)
+    );
+    assert_eq!(PhotometricModel::from("hapke", k), None);
+}
+
+#[test]
+fn test_photometry_factor() {
+    // No correction anywhere, even on the limb
+    assert_eq!(PhotometricModel::None.factor(0, 0.0, 0.0), Some(1.0));
+
+    // Lambertian Minnaert reduces to the cosine of incidence
+    let lambert = PhotometricModel::Minnaert { k: [1.0; 3] };
+    assert!((lambert.factor(0, 0.5, 0.3).unwrap() - 0.5).abs() < EPSILON);
+
+    // Exponents are per channel
+    let minnaert = PhotometricModel::Minnaert { k: [1.0, 0.5, 0.8] };
+    let expected = 0.25_f64.powf(0.5) * 0.64_f64.powf(-0.5);
+    assert!((minnaert.factor(1, 0.25, 0.64).unwrap() - expected).abs() < EPSILON);
+
+    let ls = PhotometricModel::LommelSeeliger;
+    assert!((ls.factor(0, 1.0, 1.0).unwrap() - 1.0).abs() < EPSILON);
+    assert!((ls.factor(2, 0.5, 0.25).unwrap() - 2.0 * 0.5 / 0.75).abs() < EPSILON);
+}
+
+#[test]
+fn test_photometry_terminator_and_limb() {
+    let models = [
+        PhotometricModel::Minnaert { k: [0.8; 3] },
+        PhotometricModel::LommelSeeliger,
+    ];
+    for model in models {
+        assert_eq!(model.factor(0, 0.0, 0.5), None);
+        assert_eq!(model.factor(0, 0.5, 0.01), None);
+        assert_eq!(model.factor(0, -0.2, 0.5), None);
+    }
+}
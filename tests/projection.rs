@@ -0,0 +1,355 @@
+use junocam::ellipsoid::{Ellipsoid, LatLon};
+use junocam::projection::{
+    equirectangular::EquirectangularProjection,
+    georeference::{JupiterCrs, JUPITER_IAU2015_SPHERE_RADIUS},
+    orthographic::OrthographicProjection,
+    polar::{AzimuthalMapping, PolarProjection, Pole},
+    projection::{LatLonBounds, MapProjection},
+};
+use sciimg::vector::Vector;
+
+const EPSILON: f64 = 1.0e-6;
+
+// IAU 2015 Jupiter radii, so the tests don't need a PCK
+fn jupiter() -> Ellipsoid {
+    Ellipsoid::new(71492.0, 71492.0, 66854.0)
+}
+
+#[test]
+fn test_ellipsoid_lat_lon_round_trip() {
+    let ellipsoid = jupiter();
+    for lat in [-80.0, -45.0, 0.0, 23.5, 60.0] {
+        for lon in [0.0, 45.0, 180.0, 300.0] {
+            let ll = ellipsoid.to_lat_lon(&ellipsoid.surface_point(&LatLon { lat, lon }));
+            assert!((ll.lat - lat).abs() < EPSILON, "{} vs {}", ll.lat, lat);
+            assert!((ll.lon - lon).abs() < EPSILON, "{} vs {}", ll.lon, lon);
+        }
+    }
+}
+
+#[test]
+fn test_ellipsoid_intersect() {
+    let ellipsoid = jupiter();
+
+    let hit = ellipsoid
+        .intersect(
+            &Vector::new(200000.0, 0.0, 0.0),
+            &Vector::new(-1.0, 0.0, 0.0),
+        )
+        .unwrap();
+    assert!((hit.x - 71492.0).abs() < EPSILON);
+
+    let hit = ellipsoid
+        .intersect(
+            &Vector::new(0.0, 0.0, 200000.0),
+            &Vector::new(0.0, 0.0, -1.0),
+        )
+        .unwrap();
+    assert!((hit.z - 66854.0).abs() < EPSILON);
+
+    // Looking away from the planet
+    assert!(ellipsoid
+        .intersect(
+            &Vector::new(200000.0, 0.0, 0.0),
+            &Vector::new(1.0, 0.0, 0.0)
+        )
+        .is_none());
+}
+
+#[test]
+fn test_planetographic_round_trip() {
+    let ellipsoid = jupiter();
+    for lat in [-70.0, -10.0, 0.0, 35.0, 89.0] {
+        let pg = ellipsoid.planetocentric_to_planetographic(lat);
+        assert!(pg.abs() >= lat.abs());
+        assert!((ellipsoid.planetographic_to_planetocentric(pg) - lat).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn test_equirectangular_round_trip() {
+    for planetographic in [false, true] {
+        let proj = EquirectangularProjection::new_global(1024, 512, planetographic, jupiter());
+        for (x, y) in [(0.5, 0.5), (100.0, 200.0), (512.0, 256.0), (1000.0, 500.0)] {
+            let ll = proj.point_to_lat_lon(x, y).unwrap();
+            let (x2, y2) = proj.lat_lon_to_point(&ll).unwrap();
+            assert!((x - x2).abs() < EPSILON, "{} vs {}", x, x2);
+            assert!((y - y2).abs() < EPSILON, "{} vs {}", y, y2);
+        }
+        assert!(proj.point_to_lat_lon(-1.0, 10.0).is_none());
+        assert!(proj.point_to_lat_lon(10.0, 512.0).is_none());
+    }
+}
+
+#[test]
+fn test_equirectangular_wraps_prime_meridian() {
+    // 350 to 10 degrees east, 10 pixels per degree
+    let proj = EquirectangularProjection::new(200, 100, 5.0, -5.0, 350.0, 10.0, false, jupiter());
+    let (x, _) = proj
+        .lat_lon_to_point(&LatLon {
+            lat: 0.0,
+            lon: 355.0,
+        })
+        .unwrap();
+    assert!((x - 50.0).abs() < EPSILON);
+    let (x, _) = proj
+        .lat_lon_to_point(&LatLon { lat: 0.0, lon: 5.0 })
+        .unwrap();
+    assert!((x - 150.0).abs() < EPSILON);
+    assert!(proj
+        .lat_lon_to_point(&LatLon {
+            lat: 0.0,
+            lon: 180.0
+        })
+        .is_none());
+
+    let ll = proj.point_to_lat_lon(150.0, 50.0).unwrap();
+    assert!((ll.lon - 5.0).abs() < EPSILON);
+
+    // The same window in west longitudes runs from 10 on the left down to 350
+    let proj = EquirectangularProjection::new(200, 100, 5.0, -5.0, 10.0, 350.0, true, jupiter());
+    let (x, _) = proj
+        .lat_lon_to_point(&LatLon {
+            lat: 0.0,
+            lon: 355.0,
+        })
+        .unwrap();
+    assert!((x - 50.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_orthographic_center_is_sub_observer() {
+    let ellipsoid = jupiter();
+    let sub_observer = LatLon {
+        lat: -90.0,
+        lon: 0.0,
+    };
+    let proj = OrthographicProjection::new(1024, 1024, &sub_observer, 150.0, ellipsoid);
+    let ll = proj.point_to_lat_lon(512.0, 512.0).unwrap();
+    assert!((ll.lat + 90.0).abs() < EPSILON);
+
+    // Beyond the limb
+    assert!(proj.point_to_lat_lon(0.0, 0.0).is_none());
+
+    // Northern hemisphere is on the far side
+    assert!(proj
+        .lat_lon_to_point(&LatLon {
+            lat: 30.0,
+            lon: 10.0
+        })
+        .is_none());
+}
+
+#[test]
+fn test_orthographic_round_trip() {
+    let ellipsoid = jupiter();
+    let sub_observer = LatLon {
+        lat: 20.0,
+        lon: 135.0,
+    };
+    let scale = OrthographicProjection::fit_disc_scale(800, 600, &ellipsoid);
+    let proj = OrthographicProjection::new(800, 600, &sub_observer, scale, ellipsoid);
+    for (x, y) in [(400.0, 300.0), (250.5, 200.5), (500.0, 450.0)] {
+        let ll = proj.point_to_lat_lon(x, y).unwrap();
+        let (x2, y2) = proj.lat_lon_to_point(&ll).unwrap();
+        assert!((x - x2).abs() < 1.0e-4, "{} vs {}", x, x2);
+        assert!((y - y2).abs() < 1.0e-4, "{} vs {}", y, y2);
+    }
+}
+
+#[test]
+fn test_polar_round_trip() {
+    for pole in [Pole::North, Pole::South] {
+        for mapping in [AzimuthalMapping::Stereographic, AzimuthalMapping::EqualArea] {
+            let proj = PolarProjection::new(1000, 800, pole, mapping, 50.0, None, &jupiter());
+            for (x, y) in [(500.0, 400.0), (300.5, 200.5), (650.0, 700.0)] {
+                let ll = proj.point_to_lat_lon(x, y).unwrap();
+                let (x2, y2) = proj.lat_lon_to_point(&ll).unwrap();
+                assert!((x - x2).abs() < EPSILON, "{} vs {}", x, x2);
+                assert!((y - y2).abs() < EPSILON, "{} vs {}", y, y2);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_polar_bounds() {
+    let proj = PolarProjection::new(
+        1000,
+        1000,
+        Pole::South,
+        AzimuthalMapping::Stereographic,
+        60.0,
+        None,
+        &jupiter(),
+    );
+
+    let ll = proj.point_to_lat_lon(500.0, 500.0).unwrap();
+    assert!((ll.lat + 90.0).abs() < EPSILON);
+
+    // Bounding circle touches the image edges
+    let ll = proj.point_to_lat_lon(999.999, 500.0).unwrap();
+    assert!((ll.lat + 60.0).abs() < 1.0e-3);
+
+    // Corners are equatorward of the bound
+    assert!(proj.point_to_lat_lon(1.0, 1.0).is_none());
+
+    // Wrong hemisphere
+    assert!(proj
+        .lat_lon_to_point(&LatLon {
+            lat: 70.0,
+            lon: 0.0
+        })
+        .is_none());
+
+    // South polar maps put longitude zero at the top
+    let (x, y) = proj
+        .lat_lon_to_point(&LatLon {
+            lat: -70.0,
+            lon: 0.0,
+        })
+        .unwrap();
+    assert!((x - 500.0).abs() < EPSILON);
+    assert!(y < 500.0);
+}
+
+#[test]
+fn test_bounds_image_size() {
+    let bounds = LatLonBounds::new(-20.0, -40.0, 60.0, 0.0);
+    assert_eq!(bounds.image_size(0.1, true), (600, 200));
+    assert_eq!(LatLonBounds::global().image_size(0.5, false), (720, 360));
+    assert_eq!(LatLonBounds::global().image_size(0.5, true), (720, 360));
+
+    // Boxes crossing 0/360
+    let bounds = LatLonBounds::new(-20.0, -40.0, 350.0, 10.0);
+    assert_eq!(bounds.image_size(0.1, false), (200, 200));
+    assert_eq!(bounds.image_size(0.1, true), (3400, 200));
+}
+
+#[test]
+fn test_equirectangular_georeference() {
+    let proj = EquirectangularProjection::new_global(3600, 1800, false, jupiter());
+    let georef = proj.georeference().unwrap();
+    let meters_per_degree = JUPITER_IAU2015_SPHERE_RADIUS.to_radians();
+
+    // Running 0 to 360 east, centered on 180 degrees
+    assert_eq!(georef.crs.code(), 59915);
+    assert!((georef.origin_x + 180.0 * meters_per_degree).abs() < 1.0e-3);
+    assert!((georef.origin_y - 90.0 * meters_per_degree).abs() < 1.0e-3);
+    assert!((georef.pixel_width - 0.1 * meters_per_degree).abs() < 1.0e-3);
+    assert!((georef.pixel_height + 0.1 * meters_per_degree).abs() < 1.0e-3);
+
+    // Maps within 180 degrees either side of the reference meridian keep to it
+    let bounds = LatLonBounds::new(-20.0, -40.0, -30.0, 60.0);
+    let proj = EquirectangularProjection::new(
+        900,
+        200,
+        bounds.top_lat,
+        bounds.bottom_lat,
+        bounds.left_lon,
+        bounds.right_lon,
+        false,
+        jupiter(),
+    );
+    let georef = proj.georeference().unwrap();
+    assert_eq!(georef.crs, JupiterCrs::Equirectangular);
+    assert!((georef.origin_x + 30.0 * meters_per_degree).abs() < 1.0e-3);
+
+    // West-positive planetographic maps are not in the reference system
+    let proj = EquirectangularProjection::new_global(3600, 1800, true, jupiter());
+    assert!(proj.georeference().is_none());
+}
+
+#[test]
+fn test_polar_stereographic_georeference() {
+    let ellipsoid = jupiter();
+    for pole in [Pole::North, Pole::South] {
+        let proj = PolarProjection::new(
+            1000,
+            800,
+            pole,
+            AzimuthalMapping::Stereographic,
+            50.0,
+            None,
+            &ellipsoid,
+        );
+        let georef = proj.georeference().unwrap();
+        assert_eq!(
+            georef.crs,
+            match pole {
+                Pole::North => JupiterCrs::NorthPolarStereographic,
+                Pole::South => JupiterCrs::SouthPolarStereographic,
+            }
+        );
+
+        // Map coordinates from the world file agree with the spherical polar stereographic
+        // projection on the reference sphere
+        let ll = LatLon {
+            lat: if pole == Pole::North { 70.0 } else { -70.0 },
+            lon: 30.0,
+        };
+        let (x, y) = proj.lat_lon_to_point(&ll).unwrap();
+        let map_x = georef.origin_x + x * georef.pixel_width;
+        let map_y = georef.origin_y + y * georef.pixel_height;
+
+        let rho = 2.0 * JUPITER_IAU2015_SPHERE_RADIUS * (20.0_f64.to_radians() / 2.0).tan();
+        let lon = 30.0_f64.to_radians();
+        let expected_y = match pole {
+            Pole::North => -rho * lon.cos(),
+            Pole::South => rho * lon.cos(),
+        };
+        assert!((map_x - rho * lon.sin()).abs() < 1.0e-3);
+        assert!((map_y - expected_y).abs() < 1.0e-3);
+    }
+
+    let lambert = PolarProjection::new(
+        100,
+        100,
+        Pole::North,
+        AzimuthalMapping::EqualArea,
+        50.0,
+        None,
+        &ellipsoid,
+    );
+    assert!(lambert.georeference().is_none());
+}
+
+#[test]
+fn test_georeference_sidecars() {
+    let wkt = JupiterCrs::SouthPolarStereographic.wkt();
+    assert!(wkt.starts_with("PROJCS[\"Jupiter (2015) - Sphere / Ocentric / South Polar\""));
+    assert!(wkt.contains("PARAMETER[\"latitude_of_origin\",-90]"));
+    assert!(wkt.ends_with("AUTHORITY[\"IAU_2015\",\"59935\"]]"));
+
+    let proj = EquirectangularProjection::new_global(360, 180, false, jupiter());
+    let georef = proj.georeference().unwrap();
+    let lines: Vec<f64> = georef
+        .world_file()
+        .lines()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    assert_eq!(lines.len(), 6);
+
+    // Centered on the upper left pixel, half a pixel east of 180 degrees west of the
+    // central meridian
+    let meters_per_degree = JUPITER_IAU2015_SPHERE_RADIUS.to_radians();
+    assert!((lines[0] - meters_per_degree).abs() < 1.0e-3);
+    assert!((lines[4] - (-180.0 * meters_per_degree + georef.pixel_width / 2.0)).abs() < 1.0e-3);
+    assert!((lines[5] - (georef.origin_y + georef.pixel_height / 2.0)).abs() < 1.0e-3);
+
+    let path = std::env::temp_dir().join("junocam_test_georef.png");
+    let path = path.to_str().unwrap();
+    georef.save_sidecars(path).unwrap();
+    let pgw = path.replace(".png", ".pgw");
+    let prj = path.replace(".png", ".prj");
+    assert_eq!(std::fs::read_to_string(&pgw).unwrap(), georef.world_file());
+    assert_eq!(
+        std::fs::read_to_string(&prj).unwrap(),
+        JupiterCrs::Equirectangular180.wkt()
+    );
+    assert!(JupiterCrs::Equirectangular180
+        .wkt()
+        .contains("PARAMETER[\"central_meridian\",180]"));
+    std::fs::remove_file(pgw).unwrap();
+    std::fs::remove_file(prj).unwrap();
+}
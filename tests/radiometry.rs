@@ -0,0 +1,48 @@
+use junocam::enums::Camera;
+use junocam::radiometry::{self, Radiometry, AU_KM};
+use std::f64::consts::PI;
+
+const EPSILON: f64 = 1e-9;
+
+fn radiometry() -> Radiometry {
+    Radiometry {
+        red_responsivity: 2000.0,
+        green_responsivity: 1500.0,
+        blue_responsivity: 1000.0,
+        methane_responsivity: None,
+        red_solar_irradiance: 1.45,
+        green_solar_irradiance: 1.85,
+        blue_solar_irradiance: 2.0,
+        methane_solar_irradiance: None,
+    }
+}
+
+#[test]
+fn test_dn_to_i_over_f() {
+    let radiance = radiometry::dn_to_radiance(320.0, 0.0032, 1000.0);
+    assert!((radiance - 100.0).abs() < EPSILON);
+
+    // At 1 AU, pi * L / F
+    let i_over_f = radiometry::radiance_to_i_over_f(radiance, 2.0, 1.0);
+    assert!((i_over_f - 50.0 * PI).abs() < EPSILON);
+
+    // Falls off with the square of the distance from the Sun
+    let at_jupiter = radiometry::radiance_to_i_over_f(radiance, 2.0, 5.2);
+    assert!((at_jupiter - 50.0 * PI * 5.2 * 5.2).abs() < 1e-6);
+}
+
+#[test]
+fn test_i_over_f_scale() {
+    let r = radiometry();
+
+    // Exposure is in milliseconds and solar distance in kilometers, as in the metadata
+    let scale = r.i_over_f_scale(Camera::BLUE, 3.2, 5.2 * AU_KM).unwrap();
+    let expected = PI * 5.2 * 5.2 / (0.0032 * 1000.0 * 2.0);
+    assert!((scale - expected).abs() < 1e-6);
+
+    let red = r.i_over_f_scale(Camera::RED, 3.2, 5.2 * AU_KM).unwrap();
+    assert!((red - PI * 5.2 * 5.2 / (0.0032 * 2000.0 * 1.45)).abs() < 1e-6);
+
+    assert!(r.i_over_f_scale(Camera::METHANE, 3.2, AU_KM).is_err());
+    assert!(r.i_over_f_scale(Camera::RED, 0.0, AU_KM).is_err());
+}
@@ -1,5 +1,6 @@
-use junocam::{constants, enums, metadata, rawimage};
+use junocam::{constants, enums, error::JunocamError, junocam as jc, metadata, rawimage, render};
 use sciimg::path;
+use sciimg::vector::Vector;
 mod common;
 
 #[test]
@@ -16,14 +17,17 @@ fn test_load_image() {
 
     // Split the raw image out into triplets (and individual strips
     // under the hood). Then check the count
-    assert_eq!(raw_image.get_triplet_count(), 26);
+    assert_eq!(raw_image.get_framelet_set_count(), 26);
+
+    // Nothing was lost in downlink, dark sky included
+    assert!(!raw_image.detect_gaps().has_gaps());
 
     // Load the metadata file so we can try to predict the number
     // of triplets from that then check the actual count
     // from the raw image
     let md = metadata::Metadata::new_from_file(common::constants::TEST_JSON_FILE_PATH).unwrap();
-    let expected_triplets = md.lines as u32 / (constants::STRIP_HEIGHT as u32 * 3);
-    assert_eq!(raw_image.get_triplet_count() as u32, expected_triplets);
+    let expected_triplets = md.lines as usize / (constants::STRIP_HEIGHT * 3);
+    assert_eq!(raw_image.get_framelet_set_count(), expected_triplets);
 
     // Image calibration routines. These will take a while in test
     raw_image
@@ -43,7 +47,244 @@ fn test_load_image() {
         .expect("Error applying channel weight values");
 
     // Reassemble all data back into a full strip stack
-    let _assembled_final = raw_image.assemble();
+    let _assembled_final = raw_image.assemble().unwrap();
 
     //_assembled_final.save("test.png", ImageMode::U16BIT);
 }
+
+#[test]
+fn test_load_image_errors() {
+    // An unreadable image is reported rather than panicking
+    let file_path = std::env::temp_dir().join("junocam_test_not_a.png");
+    std::fs::write(&file_path, b"not a png").unwrap();
+
+    let err = rawimage::RawImage::new_from_image(file_path.to_str().unwrap())
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref::<JunocamError>(),
+        Some(JunocamError::Io(_))
+    ));
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_methane_framelets() {
+    // Methane is read out as a single strip per framelet
+    let file_path = std::env::temp_dir().join("junocam_test_methane-raw.png");
+    let raw = image::GrayImage::from_fn(1648, 128 * 4, |x, y| {
+        image::Luma([(y / 128 * 10 + x % 2) as u8])
+    });
+    raw.save(&file_path).unwrap();
+
+    // Read as a triplet and a partial one until the filters are known
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    assert_eq!(raw_image.get_framelet_set_count(), 2);
+
+    raw_image.set_cameras(&[enums::Camera::METHANE]).unwrap();
+    assert_eq!(raw_image.get_framelet_set_count(), 4);
+    assert_eq!(raw_image.cameras(), vec![enums::Camera::METHANE]);
+    assert_eq!(
+        raw_image.framelet_sets[3].channels[0].buffer.get(1, 0),
+        31.0
+    );
+
+    // Weights are a color balance and leave methane alone
+    raw_image.apply_weights(2.0, 2.0, 2.0).unwrap();
+    let assembled = raw_image.assemble().unwrap();
+    assert_eq!(assembled.height, 128 * 4);
+    assert_eq!(assembled.get(1, 128 * 2 + 5), 21.0);
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_two_filter_framelets() {
+    let file_path = std::env::temp_dir().join("junocam_test_two_filter-raw.png");
+    let raw = image::GrayImage::from_fn(1648, 128 * 5, |_, y| image::Luma([(y / 128) as u8]));
+    raw.save(&file_path).unwrap();
+
+    // Green and red pairs, with a partial set left over at the end
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    let cameras = [enums::Camera::GREEN, enums::Camera::RED];
+    raw_image.set_cameras(&cameras).unwrap();
+    assert_eq!(raw_image.get_framelet_set_count(), 3);
+    assert_eq!(raw_image.cameras(), cameras.to_vec());
+    assert_eq!(raw_image.framelet_sets[1].channels[0].buffer.get(0, 0), 2.0);
+    assert_eq!(raw_image.framelet_sets[1].channels[1].buffer.get(0, 0), 3.0);
+    assert_eq!(raw_image.framelet_sets[2].channels[0].buffer.get(0, 0), 4.0);
+    assert_eq!(raw_image.framelet_sets[2].channels[1].gap_line_count(), 128);
+
+    assert_eq!(render::output_band_count(&cameras), 3);
+    assert_eq!(render::output_band_count(&[enums::Camera::GREEN]), 1);
+
+    // Blue is left empty, and methane out of color output
+    assert_eq!(render::empty_bands(&cameras), vec![2]);
+    assert!(render::omitted_cameras(&cameras).is_empty());
+    let with_methane = [
+        enums::Camera::BLUE,
+        enums::Camera::METHANE,
+        enums::Camera::RED,
+    ];
+    assert_eq!(
+        render::omitted_cameras(&with_methane),
+        vec![enums::Camera::METHANE]
+    );
+    assert_eq!(render::empty_bands(&with_methane), vec![1]);
+    assert!(render::empty_bands(&[enums::Camera::METHANE]).is_empty());
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_load_binned_image() {
+    // Sum the fixture 2x2, as the camera does with SAMPLING_FACTOR 2. Values are averaged
+    // to stay within 8 bits.
+    let full = image::open(common::constants::TEST_RAW_IMAGE_FILE_PATH)
+        .unwrap()
+        .to_luma8();
+    let binned = image::GrayImage::from_fn(full.width() / 2, full.height() / 2, |x, y| {
+        let sum: u32 = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|(dx, dy)| full.get_pixel(x * 2 + dx, y * 2 + dy)[0] as u32)
+            .sum();
+        image::Luma([(sum / 4) as u8])
+    });
+    let file_path = std::env::temp_dir().join("junocam_test_binned-raw.png");
+    binned.save(&file_path).unwrap();
+
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    assert_eq!(raw_image.sampling_factor, 2);
+    assert_eq!(raw_image.strip_height(), constants::STRIP_HEIGHT / 2);
+    assert_eq!(raw_image.get_framelet_set_count(), 26);
+
+    let strip = &raw_image.framelet_sets[0].channels[0];
+    assert_eq!(strip.buffer.width, constants::STRIP_WIDTH / 2);
+    assert_eq!(strip.buffer.height, constants::STRIP_HEIGHT / 2);
+    assert_eq!(strip.buffer.get(10, 5), binned.get_pixel(10, 5)[0] as f32);
+
+    // Metadata and image must agree on the summing
+    assert!(raw_image.set_layout(&raw_image.cameras(), 1).is_err());
+    raw_image
+        .appy_decomanding(enums::SampleBitMode::SQROOT)
+        .expect("Error with decompanding");
+    raw_image
+        .apply_darknoise()
+        .expect("Error with dark/flat field correction");
+
+    let assembled = raw_image.assemble().unwrap();
+    assert_eq!(assembled.width, binned.width() as usize);
+    assert_eq!(assembled.height, binned.height() as usize);
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_summed_framelet_layout() {
+    // Four summed green, red pairs
+    let file_path = std::env::temp_dir().join("junocam_test_summed-raw.png");
+    let raw = image::GrayImage::from_fn(412, 32 * 8, |_, y| image::Luma([(y / 32) as u8]));
+    raw.save(&file_path).unwrap();
+
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    assert_eq!(raw_image.sampling_factor, 4);
+
+    let cameras = [enums::Camera::GREEN, enums::Camera::RED];
+    assert!(raw_image.set_layout(&cameras, 2).is_err());
+    raw_image.set_layout(&cameras, 4).unwrap();
+    assert_eq!(raw_image.get_framelet_set_count(), 4);
+    assert_eq!(raw_image.framelet_sets[3].channels[1].buffer.height, 32);
+    assert_eq!(raw_image.framelet_sets[3].channels[1].buffer.get(0, 0), 7.0);
+
+    // Widths that aren't a whole fraction of a framelet are rejected
+    let raw = image::GrayImage::new(1000, 128 * 3);
+    raw.save(&file_path).unwrap();
+    assert!(rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).is_err());
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_summed_framelet_geometry() {
+    // Summed pixel coordinates are the full resolution coordinates scaled down
+    let summed = jc::JUNO_JUNOCAM_RED.summed(2.0);
+    let v = Vector::new(0.12, 0.01, 1.0);
+    let (x, y) = jc::JUNO_JUNOCAM_RED.vector_to_xy(&v);
+    let (sx, sy) = summed.vector_to_xy(&v);
+    assert!((sx - x / 2.0).abs() < 1e-9);
+    assert!((sy - y / 2.0).abs() < 1e-9);
+
+    let back = summed.xy_to_vector(sx, sy);
+    assert!((back.x / back.z - v.x / v.z).abs() < 1e-6);
+    assert!((back.y / back.z - v.y / v.z).abs() < 1e-6);
+}
+
+#[test]
+fn test_detect_gaps() {
+    // Two triplets with a line lost from the first green strip, and a third cut short 100
+    // lines in
+    let file_path = std::env::temp_dir().join("junocam_test_gaps-raw.png");
+    let raw = image::GrayImage::from_fn(1648, 128 * 6 + 100, |x, y| {
+        image::Luma([if y == 128 + 10 { 0 } else { (x % 7 + 1) as u8 }])
+    });
+    raw.save(&file_path).unwrap();
+
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    assert_eq!(raw_image.get_framelet_set_count(), 3);
+    assert_eq!(raw_image.get_partial_framelet_set_count(), 1);
+    assert!(raw_image.framelet_sets[2].is_partial());
+    assert!(!raw_image.framelet_sets[0].is_partial());
+
+    // The missing end of the last triplet is marked before any detection
+    let report = raw_image.gap_report();
+    assert_eq!(report.gap_lines, 28 + 128 * 2);
+    assert_eq!(report.incomplete_sets, 1);
+
+    let report = raw_image.detect_gaps();
+    assert!(report.has_gaps());
+    assert_eq!(report.strips, 9);
+    assert_eq!(report.strips_with_gaps, 4);
+    assert_eq!(report.gap_lines, 1 + 28 + 128 * 2);
+    assert_eq!(report.sets, 3);
+    assert_eq!(report.incomplete_sets, 2);
+
+    let green = &raw_image.framelet_sets[0].channels[1];
+    assert!(!green.is_line_valid(10));
+    assert!(green.is_line_valid(11));
+    assert!(!green.is_valid_at(9.5));
+    assert!(green.is_valid_at(11.5));
+
+    let blue = &raw_image.framelet_sets[2].channels[0];
+    assert!(blue.is_line_valid(99));
+    assert!(!blue.is_line_valid(100));
+
+    // Padding is trimmed off again when reassembling
+    assert_eq!(raw_image.assemble().unwrap().height, 128 * 6 + 100);
+
+    std::fs::remove_file(&file_path).unwrap();
+}
+
+#[test]
+fn test_dark_lines_are_not_gaps() {
+    // Sky with sparse noise above a bright disc, with an entirely zero line in the sky and
+    // another just above the limb
+    let file_path = std::env::temp_dir().join("junocam_test_dark_lines-raw.png");
+    let raw = image::GrayImage::from_fn(1648, 128 * 3, |x, y| {
+        let y = y % 128;
+        image::Luma([match y {
+            30 | 59 => 0,
+            0..=59 if x % 97 == 0 => 1,
+            0..=59 => 0,
+            _ => (x % 7 + 1) as u8,
+        }])
+    });
+    raw.save(&file_path).unwrap();
+
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    let report = raw_image.detect_gaps();
+    assert!(!report.has_gaps(), "{}", report);
+    assert!(raw_image.framelet_sets[0].channels[0].is_line_valid(30));
+
+    std::fs::remove_file(&file_path).unwrap();
+}
@@ -18,10 +18,10 @@ fn test_load_image_set() {
     .unwrap();
 
     // Split the raw into triplet and verify the count
-    assert_eq!(rs.image.get_triplet_count(), 26);
+    assert_eq!(rs.image.get_framelet_set_count(), 26);
 
     // Make sure the triplet count jives with what we'd
     // predict from the metadata.
-    let expected_triplets = rs.metadata.lines as u32 / (constants::STRIP_HEIGHT as u32 * 3);
-    assert_eq!(rs.image.get_triplet_count() as u32, expected_triplets);
+    let expected_triplets = rs.metadata.lines as usize / (constants::STRIP_HEIGHT * 3);
+    assert_eq!(rs.image.get_framelet_set_count(), expected_triplets);
 }
@@ -0,0 +1,81 @@
+use junocam::{
+    enums::Camera, interpolate::Interpolation, junocam as jc, lens::cylindrical::CylindricalLens,
+    lens::lens::Lens, photometry::PhotometricModel, rawimage, render,
+};
+use sciimg::{matrix::Matrix, prelude::*, quaternion::Quaternion, vector::Vector};
+
+// Red framelet whose samples are one more than their line, and the map it renders to with
+// the spacecraft frame lined up with the camera
+fn render_red_ramp(interpolation: Interpolation) -> (Image, CylindricalLens, usize) {
+    let file_path =
+        std::env::temp_dir().join(format!("junocam_test_backward_{:?}-raw.png", interpolation));
+    let raw = image::GrayImage::from_fn(1648, 128 * 3, |_, y| image::Luma([(y % 128 + 1) as u8]));
+    raw.save(&file_path).unwrap();
+    let mut raw_image = rawimage::RawImage::new_from_image(file_path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&file_path).unwrap();
+    raw_image.set_cameras(&[Camera::RED]).unwrap();
+
+    // Only the first framelet set has geometry, so only it is rendered
+    let geometry = vec![render::FrameletSetGeometry {
+        image_time_et: 0.0,
+        camera_to_j2000: Matrix::identity(),
+        j2000_to_camera: Matrix::identity(),
+        body: None,
+    }];
+
+    let lens = CylindricalLens::new(720, 360, 90.0, -90.0, 0.0, 360.0);
+    let mut map = Image::new_with_bands(720, 360, 1, ImageMode::U16BIT).unwrap();
+    let coverage = render::render_backward(
+        &raw_image,
+        &geometry,
+        &Quaternion::from_pitch_roll_yaw(0.0, 0.0, 0.0),
+        &lens,
+        interpolation,
+        PhotometricModel::None,
+        None,
+        &mut map,
+    );
+    (map, lens, coverage.covered_pixels())
+}
+
+// Line of the red framelet seen through the center of an output pixel
+fn framelet_line(lens: &CylindricalLens, x: usize, y: usize) -> f64 {
+    let v = lens
+        .point_to_vector(x as f64 + 0.5, y as f64 + 0.5)
+        .unwrap();
+    jc::JUNO_JUNOCAM_RED
+        .vector_to_xy(&Vector::new(v.x, v.z, v.y))
+        .1
+}
+
+#[test]
+fn test_backward_render_round_trip() {
+    [Interpolation::Bilinear, Interpolation::Bicubic]
+        .iter()
+        .for_each(|interpolation| {
+            let (map, lens, covered) = render_red_ramp(*interpolation);
+            assert!(covered > 0);
+
+            // Each rendered pixel carries the value of the framelet line it maps back to
+            let mut checked = 0;
+            (0..map.height).for_each(|y| {
+                (0..map.width).for_each(|x| {
+                    let value = map.get(x, y, 0);
+                    if value > 0.0 {
+                        let expected = framelet_line(&lens, x, y) + 1.0;
+                        assert!(
+                            (value as f64 - expected).abs() < 1.0e-3,
+                            "{:?} at {}, {}: {} vs {}",
+                            interpolation,
+                            x,
+                            y,
+                            value,
+                            expected
+                        );
+                        checked += 1;
+                    }
+                });
+            });
+            assert_eq!(checked, covered);
+        });
+}
@@ -24,7 +24,7 @@ fn test_spice_basic() {
 #[test]
 #[ignore = "Lack of cspice on GitHub"]
 fn test_furnish_base() {
-    jcspice::furnish_base();
+    jcspice::furnish_base().unwrap();
     jcspice::furnish("kernels/spk/spk_rec_220728_220909_220913.bsp");
     jcspice::furnish("kernels/ck/juno_sc_rec_220814_220820_v01.bc");
 
@@ -38,3 +38,26 @@ fn test_furnish_base() {
     // let (shape, frame, bsight, n, bounds) = spice::getfov(JUNO_JUNOCAM, 4, 32, 32);
     // println!("Shape: {:?}");
 }
+
+#[test]
+#[ignore = "Lack of cspice on GitHub"]
+fn test_sun_position_matches_subsolar_point() {
+    jcspice::furnish_base().unwrap();
+    jcspice::furnish("kernels/spk/spk_rec_220728_220909_220913.bsp").unwrap();
+
+    let et = spice::str2et("2022-AUG-17 15:13:57");
+    let sun = jcspice::observer_position_body_fixed("SUN", "JUPITER", "IAU_JUPITER", et);
+    let (subsolar, _, _) = spice::subslr(
+        "INTERCEPT/ELLIPSOID",
+        "JUPITER",
+        et,
+        "IAU_JUPITER",
+        "LT+S",
+        "JUNO",
+    );
+
+    // Longitude only differs by Jupiter's turn over the Juno-Jupiter light time
+    let sun_lon = sun.y.atan2(sun.x).to_degrees();
+    let subsolar_lon = subsolar[1].atan2(subsolar[0]).to_degrees();
+    assert!((sun_lon - subsolar_lon).abs() < 0.01);
+}